@@ -1,4 +1,8 @@
-use crate::token::{Keyword, Literal, Operator, Token};
+use crate::{
+    source::SourceError,
+    span::{Position, Span, Spanned},
+    token::{Keyword, Literal, Operator, Token},
+};
 use std::{collections::VecDeque, error::Error, io::BufRead};
 use utf8_chars::BufReadCharsExt;
 
@@ -6,6 +10,48 @@ use utf8_chars::BufReadCharsExt;
 pub enum LexerError {
     IoError(std::io::Error),
     UnexpectedEndOfInput,
+    UnknownOperator {
+        character: char,
+        line: usize,
+        column: usize,
+    },
+    InvalidNumber {
+        literal: String,
+        line: usize,
+        column: usize,
+    },
+    /// A word with a non-ASCII character that can't be part of an
+    /// identifier, such as a symbol, or can't start one, such as a
+    /// combining mark
+    InvalidIdentifier {
+        identifier: String,
+        line: usize,
+        column: usize,
+    },
+    /// A word or string literal longer than `LexerConfig::max_token_length`
+    TokenTooLong {
+        kind: LongTokenKind,
+        limit: usize,
+        line: usize,
+        column: usize,
+    },
+}
+
+/// The kind of token that was cut off by `LexerError::TokenTooLong`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LongTokenKind {
+    /// An identifier, keyword or number
+    Identifier,
+    String,
+}
+
+impl std::fmt::Display for LongTokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LongTokenKind::Identifier => write!(f, "identifier"),
+            LongTokenKind::String => write!(f, "string"),
+        }
+    }
 }
 
 impl std::fmt::Display for LexerError {
@@ -13,12 +59,71 @@ impl std::fmt::Display for LexerError {
         match self {
             LexerError::IoError(err) => write!(f, "IO error: {}", err),
             LexerError::UnexpectedEndOfInput => write!(f, "Unexpected end of input"),
+            LexerError::UnknownOperator {
+                character,
+                line,
+                column,
+            } => write!(f, "Unknown operator `{}` at {}:{}", character, line, column),
+            LexerError::InvalidNumber {
+                literal,
+                line,
+                column,
+            } => write!(f, "Invalid number `{}` at {}:{}", literal, line, column),
+            LexerError::InvalidIdentifier {
+                identifier,
+                line,
+                column,
+            } => write!(
+                f,
+                "Invalid identifier `{}` at {}:{}",
+                identifier, line, column
+            ),
+            LexerError::TokenTooLong {
+                kind,
+                limit,
+                line,
+                column,
+            } => write!(
+                f,
+                "The {} at {}:{} is longer than the limit of {} bytes",
+                kind, line, column, limit
+            ),
         }
     }
 }
 
 impl Error for LexerError {}
 
+impl LexerError {
+    /// The 1-based line the error was found on, if known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            LexerError::UnknownOperator { line, .. }
+            | LexerError::InvalidNumber { line, .. }
+            | LexerError::InvalidIdentifier { line, .. }
+            | LexerError::TokenTooLong { line, .. } => Some(*line),
+            LexerError::IoError(_) | LexerError::UnexpectedEndOfInput => None,
+        }
+    }
+
+    /// The 1-based column the error was found at, if known.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            LexerError::UnknownOperator { column, .. }
+            | LexerError::InvalidNumber { column, .. }
+            | LexerError::InvalidIdentifier { column, .. }
+            | LexerError::TokenTooLong { column, .. } => Some(*column),
+            LexerError::IoError(_) | LexerError::UnexpectedEndOfInput => None,
+        }
+    }
+
+    /// Attaches the offending line of `source`, the input that was lexed.
+    pub fn with_source(self, source: &str) -> SourceError<Self> {
+        let line = self.line();
+        SourceError::new(self, line, source)
+    }
+}
+
 impl From<std::io::Error> for LexerError {
     fn from(err: std::io::Error) -> Self {
         LexerError::IoError(err)
@@ -35,6 +140,69 @@ enum PostProcessingCommand {
     ClearAndSkipPeak,
 }
 
+/// The UTF-8 byte order mark, skipped at the start of the input.
+const BOM: char = '\u{FEFF}';
+
+/// Options controlling how forgiving the lexer is.
+#[derive(Debug, Clone, Default)]
+pub struct LexerConfig {
+    /// Reject operator-like symbols that aren't part of the language (`$`,
+    /// `@`, `` ` ``...) instead of folding them into the current word.
+    pub strict_operators: bool,
+    /// The longest identifier or string literal accepted, in bytes. Longer
+    /// ones fail with `LexerError::TokenTooLong` rather than being buffered
+    /// in full. `None` means no limit.
+    pub max_token_length: Option<usize>,
+    /// Lex `inf` and `nan` as float number literals where a value is
+    /// expected: right after an operator, `(`, `[`, `,` or `return`. Anywhere
+    /// else, such as at the start of a statement or after a `.`, they stay
+    /// identifiers, so `inf := 1` still declares a variable. `-inf` is a
+    /// negated `inf`.
+    pub float_specials: bool,
+    /// Count columns in UTF-16 code units, as the language server protocol
+    /// does, rather than in chars. Offsets are always in bytes.
+    pub utf16_columns: bool,
+    /// Which tokens end a statement when a line ends right after them
+    pub newlines: NewlineRules,
+}
+
+/// The tokens after which a line break ends the statement, so the lexer
+/// emits a `Newline`. After any other token, such as an operator or `,`,
+/// the statement carries on to the next line. The default is every rule.
+#[derive(Debug, Clone)]
+pub struct NewlineRules {
+    /// `)`, `]` and `}`
+    pub after_closing: bool,
+    /// Identifiers and `self`
+    pub after_identifier: bool,
+    pub after_literal: bool,
+    /// A bare `return`
+    pub after_return: bool,
+    /// The `?` of `f()?`
+    pub after_question: bool,
+}
+
+impl Default for NewlineRules {
+    fn default() -> Self {
+        Self {
+            after_closing: true,
+            after_identifier: true,
+            after_literal: true,
+            after_return: true,
+            after_question: true,
+        }
+    }
+}
+
+/// A `//` comment. Comments never produce tokens; they are collected on the
+/// side so tools like the formatter can put them back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comment {
+    /// The comment text including the leading `//`
+    pub text: String,
+    pub span: Span,
+}
+
 pub struct Lexer<R: BufRead> {
     reader: R,
 }
@@ -42,76 +210,238 @@ pub struct Lexer<R: BufRead> {
 struct LexerContext {
     current_word: String,
     tokens: Vec<Token>,
+    spans: Vec<Span>,
     state: VecDeque<LexerState>,
+    config: LexerConfig,
+    /// Position of the character being processed
+    position: Position,
+    /// Position where `current_word` started
+    word_start: Position,
+    comments: Vec<Comment>,
+    /// Text and start of the comment being read
+    comment: String,
+    comment_start: Position,
+    /// Lines of the currently open parentheses
+    open_parens: Vec<usize>,
+    /// Whether the last `)` closed a parenthesis opened on an earlier line
+    closed_multiline_paren: bool,
+    /// A statement-ending newline held back until the next token shows
+    /// whether the statement continues
+    pending_newline: Option<Position>,
 }
 
 impl<R: BufRead> Lexer<R> {
     pub fn lex(reader: R) -> Result<Vec<Token>, LexerError> {
+        Self::lex_with_config(reader, LexerConfig::default())
+    }
+
+    pub fn lex_with_config(reader: R, config: LexerConfig) -> Result<Vec<Token>, LexerError> {
+        let (tokens, _) = Self::lex_with_spans(reader, config)?;
+        Ok(tokens)
+    }
+
+    /// Lexes the input, also returning the source span of every token.
+    /// `spans[i]` is the span of `tokens[i]`.
+    pub fn lex_with_spans(
+        reader: R,
+        config: LexerConfig,
+    ) -> Result<(Vec<Token>, Vec<Span>), LexerError> {
+        let (tokens, spans, _) = Self::lex_with_comments(reader, config)?;
+        Ok((tokens, spans))
+    }
+
+    /// Lexes the input into tokens that each carry their source span.
+    pub fn lex_spanned(reader: R, config: LexerConfig) -> Result<Vec<Spanned<Token>>, LexerError> {
+        let (tokens, spans) = Self::lex_with_spans(reader, config)?;
+        Ok(tokens
+            .into_iter()
+            .zip(spans)
+            .map(|(token, span)| Spanned::new(token, span))
+            .collect())
+    }
+
+    /// Like `lex_with_spans`, but also returns the comments in source order.
+    #[allow(clippy::type_complexity)]
+    pub fn lex_with_comments(
+        reader: R,
+        config: LexerConfig,
+    ) -> Result<(Vec<Token>, Vec<Span>, Vec<Comment>), LexerError> {
+        let start = Position::new(0, 1, 1);
         let context = LexerContext {
             current_word: String::new(),
             tokens: Vec::new(),
+            spans: Vec::new(),
             state: VecDeque::new(),
+            config,
+            position: start,
+            word_start: start,
+            comments: Vec::new(),
+            comment: String::new(),
+            comment_start: start,
+            open_parens: Vec::new(),
+            closed_multiline_paren: false,
+            pending_newline: None,
         };
         let mut lexer = Self { reader };
         lexer.run(context)
     }
 
-    fn run(&mut self, mut context: LexerContext) -> Result<Vec<Token>, LexerError> {
+    #[allow(clippy::type_complexity)]
+    fn run(
+        &mut self,
+        mut context: LexerContext,
+    ) -> Result<(Vec<Token>, Vec<Span>, Vec<Comment>), LexerError> {
         let mut chars = self.reader.chars().peekable();
+        // A leading byte order mark isn't part of the source, so lines and
+        // columns start after it, but offsets still count its bytes so spans
+        // slice the input
+        if let Some(Ok(BOM)) = chars.peek() {
+            chars.next();
+            context.position.offset += BOM.len_utf8();
+        }
 
         while let Some(Ok(c)) = chars.next() {
+            if context.current_word.is_empty()
+                && !matches!(context.state.back(), Some(LexerState::InString))
+            {
+                context.word_start = context.position;
+            }
             let peak = chars.peek().and_then(|r| r.as_ref().ok()).copied();
-            let Some(cmd) = Self::process_byte(&mut context, c, peak) else {
+            let cmd = Self::process_byte(&mut context, c, peak)?;
+            Self::advance_position(&mut context, c);
+            let Some(cmd) = cmd else {
                 continue;
             };
 
             context.current_word.clear();
-            if matches!(cmd, PostProcessingCommand::ClearAndSkipPeak) {
-                chars.next();
+            if matches!(cmd, PostProcessingCommand::ClearAndSkipPeak)
+                && let Some(Ok(skipped)) = chars.next()
+            {
+                Self::advance_position(&mut context, skipped);
             }
         }
-        // Commit any remaining content at the end of input
+        // Commit any remaining content and mark the end of input
         match context.state.back() {
-            Some(LexerState::InString) => Err(LexerError::UnexpectedEndOfInput),
-            _ => {
-                Self::commit_word(&mut context);
-                Ok(context.tokens)
-            }
+            Some(LexerState::InString) => return Err(LexerError::UnexpectedEndOfInput),
+            Some(LexerState::InComment) => Self::finish_comment(&mut context),
+            None => Self::commit_word(&mut context)?,
         }
+        Self::flush_pending_newline(&mut context);
+        Self::push_at_current(&mut context, Token::Eof, 0);
+        Ok((context.tokens, context.spans, context.comments))
+    }
+
+    /// A byte order mark after the start of the input separates tokens like
+    /// whitespace.
+    fn is_whitespace(c: char) -> bool {
+        c.is_whitespace() || c == BOM
+    }
+
+    fn advance_position(context: &mut LexerContext, c: char) {
+        context.position.offset += c.len_utf8();
+        if c == '\n' {
+            context.position.line += 1;
+            context.position.column = 1;
+        } else if context.config.utf16_columns {
+            context.position.column += c.len_utf16();
+        } else {
+            context.position.column += 1;
+        }
+    }
+
+    /// Pushes a token that starts at the current character and spans `width`
+    /// ASCII characters on the same line.
+    fn push_at_current(context: &mut LexerContext, token: Token, width: usize) {
+        let start = context.position;
+        let end = Position::new(start.offset + width, start.line, start.column + width);
+        context.tokens.push(token);
+        context.spans.push(Span::new(start, end));
     }
 
     fn process_byte(
         context: &mut LexerContext,
         c: char,
         peak: Option<char>,
-    ) -> Option<PostProcessingCommand> {
+    ) -> Result<Option<PostProcessingCommand>, LexerError> {
         match context.state.back() {
             Some(LexerState::InString) => Self::process_string(context, c),
             Some(LexerState::InComment) => {
                 if c == '\n' {
+                    Self::finish_comment(context);
                     context.state.pop_back();
-                    if Self::should_insert_newline(&context.tokens) {
-                        context.tokens.push(Token::Newline);
-                    }
-                    Some(PostProcessingCommand::Clear)
+                    Self::end_line(context);
+                    Ok(Some(PostProcessingCommand::Clear))
                 } else {
-                    None
+                    context.comment.push(c);
+                    Ok(None)
                 }
             }
             None => Self::process_normal(context, c, peak),
         }
     }
 
-    fn process_string(context: &mut LexerContext, c: char) -> Option<PostProcessingCommand> {
+    /// Whether a line starting with `c` continues the previous line's
+    /// expression, i.e. starts with `.` or a binary operator.
+    fn continues_expression(c: char, peak: Option<char>) -> bool {
+        match c {
+            '/' => peak != Some('/'),
+            '.' | '+' | '-' | '*' | '%' | '^' | '&' | '|' | '<' | '>' => true,
+            '=' | '!' => peak == Some('='),
+            _ => false,
+        }
+    }
+
+    /// Ends a line at the current `\n`, emitting a `Newline` if the last
+    /// token ends a statement.
+    fn end_line(context: &mut LexerContext) {
+        if !Self::should_insert_newline(context) {
+            return;
+        }
+        if context.tokens.last() == Some(&Token::CloseParen) && context.closed_multiline_paren {
+            // A call split across lines may be followed by a method chain or
+            // operator on the next line
+            context.pending_newline.get_or_insert(context.position);
+        } else {
+            Self::push_at_current(context, Token::Newline, 1);
+        }
+    }
+
+    fn flush_pending_newline(context: &mut LexerContext) {
+        if let Some(start) = context.pending_newline.take() {
+            let end = Position::new(start.offset + 1, start.line, start.column + 1);
+            context.tokens.push(Token::Newline);
+            context.spans.push(Span::new(start, end));
+        }
+    }
+
+    /// Ends the comment being read right before the current character.
+    fn finish_comment(context: &mut LexerContext) {
+        let text = context.comment.trim_end().to_string();
+        context.comments.push(Comment {
+            text,
+            span: Span::new(context.comment_start, context.position),
+        });
+        context.comment.clear();
+    }
+
+    fn process_string(
+        context: &mut LexerContext,
+        c: char,
+    ) -> Result<Option<PostProcessingCommand>, LexerError> {
         if c == '"' && !context.current_word.ends_with('\\') {
             context.state.pop_back();
+            // The span covers both quotes
+            let mut end = context.position;
+            end.offset += 1;
+            end.column += 1;
             context.tokens.push(Token::Literal(Literal::String(
                 context.current_word.clone(),
             )));
-            Some(PostProcessingCommand::Clear)
+            context.spans.push(Span::new(context.word_start, end));
+            Ok(Some(PostProcessingCommand::Clear))
         } else {
-            context.current_word.push(c);
-            None
+            Self::push_char(context, c, LongTokenKind::String)?;
+            Ok(None)
         }
     }
 
@@ -119,30 +449,50 @@ impl<R: BufRead> Lexer<R> {
         context: &mut LexerContext,
         c: char,
         peak: Option<char>,
-    ) -> Option<PostProcessingCommand> {
+    ) -> Result<Option<PostProcessingCommand>, LexerError> {
+        // A comment leaves the line break pending for the line after it
+        let starts_comment = c == '/' && peak == Some('/');
+        if !Self::is_whitespace(c) && !starts_comment && context.pending_newline.is_some() {
+            if Self::continues_expression(c, peak) {
+                context.pending_newline = None;
+            } else {
+                Self::flush_pending_newline(context);
+            }
+        }
+
+        match c {
+            '(' => context.open_parens.push(context.position.line),
+            ')' => {
+                let opened = context.open_parens.pop();
+                context.closed_multiline_paren =
+                    opened.is_some_and(|line| line != context.position.line);
+            }
+            _ => {}
+        }
+
         match c {
             '\n' => {
-                Self::commit_word(context);
-                if Self::should_insert_newline(&context.tokens) {
-                    context.tokens.push(Token::Newline);
-                }
-                Some(PostProcessingCommand::Clear)
+                Self::commit_word(context)?;
+                Self::end_line(context);
+                Ok(Some(PostProcessingCommand::Clear))
             }
             '"' => {
                 context.state.push_back(LexerState::InString);
-                None
+                Ok(None)
             }
             '/' if peak == Some('/') => {
                 context.state.push_back(LexerState::InComment);
-                Self::commit_word(context);
-                None
+                context.comment = String::from("/");
+                context.comment_start = context.position;
+                Self::commit_word(context)?;
+                Ok(None)
             }
-            c if c.is_whitespace() => {
+            c if Self::is_whitespace(c) => {
                 if !context.current_word.is_empty() {
-                    Self::commit_word(context);
-                    Some(PostProcessingCommand::Clear)
+                    Self::commit_word(context)?;
+                    Ok(Some(PostProcessingCommand::Clear))
                 } else {
-                    None
+                    Ok(None)
                 }
             }
             c => Self::process_operator_or_char(context, c, peak),
@@ -153,60 +503,181 @@ impl<R: BufRead> Lexer<R> {
         context: &mut LexerContext,
         c: char,
         peak: Option<char>,
-    ) -> Option<PostProcessingCommand> {
-        if let Ok(token) = Token::try_from(c) {
-            Self::commit_and_push(context, token);
-            return Some(PostProcessingCommand::Clear);
+    ) -> Result<Option<PostProcessingCommand>, LexerError> {
+        if Self::continues_number(&context.current_word, c, peak) {
+            Self::push_char(context, c, LongTokenKind::Identifier)?;
+            return Ok(None);
         }
 
+        // Two-char operators come first so `..` wins over `Dot`
         let two_char = format!("{}{}", c, peak.unwrap_or(' '));
         if let Ok(op) = Operator::try_from(two_char.as_str()) {
-            Self::commit_and_push(context, Token::Operator(op));
-            return Some(PostProcessingCommand::ClearAndSkipPeak);
+            Self::commit_and_push(context, Token::Operator(op), 2)?;
+            return Ok(Some(PostProcessingCommand::ClearAndSkipPeak));
+        }
+
+        if let Ok(token) = Token::try_from(c) {
+            Self::commit_and_push(context, token, 1)?;
+            return Ok(Some(PostProcessingCommand::Clear));
         }
 
         if let Ok(op) = Operator::try_from(c.to_string().as_str()) {
-            Self::commit_and_push(context, Token::Operator(op));
-            return Some(PostProcessingCommand::Clear);
+            Self::commit_and_push(context, Token::Operator(op), 1)?;
+            return Ok(Some(PostProcessingCommand::Clear));
         }
 
+        // Every known symbol has been handled above, so any other ASCII
+        // punctuation is an operator the language doesn't have.
+        if context.config.strict_operators && c.is_ascii_punctuation() && c != '_' {
+            return Err(LexerError::UnknownOperator {
+                character: c,
+                line: context.position.line,
+                column: context.position.column,
+            });
+        }
+
+        Self::push_char(context, c, LongTokenKind::Identifier)?;
+        Ok(None)
+    }
+
+    /// Appends `c` to `current_word`, failing if that makes it longer than
+    /// `max_token_length`.
+    fn push_char(
+        context: &mut LexerContext,
+        c: char,
+        kind: LongTokenKind,
+    ) -> Result<(), LexerError> {
+        if let Some(limit) = context.config.max_token_length
+            && context.current_word.len() + c.len_utf8() > limit
+        {
+            return Err(LexerError::TokenTooLong {
+                kind,
+                limit,
+                line: context.word_start.line,
+                column: context.word_start.column,
+            });
+        }
         context.current_word.push(c);
-        None
+        Ok(())
     }
 
-    fn commit_and_push(context: &mut LexerContext, token: Token) {
-        Self::commit_word(context);
-        context.tokens.push(token);
+    /// Decimal points and exponent signs are part of a number literal
+    /// rather than `Dot`/`Plus`/`Minus` tokens, e.g. `1.5e-3`.
+    fn continues_number(word: &str, c: char, peak: Option<char>) -> bool {
+        if !word.starts_with(|c: char| c.is_ascii_digit()) {
+            return false;
+        }
+        let is_radix = ["0x", "0b", "0o"].iter().any(|p| word.starts_with(p));
+        let has_exponent = word.contains(['e', 'E']);
+        match c {
+            '.' => {
+                !is_radix
+                    && !has_exponent
+                    && !word.contains('.')
+                    && peak.is_some_and(|p| p.is_ascii_digit())
+            }
+            '+' | '-' => !is_radix && word.ends_with(['e', 'E']),
+            _ => false,
+        }
+    }
+
+    fn commit_and_push(
+        context: &mut LexerContext,
+        token: Token,
+        width: usize,
+    ) -> Result<(), LexerError> {
+        Self::commit_word(context)?;
+        Self::push_at_current(context, token, width);
+        Ok(())
     }
 
-    fn commit_word(context: &mut LexerContext) {
+    fn commit_word(context: &mut LexerContext) -> Result<(), LexerError> {
         if context.current_word.is_empty() {
-            return;
+            return Ok(());
         }
 
         let token = if let Ok(keyword) = Keyword::try_from(context.current_word.as_str()) {
             Token::Keyword(keyword)
         } else if let Ok(literal) = Literal::try_from(context.current_word.as_str()) {
             Token::Literal(literal)
+        } else if context.config.float_specials
+            && Self::expects_value(&context.tokens)
+            && let Some(literal) = Literal::float_special(&context.current_word)
+        {
+            Token::Literal(literal)
+        } else if context
+            .current_word
+            .starts_with(|c: char| c.is_ascii_digit())
+        {
+            // Identifiers can't start with a digit, so this is a malformed
+            // number such as `0x` or `0b102`
+            return Err(LexerError::InvalidNumber {
+                literal: context.current_word.clone(),
+                line: context.word_start.line,
+                column: context.word_start.column,
+            });
+        } else if !Self::is_identifier(&context.current_word) {
+            return Err(LexerError::InvalidIdentifier {
+                identifier: context.current_word.clone(),
+                line: context.word_start.line,
+                column: context.word_start.column,
+            });
         } else {
             Token::Identifier(context.current_word.clone())
         };
+        // Words end right before the character being processed
         context.tokens.push(token);
+        context
+            .spans
+            .push(Span::new(context.word_start, context.position));
         context.current_word.clear();
+        Ok(())
     }
 
-    fn should_insert_newline(tokens: &[Token]) -> bool {
-        tokens.last().is_some_and(|last_token| {
-            matches!(
-                last_token,
-                Token::CloseBrace
-                    | Token::CloseBracket
-                    | Token::CloseParen
-                    | Token::Identifier(_)
-                    | Token::Literal(_)
-            )
+    /// Non-ASCII characters follow the Unicode identifier rules, so `café`
+    /// and `π` are identifiers but a word starting with a combining mark or
+    /// containing a symbol is not. ASCII characters are left as they were
+    /// lexed, since `LexerConfig::strict_operators` decides which of them
+    /// may appear in a word.
+    fn is_identifier(word: &str) -> bool {
+        word.chars().enumerate().all(|(i, c)| {
+            c.is_ascii()
+                || if i == 0 {
+                    unicode_ident::is_xid_start(c)
+                } else {
+                    unicode_ident::is_xid_continue(c)
+                }
         })
     }
+
+    /// Whether the next word is an operand, for `LexerConfig::float_specials`
+    fn expects_value(tokens: &[Token]) -> bool {
+        matches!(
+            tokens.last(),
+            Some(
+                Token::Operator(_)
+                    | Token::OpenParen
+                    | Token::OpenBracket
+                    | Token::Comma
+                    | Token::Keyword(Keyword::Return)
+            )
+        )
+    }
+
+    fn should_insert_newline(context: &LexerContext) -> bool {
+        let rules = &context.config.newlines;
+        context
+            .tokens
+            .last()
+            .is_some_and(|last_token| match last_token {
+                Token::CloseBrace | Token::CloseBracket | Token::CloseParen => rules.after_closing,
+                Token::Identifier(_) | Token::Keyword(Keyword::SelfKw) => rules.after_identifier,
+                Token::Literal(_) => rules.after_literal,
+                Token::Keyword(Keyword::Return) => rules.after_return,
+                Token::Operator(Operator::Question) => rules.after_question,
+                _ => false,
+            })
+    }
 }
 
 #[cfg(test)]
@@ -217,10 +688,28 @@ mod tests {
 
     use super::*;
 
+    /// Checks that `tokens` end with a `Token::Eof` and removes it, so
+    /// expected tokens can leave it out.
+    fn without_eof(tokens: Vec<Token>) -> Vec<Token> {
+        match tokens.split_last() {
+            Some((Token::Eof, rest)) => rest.to_vec(),
+            _ => panic!("Expected tokens to end with Eof, got {:?}", tokens),
+        }
+    }
+
     fn lex_string(input: &str) -> Result<Vec<Token>, LexerError> {
         let string_reader = StringReader::new(input);
         let reader = BufReader::new(string_reader);
-        Lexer::lex(reader)
+        Lexer::lex(reader).map(without_eof)
+    }
+
+    fn lex_strict(input: &str) -> Result<Vec<Token>, LexerError> {
+        let string_reader = StringReader::new(input);
+        let config = LexerConfig {
+            strict_operators: true,
+            ..LexerConfig::default()
+        };
+        Lexer::lex_with_config(BufReader::new(string_reader), config).map(without_eof)
     }
 
     #[test]
@@ -258,6 +747,102 @@ mod tests {
         assert_eq!(tokens, expected_tokens);
     }
 
+    #[test]
+    fn test_token_spans() {
+        let input = "module Main\nx := \"hi\" + 10";
+        let reader = BufReader::new(StringReader::new(input));
+        let (tokens, spans) = Lexer::lex_with_spans(reader, LexerConfig::default()).unwrap();
+
+        let span = |start: (usize, usize, usize), end: (usize, usize, usize)| {
+            Span::new(
+                Position::new(start.0, start.1, start.2),
+                Position::new(end.0, end.1, end.2),
+            )
+        };
+
+        assert_eq!(tokens.len(), spans.len());
+        assert_eq!(tokens[0], Token::Keyword(Keyword::Module));
+        assert_eq!(spans[0], span((0, 1, 1), (6, 1, 7)));
+        assert_eq!(spans[1], span((7, 1, 8), (11, 1, 12)));
+        assert_eq!(tokens[2], Token::Newline);
+        assert_eq!(spans[2], span((11, 1, 12), (12, 1, 13)));
+        assert_eq!(tokens[3], Token::Identifier("x".to_string()));
+        assert_eq!(spans[3], span((12, 2, 1), (13, 2, 2)));
+        assert_eq!(tokens[4], Token::Operator(Operator::Walrus));
+        assert_eq!(spans[4], span((14, 2, 3), (16, 2, 5)));
+        assert_eq!(tokens[5], Token::Literal(Literal::String("hi".to_string())));
+        assert_eq!(spans[5], span((17, 2, 6), (21, 2, 10)));
+        assert_eq!(spans[6], span((22, 2, 11), (23, 2, 12)));
+        assert_eq!(spans[7], span((24, 2, 13), (26, 2, 15)));
+    }
+
+    const MULTI_BYTE: &str = "s := \"é😀\" + x";
+
+    #[test]
+    fn test_spans_after_multi_byte_chars() {
+        let reader = BufReader::new(StringReader::new(MULTI_BYTE));
+        let (tokens, spans) = Lexer::lex_with_spans(reader, LexerConfig::default()).unwrap();
+
+        assert_eq!(
+            tokens[2],
+            Token::Literal(Literal::String("é😀".to_string()))
+        );
+        // Offsets are in bytes, so they slice the source
+        let text = |span: &Span| &MULTI_BYTE[span.start.offset..span.end.offset];
+        assert_eq!(text(&spans[2]), "\"é😀\"");
+        assert_eq!(text(&spans[3]), "+");
+        assert_eq!(text(&spans[4]), "x");
+        // Columns are in chars
+        assert_eq!(spans[2].end, Position::new(13, 1, 10));
+        assert_eq!(spans[3].start, Position::new(14, 1, 11));
+        assert_eq!(spans[4].start, Position::new(16, 1, 13));
+    }
+
+    #[test]
+    fn test_utf16_columns() {
+        let config = LexerConfig {
+            utf16_columns: true,
+            ..LexerConfig::default()
+        };
+        let reader = BufReader::new(StringReader::new(MULTI_BYTE));
+        let (_, spans) = Lexer::lex_with_spans(reader, config).unwrap();
+
+        // `😀` is two UTF-16 code units; offsets stay in bytes
+        assert_eq!(spans[2].end, Position::new(13, 1, 11));
+        assert_eq!(spans[3].start, Position::new(14, 1, 12));
+        assert_eq!(spans[4].start, Position::new(16, 1, 14));
+    }
+
+    #[test]
+    fn test_lex_spanned() {
+        let reader = BufReader::new(StringReader::new("module Main\n"));
+        let tokens = Lexer::lex_spanned(reader, LexerConfig::default()).unwrap();
+
+        assert_eq!(tokens.len(), 4);
+        assert_eq!(
+            tokens[1],
+            Spanned::new(
+                Token::Identifier("Main".to_string()),
+                Span::new(Position::new(7, 1, 8), Position::new(11, 1, 12))
+            )
+        );
+        // Matching goes through `Deref` to the token
+        assert!(matches!(*tokens[0], Token::Keyword(Keyword::Module)));
+        assert!(tokens[2].matches(&Token::Newline));
+        assert_eq!(tokens[2].clone().into_inner(), Token::Newline);
+    }
+
+    #[test]
+    fn test_spanned_deref() {
+        let spanned = Spanned::new(Token::Operator(Operator::Plus), Span::default());
+
+        match &*spanned {
+            Token::Operator(operator) => assert!(operator.is_arithmetic()),
+            token => panic!("Expected operator, got {:?}", token),
+        }
+        assert_eq!(spanned.to_string(), "+");
+    }
+
     #[test]
     fn test_empty_input() {
         let tokens = lex_string("").unwrap();
@@ -270,6 +855,27 @@ mod tests {
         assert_eq!(tokens, vec![]);
     }
 
+    #[test]
+    fn test_eof_ends_every_input() {
+        for input in ["", "x := 1\n", "x // comment", "x\n\n"] {
+            let reader = BufReader::new(StringReader::new(input));
+            let tokens = Lexer::lex(reader).unwrap();
+            assert_eq!(tokens.last(), Some(&Token::Eof), "input {:?}", input);
+            assert_eq!(tokens.iter().filter(|t| **t == Token::Eof).count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_eof_span_is_empty_at_end_of_input() {
+        let input = "x := 1\ny";
+        let reader = BufReader::new(StringReader::new(input));
+        let (tokens, spans) = Lexer::lex_with_spans(reader, LexerConfig::default()).unwrap();
+
+        assert_eq!(tokens.last(), Some(&Token::Eof));
+        let end = Position::new(input.len(), 2, 2);
+        assert_eq!(spans.last(), Some(&Span::new(end, end)));
+    }
+
     #[test]
     fn test_keywords() {
         let input = "module import fn if else return";
@@ -287,6 +893,30 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_keywords_never_lex_as_identifiers() {
+        for keyword in Keyword::all() {
+            let tokens = lex_string(&keyword.to_string()).unwrap();
+            assert_eq!(tokens, vec![Token::Keyword(keyword.clone())]);
+        }
+    }
+
+    #[test]
+    fn test_self_keyword() {
+        let input = "self.x\nself";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Keyword(Keyword::SelfKw),
+            Token::Dot,
+            Token::Identifier("x".to_string()),
+            Token::Newline,
+            Token::Keyword(Keyword::SelfKw),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
     #[test]
     fn test_identifiers() {
         let input = "variable myFunc _private camelCase snake_case CONSTANT";
@@ -344,6 +974,61 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_colon_and_walrus() {
+        let colon = Token::Operator(Operator::Colon);
+        let walrus = Token::Operator(Operator::Walrus);
+        let ident = |name: &str| Token::Identifier(name.to_string());
+
+        assert_eq!(lex_string("x := 5").unwrap()[1], walrus);
+        assert_eq!(lex_string("x:=5").unwrap()[1], walrus);
+        assert_eq!(
+            lex_string("x :i32 = 5").unwrap()[..4],
+            [
+                ident("x"),
+                colon.clone(),
+                ident("i32"),
+                Token::Operator(Operator::Reassign)
+            ]
+        );
+        // With a space in between, `: =` is a colon followed by `=`
+        assert_eq!(lex_string("x : = 5").unwrap()[1], colon);
+    }
+
+    #[test]
+    fn test_path_separator() {
+        let ident = |name: &str| Token::Identifier(name.to_string());
+        let op = Token::Operator;
+
+        assert_eq!(
+            lex_string("a::b").unwrap(),
+            vec![ident("a"), op(Operator::PathSep), ident("b")]
+        );
+        assert_eq!(
+            lex_string("a:b").unwrap(),
+            vec![ident("a"), op(Operator::Colon), ident("b")]
+        );
+        assert_eq!(
+            lex_string("a:=b").unwrap(),
+            vec![ident("a"), op(Operator::Walrus), ident("b")]
+        );
+        assert_eq!(
+            lex_string("std::io::println").unwrap(),
+            vec![
+                ident("std"),
+                op(Operator::PathSep),
+                ident("io"),
+                op(Operator::PathSep),
+                ident("println")
+            ]
+        );
+        // `:::` is a path separator, then a colon
+        assert_eq!(
+            lex_string("a:::b").unwrap()[1..3],
+            [op(Operator::PathSep), op(Operator::Colon)]
+        );
+    }
+
     #[test]
     fn test_punctuation() {
         let input = "(){}[],.";
@@ -444,8 +1129,7 @@ mod tests {
 
         let expected = vec![
             Token::Identifier("x".to_string()),
-            Token::Operator(Operator::Assign),
-            Token::Operator(Operator::Reassign),
+            Token::Operator(Operator::Walrus),
             Token::Literal(Literal::Number("5".to_string())),
             Token::Operator(Operator::Plus),
             Token::Literal(Literal::Number("3".to_string())),
@@ -490,6 +1174,118 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_const_fn_signature() {
+        let input = "const fn square(x i32) -> i32";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Keyword(Keyword::Const),
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("square".to_string()),
+            Token::OpenParen,
+            Token::Identifier("x".to_string()),
+            Token::Identifier("i32".to_string()),
+            Token::CloseParen,
+            Token::Operator(Operator::Arrow),
+            Token::Identifier("i32".to_string()),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_leading_bom_is_skipped() {
+        let input = "\u{FEFF}module Main\n";
+        let reader = StringReader::new(input);
+        let (tokens, spans) =
+            Lexer::lex_with_spans(BufReader::new(reader), LexerConfig::default()).unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Keyword(Keyword::Module),
+                Token::Identifier("Main".to_string()),
+                Token::Newline,
+                Token::Eof,
+            ]
+        );
+        assert_eq!(spans[0].start, Position::new(3, 1, 1));
+        let name = spans[1];
+        assert_eq!(&input[name.start.offset..name.end.offset], "Main");
+    }
+
+    #[test]
+    fn test_bom_mid_file_is_whitespace() {
+        let input = "a\u{FEFF}b";
+        let tokens = lex_string(input).unwrap();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::Identifier("b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrow_operators() {
+        let input = "a => b <- c -> d";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::Operator(Operator::FatArrow),
+            Token::Identifier("b".to_string()),
+            Token::Operator(Operator::LeftArrow),
+            Token::Identifier("c".to_string()),
+            Token::Operator(Operator::Arrow),
+            Token::Identifier("d".to_string()),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_arrows_without_spaces() {
+        let input = "a<-b=>c";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::Operator(Operator::LeftArrow),
+            Token::Identifier("b".to_string()),
+            Token::Operator(Operator::FatArrow),
+            Token::Identifier("c".to_string()),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_operators_next_to_arrows() {
+        let input = "a < -b = c >= d <= e > f";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::Operator(Operator::Less),
+            Token::Operator(Operator::Minus),
+            Token::Identifier("b".to_string()),
+            Token::Operator(Operator::Reassign),
+            Token::Identifier("c".to_string()),
+            Token::Operator(Operator::GreaterEqual),
+            Token::Identifier("d".to_string()),
+            Token::Operator(Operator::LessEqual),
+            Token::Identifier("e".to_string()),
+            Token::Operator(Operator::Greater),
+            Token::Identifier("f".to_string()),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
     #[test]
     fn test_pipeline_operator() {
         let input = "data |> transform |> filter";
@@ -506,6 +1302,25 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_newline_after_try_operator() {
+        let input = "x := parse()?\ny";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Identifier("x".to_string()),
+            Token::Operator(Operator::Walrus),
+            Token::Identifier("parse".to_string()),
+            Token::OpenParen,
+            Token::CloseParen,
+            Token::Operator(Operator::Question),
+            Token::Newline,
+            Token::Identifier("y".to_string()),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
     #[test]
     fn test_array_access() {
         let input = "arr[0] = arr[i + 1]";
@@ -528,6 +1343,31 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_range_operator() {
+        let input = "arr[1..3] arr[..n] 1.5..2";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Identifier("arr".to_string()),
+            Token::OpenBracket,
+            Token::Literal(Literal::Number("1".to_string())),
+            Token::Operator(Operator::Range),
+            Token::Literal(Literal::Number("3".to_string())),
+            Token::CloseBracket,
+            Token::Identifier("arr".to_string()),
+            Token::OpenBracket,
+            Token::Operator(Operator::Range),
+            Token::Identifier("n".to_string()),
+            Token::CloseBracket,
+            Token::Literal(Literal::Number("1.5".to_string())),
+            Token::Operator(Operator::Range),
+            Token::Literal(Literal::Number("2".to_string())),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
     #[test]
     fn test_method_chaining() {
         let input = "obj.method1().method2().property";
@@ -843,13 +1683,9 @@ mod tests {
         let input = "123 3.14 0xFF 1e10";
         let tokens = lex_string(input).unwrap();
 
-        // Note: The lexer currently treats all of these as identifiers
-        // In a real implementation, these would be parsed as numeric literals
         let expected = vec![
             Token::Literal(Literal::Number("123".to_string())),
-            Token::Literal(Literal::Number("3".to_string())),
-            Token::Dot,
-            Token::Literal(Literal::Number("14".to_string())),
+            Token::Literal(Literal::Number("3.14".to_string())),
             Token::Literal(Literal::Number("0xFF".to_string())),
             Token::Literal(Literal::Number("1e10".to_string())),
         ];
@@ -857,6 +1693,42 @@ mod tests {
         assert_eq!(tokens, expected);
     }
 
+    #[test]
+    fn test_numeric_literal_spelling_preserved() {
+        let input = "1_000 1.5e-3 2E+8 0b1010 0xff_ff";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Literal(Literal::Number("1_000".to_string())),
+            Token::Literal(Literal::Number("1.5e-3".to_string())),
+            Token::Literal(Literal::Number("2E+8".to_string())),
+            Token::Literal(Literal::Number("0b1010".to_string())),
+            Token::Literal(Literal::Number("0xff_ff".to_string())),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_number_followed_by_operators() {
+        let input = "x.y 1-2 0x1e-3";
+        let tokens = lex_string(input).unwrap();
+
+        let expected = vec![
+            Token::Identifier("x".to_string()),
+            Token::Dot,
+            Token::Identifier("y".to_string()),
+            Token::Literal(Literal::Number("1".to_string())),
+            Token::Operator(Operator::Minus),
+            Token::Literal(Literal::Number("2".to_string())),
+            Token::Literal(Literal::Number("0x1e".to_string())),
+            Token::Operator(Operator::Minus),
+            Token::Literal(Literal::Number("3".to_string())),
+        ];
+
+        assert_eq!(tokens, expected);
+    }
+
     #[test]
     fn test_multiple_unclosed_strings() {
         // Test with properly closed first string and unclosed second string
@@ -909,6 +1781,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tilde_operator() {
+        let input = "a := ~x + 1";
+        let expected = vec![
+            Token::Identifier("a".to_string()),
+            Token::Operator(Operator::Walrus),
+            Token::Operator(Operator::Tilde),
+            Token::Identifier("x".to_string()),
+            Token::Operator(Operator::Plus),
+            Token::Literal(Literal::Number("1".to_string())),
+        ];
+
+        assert_eq!(lex_string(input).unwrap(), expected);
+        assert_eq!(lex_strict(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unknown_operator_lenient() {
+        let tokens = lex_string("$x").unwrap();
+
+        let expected = vec![Token::Identifier("$x".to_string())];
+
+        assert_eq!(tokens, expected);
+    }
+
+    #[test]
+    fn test_unknown_operator_strict() {
+        let result = lex_strict("$x");
+
+        match result {
+            Err(LexerError::UnknownOperator {
+                character: '$',
+                line: 1,
+                column: 1,
+            }) => (),
+            _ => panic!("Expected UnknownOperator error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_unknown_operator_strict_position() {
+        let result = lex_strict("a := 1\nb := a $ 2");
+
+        match result {
+            Err(LexerError::UnknownOperator {
+                character: '$',
+                line: 2,
+                column: 8,
+            }) => (),
+            _ => panic!("Expected UnknownOperator error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_error_with_source_line() {
+        let input = "a := 1\nb := a $ 2\n";
+        let error = lex_strict(input).unwrap_err().with_source(input);
+
+        assert_eq!(error.line, Some(2));
+        assert_eq!(error.line_text, Some("b := a $ 2".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "error at line 2: Unknown operator `$` at 2:8\n    b := a $ 2"
+        );
+    }
+
+    #[test]
+    fn test_strict_allows_known_symbols() {
+        let input = "x := (a + b) * c[0] // comment with $ and @\ny := \"@ in a string\"";
+        assert_eq!(lex_strict(input).unwrap(), lex_string(input).unwrap());
+    }
+
     #[test]
     fn test_escaped_quote_at_end() {
         // Test string ending with escaped quote
@@ -921,4 +1865,370 @@ mod tests {
             _ => panic!("Expected UnexpectedEndOfInput error"),
         }
     }
+
+    #[test]
+    fn test_comments_are_collected() {
+        let input = "x := 1 // one\n// two  \ny := 2\n// three";
+        let (tokens, _, comments) = Lexer::lex_with_comments(
+            BufReader::new(StringReader::new(input)),
+            LexerConfig::default(),
+        )
+        .unwrap();
+
+        assert_eq!(without_eof(tokens), lex_string(input).unwrap());
+        let texts: Vec<&str> = comments.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["// one", "// two", "// three"]);
+        assert_eq!(comments[0].span.start, Position::new(7, 1, 8));
+        assert_eq!(comments[1].span.start.line, 2);
+        assert_eq!(comments[2].span.start.line, 4);
+    }
+
+    #[test]
+    fn test_method_chain_after_multiline_call() {
+        let tokens = lex_string("x := foo(\n    a,\n    b\n)\n    .bar()\ny := 1").unwrap();
+
+        let close = tokens.iter().position(|t| *t == Token::CloseParen).unwrap();
+        assert_eq!(tokens[close + 1], Token::Dot);
+        assert_eq!(tokens[close + 2], Token::Identifier("bar".to_string()));
+    }
+
+    #[test]
+    fn test_operator_after_multiline_call() {
+        let tokens = lex_string("x := foo(\n    a\n)\n    + 1\n").unwrap();
+        let close = tokens.iter().position(|t| *t == Token::CloseParen).unwrap();
+        assert_eq!(tokens[close + 1], Token::Operator(Operator::Plus));
+    }
+
+    #[test]
+    fn test_method_chain_after_multiline_call_and_comment() {
+        let chained = lex_string("foo(\n    1\n).bar()").unwrap();
+        for input in [
+            "foo(\n    1\n) // c\n.bar()",
+            "foo(\n    1\n)\n// c\n.bar()",
+        ] {
+            assert_eq!(lex_string(input).unwrap(), chained, "{:?}", input);
+        }
+
+        // Without a continuation the line still ends after the comment
+        for input in ["foo(\n    1\n) // c\ny := 1", "foo(\n    1\n)\n// c"] {
+            let tokens = lex_string(input).unwrap();
+            let close = tokens.iter().position(|t| *t == Token::CloseParen).unwrap();
+            assert_eq!(tokens[close + 1], Token::Newline, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_newline_after_multiline_call() {
+        let tokens = lex_string("foo(\n    a\n)\ny := 1").unwrap();
+        let close = tokens.iter().position(|t| *t == Token::CloseParen).unwrap();
+        assert_eq!(tokens[close + 1], Token::Newline);
+
+        let tokens = lex_string("foo(\n    a\n)\n").unwrap();
+        assert_eq!(tokens.last(), Some(&Token::Newline));
+    }
+
+    #[test]
+    fn test_single_line_call_still_ends_statement() {
+        let tokens = lex_string("foo(a)\n.bar()").unwrap();
+        let close = tokens.iter().position(|t| *t == Token::CloseParen).unwrap();
+        assert_eq!(tokens[close + 1], Token::Newline);
+    }
+
+    #[test]
+    fn test_newline_after_bare_return() {
+        let tokens = lex_string("return\nx").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Keyword(Keyword::Return),
+                Token::Newline,
+                Token::Identifier("x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_numbers_are_errors() {
+        for input in ["0x", "0b", "0o", "0x_", "0xg", "0b2", "1e", "1e+", "12ab"] {
+            match lex_string(input) {
+                Err(LexerError::InvalidNumber { literal, .. }) => assert_eq!(literal, input),
+                result => panic!("Expected InvalidNumber for {:?}, got {:?}", input, result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_at() {
+        let tokens = lex_strict("@required name").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::At,
+                Token::Identifier("required".to_string()),
+                Token::Identifier("name".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semicolon() {
+        let tokens = lex_string("x := 1; y := x;\n").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Identifier("x".to_string()),
+                Token::Operator(Operator::Walrus),
+                Token::Literal(Literal::Number("1".to_string())),
+                Token::Semicolon,
+                Token::Identifier("y".to_string()),
+                Token::Operator(Operator::Walrus),
+                Token::Identifier("x".to_string()),
+                Token::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_number_position() {
+        let error = lex_string("x := 0x\n").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid number `0x` at 1:6");
+    }
+
+    fn lex_limited(input: &str, limit: usize) -> Result<Vec<Token>, LexerError> {
+        let config = LexerConfig {
+            max_token_length: Some(limit),
+            ..LexerConfig::default()
+        };
+        Lexer::lex_with_config(BufReader::new(StringReader::new(input)), config).map(without_eof)
+    }
+
+    fn lex_newlines(input: &str, newlines: NewlineRules) -> Vec<Token> {
+        let config = LexerConfig {
+            newlines,
+            ..LexerConfig::default()
+        };
+        without_eof(
+            Lexer::lex_with_config(BufReader::new(StringReader::new(input)), config).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_newline_after_literal() {
+        let number = |n: &str| Token::Literal(Literal::Number(n.to_string()));
+
+        let tokens = lex_newlines("{\n    1\n}\n", NewlineRules::default());
+        assert_eq!(
+            tokens,
+            vec![
+                Token::OpenBrace,
+                number("1"),
+                Token::Newline,
+                Token::CloseBrace,
+                Token::Newline,
+            ]
+        );
+
+        let rules = NewlineRules {
+            after_literal: false,
+            ..NewlineRules::default()
+        };
+        let tokens = lex_newlines("{\n    1\n}\n", rules.clone());
+        assert_eq!(
+            tokens,
+            vec![
+                Token::OpenBrace,
+                number("1"),
+                Token::CloseBrace,
+                Token::Newline,
+            ]
+        );
+        // Only literals are affected
+        let tokens = lex_newlines("x\n", rules);
+        assert_eq!(
+            tokens,
+            vec![Token::Identifier("x".to_string()), Token::Newline]
+        );
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let tokens = lex_string("café := π * cafe\u{301}\n").unwrap();
+        assert_eq!(tokens[0], Token::Identifier("café".to_string()));
+        assert_eq!(tokens[2], Token::Identifier("π".to_string()));
+        // A combining mark may continue an identifier
+        assert_eq!(tokens[4], Token::Identifier("cafe\u{301}".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_unicode_identifier() {
+        // U+0301 is a combining acute accent
+        let error = lex_string("x := \u{301}e\n").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid identifier `\u{301}e` at 1:6");
+
+        match lex_string("a→b := 1\n") {
+            Err(LexerError::InvalidIdentifier {
+                identifier,
+                line: 1,
+                column: 1,
+            }) => assert_eq!(identifier, "a→b"),
+            other => panic!("Expected InvalidIdentifier, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_max_token_length_identifier() {
+        let input = format!("x := {}\n", "a".repeat(9));
+        match lex_limited(&input, 8) {
+            Err(LexerError::TokenTooLong {
+                kind: LongTokenKind::Identifier,
+                limit: 8,
+                line: 1,
+                column: 6,
+            }) => (),
+            other => panic!("Expected TokenTooLong, got {:?}", other),
+        }
+
+        let tokens = lex_limited(&format!("x := {}\n", "a".repeat(8)), 8).unwrap();
+        assert!(tokens.contains(&Token::Identifier("a".repeat(8))));
+    }
+
+    #[test]
+    fn test_max_token_length_string() {
+        let input = format!("x := \"{}\"\n", "s".repeat(9));
+        let error = lex_limited(&input, 8).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The string at 1:6 is longer than the limit of 8 bytes"
+        );
+
+        let tokens = lex_limited(&format!("x := \"{}\"\n", "s".repeat(8)), 8).unwrap();
+        assert!(tokens.contains(&Token::Literal(Literal::String("s".repeat(8)))));
+    }
+
+    fn lex_float_specials(input: &str) -> Result<Vec<Token>, LexerError> {
+        let config = LexerConfig {
+            float_specials: true,
+            ..LexerConfig::default()
+        };
+        Lexer::lex_with_config(BufReader::new(StringReader::new(input)), config).map(without_eof)
+    }
+
+    #[test]
+    fn test_float_specials() {
+        let number = |n: &str| Token::Literal(Literal::Number(n.to_string()));
+
+        let tokens = lex_float_specials("x := inf\ny := -inf\nz := f(nan, 1)\n").unwrap();
+        assert_eq!(tokens[2], number("inf"));
+        assert_eq!(tokens[6], Token::Operator(Operator::Minus));
+        assert_eq!(tokens[7], number("inf"));
+        assert_eq!(tokens[13], number("nan"));
+        let Token::Literal(nan) = &tokens[13] else {
+            unreachable!()
+        };
+        assert!(nan.as_f64().is_some_and(f64::is_nan));
+    }
+
+    #[test]
+    fn test_float_specials_outside_value_position() {
+        let tokens = lex_float_specials("inf := 1\nx := limits.nan\n").unwrap();
+        assert_eq!(tokens[0], Token::Identifier("inf".to_string()));
+        assert_eq!(tokens[8], Token::Identifier("nan".to_string()));
+    }
+
+    #[test]
+    fn test_float_specials_off_by_default() {
+        let tokens = lex_string("inf := 1\nx := inf + nan\n").unwrap();
+        assert_eq!(tokens[0], Token::Identifier("inf".to_string()));
+        assert_eq!(tokens[6], Token::Identifier("inf".to_string()));
+        assert_eq!(tokens[8], Token::Identifier("nan".to_string()));
+    }
+
+    #[test]
+    fn test_max_token_length_defaults_to_unlimited() {
+        let input = format!("x := {}\n", "a".repeat(100_000));
+        assert!(lex_string(&input).is_ok());
+    }
+
+    mod no_panic {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        proptest! {
+            #[test]
+            fn lexes_arbitrary_bytes(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+                let _ = Lexer::lex(bytes.as_slice());
+                let _ = Lexer::lex_with_config(bytes.as_slice(), LexerConfig { strict_operators: true, ..LexerConfig::default() });
+            }
+
+            #[test]
+            fn lexes_arbitrary_text(input in "\\PC{0,128}") {
+                let _ = lex_string(&input);
+                let _ = lex_strict(&input);
+            }
+
+            #[test]
+            fn lexes_token_like_text(input in r#"([0-9a-zA-Z_.xXbBoOeE+\-*/%=!<>:?|(){}\[\],"\\ \t\n]){0,64}"#) {
+                let _ = lex_string(&input);
+                let _ = lex_strict(&input);
+            }
+        }
+    }
+
+    /// Lexing the `Display` of a token gives back that token. Left out are
+    /// `Newline`, which is only emitted after a token that ends a statement,
+    /// `Eof`, which displays as `end of input`, and strings with `"` or `\`,
+    /// which `Literal`'s `Display` doesn't escape.
+    mod round_trip {
+        use proptest::prelude::*;
+
+        use super::*;
+
+        fn identifier() -> impl Strategy<Value = Token> {
+            "[a-zA-Z_][a-zA-Z0-9_]{0,8}"
+                .prop_filter("keywords and booleans aren't identifiers", |name| {
+                    !Keyword::is_reserved(name) && Literal::try_from(name.as_str()).is_err()
+                })
+                .prop_map(Token::Identifier)
+        }
+
+        fn literal() -> impl Strategy<Value = Literal> {
+            prop_oneof![
+                "[a-zA-Z0-9 _.,:;+*/=<>(){}]{0,16}".prop_map(Literal::String),
+                "[1-9][0-9]{0,6}|0".prop_map(Literal::Number),
+                "[0-9]{1,4}\\.[0-9]{1,4}".prop_map(Literal::Number),
+                "0x[0-9a-f]{1,4}|0b[01]{1,8}|0o[0-7]{1,4}".prop_map(Literal::Number),
+                any::<bool>().prop_map(Literal::Boolean),
+            ]
+        }
+
+        fn token() -> impl Strategy<Value = Token> {
+            prop_oneof![
+                identifier(),
+                proptest::sample::select(Keyword::all()).prop_map(Token::Keyword),
+                proptest::sample::select(Operator::all()).prop_map(Token::Operator),
+                literal().prop_map(Token::Literal),
+                proptest::sample::select(vec![
+                    Token::OpenParen,
+                    Token::CloseParen,
+                    Token::OpenBrace,
+                    Token::CloseBrace,
+                    Token::OpenBracket,
+                    Token::CloseBracket,
+                    Token::Comma,
+                    Token::Dot,
+                    Token::Semicolon,
+                    Token::At,
+                ]),
+            ]
+        }
+
+        proptest! {
+            #[test]
+            fn display_lexes_to_the_same_token(token in token()) {
+                let tokens = lex_string(&token.to_string()).unwrap();
+                prop_assert_eq!(tokens, vec![token]);
+            }
+        }
+    }
 }
@@ -1,4 +1,6 @@
 pub mod lexer;
+pub mod source;
+pub mod span;
 pub mod token;
 
 #[cfg(test)]
@@ -23,6 +25,16 @@ mod tests {
         assert_debug_snapshot!(lex("../../examples/hello_world.aeon"));
     }
 
+    #[test]
+    fn test_hello_world_spans() {
+        let source = std::fs::read_to_string("../../examples/hello_world.aeon")
+            .expect("failed to load test source");
+        let reader = StringReader::new(source.as_str());
+        let (tokens, spans) = Lexer::lex_with_spans(BufReader::new(reader), LexerConfig::default())
+            .expect("failed to lex");
+        assert_debug_snapshot!(SpannedTokenDebug::all(&tokens, &spans));
+    }
+
     #[test]
     fn test_modules() {
         assert_debug_snapshot!(lex("../../examples/structs.aeon"));
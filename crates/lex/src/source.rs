@@ -0,0 +1,65 @@
+use std::{error::Error, fmt};
+
+/// An error together with the source line it points at, so it can be printed
+/// as `error at line 5: ...` followed by the offending line.
+#[derive(Debug)]
+pub struct SourceError<E> {
+    pub error: E,
+    /// 1-based, if the error knows where it happened
+    pub line: Option<usize>,
+    pub line_text: Option<String>,
+}
+
+impl<E> SourceError<E> {
+    /// Looks up `line` in `source`.
+    pub fn new(error: E, line: Option<usize>, source: &str) -> Self {
+        let line_text = line.and_then(|line| line_text(source, line));
+        SourceError {
+            error,
+            line,
+            line_text,
+        }
+    }
+}
+
+/// The text of the 1-based `line` of `source`, without its line ending.
+pub fn line_text(source: &str, line: usize) -> Option<String> {
+    source
+        .lines()
+        .nth(line.checked_sub(1)?)
+        .map(|text| text.to_string())
+}
+
+impl<E: fmt::Display> fmt::Display for SourceError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, &self.line_text) {
+            (Some(line), Some(text)) => {
+                write!(f, "error at line {}: {}\n    {}", line, self.error, text)
+            }
+            (Some(line), None) => write!(f, "error at line {}: {}", line, self.error),
+            (None, _) => write!(f, "error: {}", self.error),
+        }
+    }
+}
+
+impl<E: Error> Error for SourceError<E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_text() {
+        let source = "module Main\r\nfn main() {\n}";
+        assert_eq!(line_text(source, 1), Some("module Main".to_string()));
+        assert_eq!(line_text(source, 3), Some("}".to_string()));
+        assert_eq!(line_text(source, 0), None);
+        assert_eq!(line_text(source, 4), None);
+    }
+
+    #[test]
+    fn test_display_without_line() {
+        let error = SourceError::new("oops", None, "module Main");
+        assert_eq!(error.to_string(), "error: oops");
+    }
+}
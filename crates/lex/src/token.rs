@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::span::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Identifier(String),
@@ -14,10 +16,51 @@ pub enum Token {
     OpenBracket,
     Comma,
     Dot,
+    Semicolon,
+    /// Starts an attribute, as in `@required`
+    At,
     Newline,
+    /// Marks the end of the input, so the lexer ends every token list with
+    /// one. `TokenStream` treats it like running out of tokens, whether or
+    /// not it is the last one.
+    Eof,
+}
+
+/// Broad classes of tokens, e.g. for syntax highlighting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Identifier,
+    Keyword,
+    Operator,
+    Literal,
+    /// Brackets, commas and dots
+    Punctuation,
+    Newline,
+    Eof,
 }
 
 impl Token {
+    pub fn kind(&self) -> TokenKind {
+        match self {
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::Keyword(_) => TokenKind::Keyword,
+            Token::Operator(_) => TokenKind::Operator,
+            Token::Literal(_) => TokenKind::Literal,
+            Token::OpenParen
+            | Token::CloseParen
+            | Token::CloseBrace
+            | Token::OpenBrace
+            | Token::CloseBracket
+            | Token::OpenBracket
+            | Token::Comma
+            | Token::Dot
+            | Token::Semicolon
+            | Token::At => TokenKind::Punctuation,
+            Token::Newline => TokenKind::Newline,
+            Token::Eof => TokenKind::Eof,
+        }
+    }
+
     pub fn is_equality(&self) -> bool {
         matches!(self, Token::Operator(Operator::Equal))
             || matches!(self, Token::Operator(Operator::NotEqual))
@@ -44,12 +87,29 @@ impl Token {
     pub fn is_unary(&self) -> bool {
         matches!(self, Token::Operator(Operator::Minus))
             || matches!(self, Token::Operator(Operator::Plus))
+            || matches!(self, Token::Operator(Operator::Tilde))
     }
 
     pub fn is_primary(&self) -> bool {
         matches!(self, Token::Identifier(_))
             || matches!(self, Token::Literal(_))
             || matches!(self, Token::OpenParen)
+            || matches!(self, Token::Keyword(Keyword::SelfKw))
+    }
+
+    /// Checks whether this token matches `pattern` when consuming from a
+    /// token stream. Identifiers and literals match structurally, so any
+    /// identifier matches an identifier pattern and any literal of the same
+    /// kind matches a literal pattern, e.g. any string matches a string
+    /// pattern but a number does not. Every other token must match exactly.
+    pub fn matches(&self, pattern: &Token) -> bool {
+        match (pattern, self) {
+            (Token::Identifier(_), Token::Identifier(_)) => true,
+            (Token::Literal(expected), Token::Literal(actual)) => {
+                std::mem::discriminant(expected) == std::mem::discriminant(actual)
+            }
+            _ => self == pattern,
+        }
     }
 }
 
@@ -68,11 +128,39 @@ impl fmt::Display for Token {
             Token::CloseBracket => write!(f, "]"),
             Token::Comma => write!(f, ","),
             Token::Dot => write!(f, "."),
+            Token::Semicolon => write!(f, ";"),
+            Token::At => write!(f, "@"),
             Token::Newline => writeln!(f),
+            Token::Eof => write!(f, "end of input"),
         }
     }
 }
 
+/// Debug-formats a token with its span on one line, e.g.
+/// `[1:1-1:7] Keyword(Module)`, so snapshots of spanned tokens stay short and
+/// diff line by line.
+pub struct SpannedTokenDebug<'a> {
+    pub token: &'a Token,
+    pub span: &'a Span,
+}
+
+impl<'a> SpannedTokenDebug<'a> {
+    /// Pairs up the tokens and spans returned by `Lexer::lex_with_spans`.
+    pub fn all(tokens: &'a [Token], spans: &'a [Span]) -> Vec<Self> {
+        tokens
+            .iter()
+            .zip(spans)
+            .map(|(token, span)| SpannedTokenDebug { token, span })
+            .collect()
+    }
+}
+
+impl fmt::Debug for SpannedTokenDebug<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {:?}", self.span, self.token)
+    }
+}
+
 impl TryFrom<char> for Token {
     type Error = ();
     fn try_from(c: char) -> Result<Self, ()> {
@@ -85,6 +173,8 @@ impl TryFrom<char> for Token {
             ']' => Ok(Token::CloseBracket),
             ',' => Ok(Token::Comma),
             '.' => Ok(Token::Dot),
+            ';' => Ok(Token::Semicolon),
+            '@' => Ok(Token::At),
             _ => Err(()),
         }
     }
@@ -103,6 +193,46 @@ pub enum Keyword {
     Null,
     Void,
     Mut,
+    SelfKw,
+    Const,
+    Impl,
+    Trait,
+    Match,
+    Assert,
+    /// `yield value`, reserved for generator functions
+    Yield,
+}
+
+impl Keyword {
+    /// Every keyword of the language, in declaration order.
+    pub fn all() -> &'static [Keyword] {
+        &[
+            Keyword::Module,
+            Keyword::Import,
+            Keyword::Fn,
+            Keyword::Struct,
+            Keyword::Enum,
+            Keyword::If,
+            Keyword::Else,
+            Keyword::Return,
+            Keyword::Null,
+            Keyword::Void,
+            Keyword::Mut,
+            Keyword::SelfKw,
+            Keyword::Const,
+            Keyword::Impl,
+            Keyword::Trait,
+            Keyword::Match,
+            Keyword::Assert,
+            Keyword::Yield,
+        ]
+    }
+
+    /// Whether `s` is reserved and can therefore never lex as an identifier.
+    /// Keywords are matched case-insensitively, like the lexer does.
+    pub fn is_reserved(s: &str) -> bool {
+        Keyword::try_from(s).is_ok()
+    }
 }
 
 impl fmt::Display for Keyword {
@@ -119,6 +249,13 @@ impl fmt::Display for Keyword {
             Keyword::Null => write!(f, "null"),
             Keyword::Void => write!(f, "void"),
             Keyword::Mut => write!(f, "mut"),
+            Keyword::SelfKw => write!(f, "self"),
+            Keyword::Const => write!(f, "const"),
+            Keyword::Impl => write!(f, "impl"),
+            Keyword::Trait => write!(f, "trait"),
+            Keyword::Match => write!(f, "match"),
+            Keyword::Assert => write!(f, "assert"),
+            Keyword::Yield => write!(f, "yield"),
         }
     }
 }
@@ -138,6 +275,13 @@ impl TryFrom<&str> for Keyword {
             "null" => Ok(Keyword::Null),
             "void" => Ok(Keyword::Void),
             "mut" => Ok(Keyword::Mut),
+            "self" => Ok(Keyword::SelfKw),
+            "const" => Ok(Keyword::Const),
+            "impl" => Ok(Keyword::Impl),
+            "trait" => Ok(Keyword::Trait),
+            "match" => Ok(Keyword::Match),
+            "assert" => Ok(Keyword::Assert),
+            "yield" => Ok(Keyword::Yield),
             _ => Err(()),
         }
     }
@@ -160,44 +304,158 @@ impl fmt::Display for Literal {
     }
 }
 
+impl Literal {
+    /// The value of a number literal. Radix forms must fit an `i64`.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Literal::Number(n) => self
+                .as_i64()
+                .map(|value| value as f64)
+                .or_else(|| n.replace('_', "").parse().ok()),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer number literal in any radix, or `None` for a
+    /// float or a value that doesn't fit an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        let Literal::Number(n) = self else {
+            return None;
+        };
+        let digits = n.replace('_', "");
+        if let Some(hex) = digits.strip_prefix("0x") {
+            i64::from_str_radix(hex, 16).ok()
+        } else if let Some(bin) = digits.strip_prefix("0b") {
+            i64::from_str_radix(bin, 2).ok()
+        } else if let Some(oct) = digits.strip_prefix("0o") {
+            i64::from_str_radix(oct, 8).ok()
+        } else {
+            digits.parse().ok()
+        }
+    }
+
+    /// `inf`, `-inf` or `nan` as a float number literal. These spellings are
+    /// also valid identifiers, so `TryFrom` never produces them; the lexer
+    /// only does with `LexerConfig::float_specials` set.
+    pub fn float_special(s: &str) -> Option<Literal> {
+        match s {
+            "inf" | "-inf" | "nan" => Some(Literal::Number(s.to_string())),
+            _ => None,
+        }
+    }
+
+    /// Decodes the escapes in the body of a string literal: `\n`, `\r`,
+    /// `\t`, `\0`, `\\`, `\'`, `\"`, `\xHH` up to `\x7F`, and `\u{...}` with
+    /// one to six hex digits. `None` if an escape is malformed or doesn't
+    /// name a unicode scalar value.
+    pub fn unescape(s: &str) -> Option<String> {
+        let mut decoded = String::with_capacity(s.len());
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                decoded.push(c);
+                continue;
+            }
+            decoded.push(match chars.next()? {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                c @ ('\\' | '\'' | '"') => c,
+                'x' => {
+                    let digits: String = chars.by_ref().take(2).collect();
+                    if digits.len() != 2 || !is_radix_body(&digits, 16) {
+                        return None;
+                    }
+                    let value = u8::from_str_radix(&digits, 16).ok()?;
+                    if value > 0x7F {
+                        return None;
+                    }
+                    char::from(value)
+                }
+                'u' => {
+                    if chars.next()? != '{' {
+                        return None;
+                    }
+                    let rest = chars.as_str();
+                    let (digits, _) = rest.split_once('}')?;
+                    if !(1..=6).contains(&digits.len()) || !is_radix_body(digits, 16) {
+                        return None;
+                    }
+                    chars = rest[digits.len() + 1..].chars();
+                    char::from_u32(u32::from_str_radix(digits, 16).ok()?)?
+                }
+                _ => return None,
+            });
+        }
+        Some(decoded)
+    }
+
+    /// Decodes the body of a char literal, which must be exactly one
+    /// character once its escapes are decoded.
+    pub fn unescape_char(s: &str) -> Option<char> {
+        let decoded = Literal::unescape(s)?;
+        let mut chars = decoded.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Literal::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
 impl TryFrom<&str> for Literal {
     type Error = ();
     fn try_from(s: &str) -> Result<Self, ()> {
         match s {
             "true" => Ok(Literal::Boolean(true)),
             "false" => Ok(Literal::Boolean(false)),
-            s if s.starts_with("0x") => {
-                if u64::from_str_radix(&s[2..], 16).is_ok() {
-                    Ok(Literal::Number(s.to_string()))
+            s if s.starts_with(|c: char| c.is_ascii_digit()) => {
+                // Underscores are digit separators; the original spelling is
+                // kept so printing reproduces the source exactly.
+                let digits = s.replace('_', "");
+                let valid = if let Some(hex) = digits.strip_prefix("0x") {
+                    is_radix_body(hex, 16)
+                } else if let Some(bin) = digits.strip_prefix("0b") {
+                    is_radix_body(bin, 2)
+                } else if let Some(oct) = digits.strip_prefix("0o") {
+                    is_radix_body(oct, 8)
                 } else {
-                    Err(())
-                }
-            }
-            s if s.starts_with("0b") => {
-                if u64::from_str_radix(&s[2..], 2).is_ok() {
-                    Ok(Literal::Number(s.to_string()))
-                } else {
-                    Err(())
-                }
-            }
-            s if s.starts_with("0o") => {
-                if u64::from_str_radix(&s[2..], 8).is_ok() {
+                    digits.parse::<f64>().is_ok() || digits.parse::<u64>().is_ok()
+                };
+
+                if valid {
                     Ok(Literal::Number(s.to_string()))
                 } else {
                     Err(())
                 }
             }
-            s if s.parse::<f64>().is_ok() || s.parse::<u64>().is_ok() => {
-                Ok(Literal::Number(s.to_string()))
-            }
             _ => Err(()),
         }
     }
 }
 
+/// The digits after a `0x`/`0b`/`0o` prefix. Checked digit by digit rather
+/// than with `from_str_radix`, which accepts a leading `+` and rejects values
+/// that overflow a `u64`.
+fn is_radix_body(body: &str, radix: u32) -> bool {
+    !body.is_empty() && body.chars().all(|c| c.is_digit(radix))
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
-    Assign,
+    /// `:`, as in the type annotation of `x :i32 = 5`
+    Colon,
+    /// `:=`, declaring a variable
+    Walrus,
+    /// `::`, between the segments of a path like `std::io::println`
+    PathSep,
     Reassign,
     Plus,
     Minus,
@@ -217,40 +475,176 @@ pub enum Operator {
     Or,
     Pipeline,
     Question,
+    Range,
+    Arrow,
+    FatArrow,
+    LeftArrow,
+    Tilde,
 }
 
-impl fmt::Display for Operator {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Operator {
+    /// Every operator, in declaration order.
+    pub fn all() -> &'static [Operator] {
+        &[
+            Operator::Colon,
+            Operator::Walrus,
+            Operator::PathSep,
+            Operator::Reassign,
+            Operator::Plus,
+            Operator::Minus,
+            Operator::Star,
+            Operator::Slash,
+            Operator::Percent,
+            Operator::Caret,
+            Operator::Ampersand,
+            Operator::Pipe,
+            Operator::Less,
+            Operator::Greater,
+            Operator::Equal,
+            Operator::NotEqual,
+            Operator::LessEqual,
+            Operator::GreaterEqual,
+            Operator::And,
+            Operator::Or,
+            Operator::Pipeline,
+            Operator::Question,
+            Operator::Range,
+            Operator::Arrow,
+            Operator::FatArrow,
+            Operator::LeftArrow,
+            Operator::Tilde,
+        ]
+    }
+
+    /// `+ - * / % ^`, where `^` raises to a power
+    pub fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            Operator::Plus
+                | Operator::Minus
+                | Operator::Star
+                | Operator::Slash
+                | Operator::Percent
+                | Operator::Caret
+        )
+    }
+
+    /// `< > == != <= >=`
+    pub fn is_comparison(&self) -> bool {
+        matches!(
+            self,
+            Operator::Less
+                | Operator::Greater
+                | Operator::Equal
+                | Operator::NotEqual
+                | Operator::LessEqual
+                | Operator::GreaterEqual
+        )
+    }
+
+    /// `&& ||`
+    pub fn is_logical(&self) -> bool {
+        matches!(self, Operator::And | Operator::Or)
+    }
+
+    /// `& | ~`. `^` is the exponent, not exclusive or.
+    pub fn is_bitwise(&self) -> bool {
+        matches!(self, Operator::Ampersand | Operator::Pipe | Operator::Tilde)
+    }
+
+    /// Whether a binary expression using this operator evaluates to a bool,
+    /// whatever its operand types.
+    pub fn result_is_bool(&self) -> bool {
+        self.is_comparison() || self.is_logical()
+    }
+
+    /// `:= = ^`. `a = b = c` groups as `a = (b = c)`, and the exponent `^`
+    /// groups the same way: `2 ^ 3 ^ 2` is `2 ^ (3 ^ 2)`.
+    pub fn is_right_associative(&self) -> bool {
+        matches!(
+            self,
+            Operator::Walrus | Operator::Reassign | Operator::Caret
+        )
+    }
+
+    /// How the operator is written, such as `+`
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Colon => ":",
+            Operator::Walrus => ":=",
+            Operator::PathSep => "::",
+            Operator::Reassign => "=",
+            Operator::Plus => "+",
+            Operator::Minus => "-",
+            Operator::Star => "*",
+            Operator::Slash => "/",
+            Operator::Percent => "%",
+            Operator::Caret => "^",
+            Operator::Ampersand => "&",
+            Operator::Pipe => "|",
+            Operator::Less => "<",
+            Operator::Greater => ">",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::LessEqual => "<=",
+            Operator::GreaterEqual => ">=",
+            Operator::And => "&&",
+            Operator::Or => "||",
+            Operator::Pipeline => "|>",
+            Operator::Question => "?",
+            Operator::Range => "..",
+            Operator::Arrow => "->",
+            Operator::FatArrow => "=>",
+            Operator::LeftArrow => "<-",
+            Operator::Tilde => "~",
+        }
+    }
+
+    /// A human readable name for the operator, such as `plus` for `+`
+    pub fn name(&self) -> &'static str {
         match self {
-            Operator::Assign => write!(f, ":"),
-            Operator::Reassign => write!(f, "="),
-            Operator::Plus => write!(f, "+"),
-            Operator::Minus => write!(f, "-"),
-            Operator::Star => write!(f, "*"),
-            Operator::Slash => write!(f, "/"),
-            Operator::Percent => write!(f, "%"),
-            Operator::Caret => write!(f, "^"),
-            Operator::Ampersand => write!(f, "&"),
-            Operator::Pipe => write!(f, "|"),
-            Operator::Less => write!(f, "<"),
-            Operator::Greater => write!(f, ">"),
-            Operator::Equal => write!(f, "=="),
-            Operator::NotEqual => write!(f, "!="),
-            Operator::LessEqual => write!(f, "<="),
-            Operator::GreaterEqual => write!(f, ">="),
-            Operator::And => write!(f, "&&"),
-            Operator::Or => write!(f, "||"),
-            Operator::Pipeline => write!(f, "|>"),
-            Operator::Question => write!(f, "?"),
+            Operator::Colon => "colon",
+            Operator::Walrus => "walrus",
+            Operator::PathSep => "path separator",
+            Operator::Reassign => "reassign",
+            Operator::Plus => "plus",
+            Operator::Minus => "minus",
+            Operator::Star => "star",
+            Operator::Slash => "slash",
+            Operator::Percent => "percent",
+            Operator::Caret => "caret",
+            Operator::Ampersand => "ampersand",
+            Operator::Pipe => "pipe",
+            Operator::Less => "less than",
+            Operator::Greater => "greater than",
+            Operator::Equal => "equal",
+            Operator::NotEqual => "not equal",
+            Operator::LessEqual => "less than or equal",
+            Operator::GreaterEqual => "greater than or equal",
+            Operator::And => "and",
+            Operator::Or => "or",
+            Operator::Pipeline => "pipeline",
+            Operator::Question => "question mark",
+            Operator::Range => "range",
+            Operator::Arrow => "arrow",
+            Operator::FatArrow => "fat arrow",
+            Operator::LeftArrow => "left arrow",
+            Operator::Tilde => "tilde",
         }
     }
 }
 
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
 impl TryFrom<&str> for Operator {
     type Error = ();
     fn try_from(s: &str) -> Result<Self, ()> {
         match s {
-            ":" => Ok(Operator::Assign),
+            ":" => Ok(Operator::Colon),
             "=" => Ok(Operator::Reassign),
             "+" => Ok(Operator::Plus),
             "-" => Ok(Operator::Minus),
@@ -260,6 +654,7 @@ impl TryFrom<&str> for Operator {
             "^" => Ok(Operator::Caret),
             "&" => Ok(Operator::Ampersand),
             "|" => Ok(Operator::Pipe),
+            "~" => Ok(Operator::Tilde),
 
             "<" => Ok(Operator::Less),
             ">" => Ok(Operator::Greater),
@@ -267,13 +662,271 @@ impl TryFrom<&str> for Operator {
             "&&" => Ok(Operator::And),
             "||" => Ok(Operator::Or),
 
+            ":=" => Ok(Operator::Walrus),
+            "::" => Ok(Operator::PathSep),
             "!=" => Ok(Operator::NotEqual),
             "==" => Ok(Operator::Equal),
             "<=" => Ok(Operator::LessEqual),
             ">=" => Ok(Operator::GreaterEqual),
             "|>" => Ok(Operator::Pipeline),
             "?" => Ok(Operator::Question),
+            ".." => Ok(Operator::Range),
+            "->" => Ok(Operator::Arrow),
+            "=>" => Ok(Operator::FatArrow),
+            "<-" => Ok(Operator::LeftArrow),
             _ => Err(()),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_all_round_trips() {
+        for keyword in Keyword::all() {
+            assert_eq!(
+                Keyword::try_from(keyword.to_string().as_str()),
+                Ok(keyword.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_operator_symbol_and_name() {
+        let cases = [
+            (Operator::Plus, "+", "plus"),
+            (Operator::Colon, ":", "colon"),
+            (Operator::Walrus, ":=", "walrus"),
+            (Operator::PathSep, "::", "path separator"),
+            (Operator::Reassign, "=", "reassign"),
+            (Operator::LessEqual, "<=", "less than or equal"),
+            (Operator::Pipeline, "|>", "pipeline"),
+            (Operator::FatArrow, "=>", "fat arrow"),
+        ];
+        for (operator, symbol, name) in cases {
+            assert_eq!(operator.symbol(), symbol);
+            assert_eq!(operator.name(), name);
+            assert_eq!(operator.to_string(), symbol);
+        }
+    }
+
+    #[test]
+    fn test_is_reserved() {
+        assert!(Keyword::is_reserved("module"));
+        assert!(Keyword::is_reserved("self"));
+        assert!(Keyword::is_reserved("Fn"));
+        assert!(!Keyword::is_reserved("main"));
+        assert!(!Keyword::is_reserved("modules"));
+    }
+
+    #[test]
+    fn test_literal_empty_radix_body() {
+        assert_eq!(Literal::try_from("0x"), Err(()));
+        assert_eq!(Literal::try_from("0b"), Err(()));
+        assert_eq!(Literal::try_from("0o"), Err(()));
+        assert_eq!(Literal::try_from("0x_"), Err(()));
+    }
+
+    #[test]
+    fn test_literal_invalid_radix_digits() {
+        assert_eq!(Literal::try_from("0b2"), Err(()));
+        assert_eq!(Literal::try_from("0o9"), Err(()));
+        assert_eq!(Literal::try_from("0xg"), Err(()));
+        assert_eq!(Literal::try_from("0x+1"), Err(()));
+    }
+
+    #[test]
+    fn test_literal_zero() {
+        assert_eq!(Literal::try_from("0"), Ok(Literal::Number("0".to_string())));
+        assert_eq!(
+            Literal::try_from("0b0"),
+            Ok(Literal::Number("0b0".to_string()))
+        );
+        assert_eq!(
+            Literal::try_from("0xFFFF_FFFF_FFFF_FFFF_FF"),
+            Ok(Literal::Number("0xFFFF_FFFF_FFFF_FFFF_FF".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_literal_float_special() {
+        for special in ["inf", "-inf", "nan"] {
+            assert_eq!(Literal::try_from(special), Err(()));
+        }
+
+        let inf = Literal::float_special("inf").unwrap();
+        assert_eq!(inf.as_f64(), Some(f64::INFINITY));
+        assert_eq!(inf.as_i64(), None);
+        let negative = Literal::float_special("-inf").unwrap();
+        assert_eq!(negative.as_f64(), Some(f64::NEG_INFINITY));
+        let nan = Literal::float_special("nan").unwrap();
+        assert!(nan.as_f64().is_some_and(f64::is_nan));
+        assert_eq!(Literal::float_special("infinity"), None);
+        assert_eq!(Literal::float_special("NaN"), None);
+    }
+
+    #[test]
+    fn test_literal_values() {
+        let number = |n: &str| Literal::Number(n.to_string());
+
+        assert_eq!(number("1_000").as_i64(), Some(1000));
+        assert_eq!(number("1_000").as_f64(), Some(1000.0));
+        assert_eq!(number("0xff_ff").as_i64(), Some(0xffff));
+        assert_eq!(number("0b1010").as_i64(), Some(10));
+        assert_eq!(number("0o17").as_f64(), Some(15.0));
+        assert_eq!(number("1.5e-3").as_f64(), Some(1.5e-3));
+        assert_eq!(number("1.5e-3").as_i64(), None);
+        assert_eq!(number("0xFFFF_FFFF_FFFF_FFFF_FF").as_i64(), None);
+        assert_eq!(Literal::Boolean(true).as_bool(), Some(true));
+        assert_eq!(number("1").as_bool(), None);
+
+        let string = Literal::String("1".to_string());
+        assert_eq!(string.as_f64(), None);
+        assert_eq!(string.as_i64(), None);
+        assert_eq!(string.as_bool(), None);
+    }
+
+    #[test]
+    fn test_literal_exponents() {
+        // (literal, valid)
+        let cases = [
+            ("1e-10", true),
+            ("1.5e+3", true),
+            ("0.0e0", true),
+            ("1E10", true),
+            ("1e", false),
+            ("1e+", false),
+            ("1.2.3", false),
+        ];
+        for (literal, valid) in cases {
+            let expected = if valid {
+                Ok(Literal::Number(literal.to_string()))
+            } else {
+                Err(())
+            };
+            assert_eq!(Literal::try_from(literal), expected, "{}", literal);
+        }
+    }
+
+    #[test]
+    fn test_operator_categories() {
+        // (operator, arithmetic, comparison, logical, bitwise, result_is_bool)
+        let cases = [
+            (Operator::Colon, false, false, false, false, false),
+            (Operator::Walrus, false, false, false, false, false),
+            (Operator::PathSep, false, false, false, false, false),
+            (Operator::Reassign, false, false, false, false, false),
+            (Operator::Plus, true, false, false, false, false),
+            (Operator::Minus, true, false, false, false, false),
+            (Operator::Star, true, false, false, false, false),
+            (Operator::Slash, true, false, false, false, false),
+            (Operator::Percent, true, false, false, false, false),
+            (Operator::Caret, true, false, false, false, false),
+            (Operator::Ampersand, false, false, false, true, false),
+            (Operator::Pipe, false, false, false, true, false),
+            (Operator::Less, false, true, false, false, true),
+            (Operator::Greater, false, true, false, false, true),
+            (Operator::Equal, false, true, false, false, true),
+            (Operator::NotEqual, false, true, false, false, true),
+            (Operator::LessEqual, false, true, false, false, true),
+            (Operator::GreaterEqual, false, true, false, false, true),
+            (Operator::And, false, false, true, false, true),
+            (Operator::Or, false, false, true, false, true),
+            (Operator::Pipeline, false, false, false, false, false),
+            (Operator::Question, false, false, false, false, false),
+            (Operator::Range, false, false, false, false, false),
+            (Operator::Arrow, false, false, false, false, false),
+            (Operator::FatArrow, false, false, false, false, false),
+            (Operator::LeftArrow, false, false, false, false, false),
+            (Operator::Tilde, false, false, false, true, false),
+        ];
+
+        for (operator, arithmetic, comparison, logical, bitwise, result_is_bool) in cases {
+            assert_eq!(operator.is_arithmetic(), arithmetic, "{}", operator);
+            assert_eq!(operator.is_comparison(), comparison, "{}", operator);
+            assert_eq!(operator.is_logical(), logical, "{}", operator);
+            assert_eq!(operator.is_bitwise(), bitwise, "{}", operator);
+            assert_eq!(operator.result_is_bool(), result_is_bool, "{}", operator);
+        }
+    }
+
+    #[test]
+    fn test_is_right_associative() {
+        assert!(Operator::Caret.is_right_associative());
+        assert!(Operator::Reassign.is_right_associative());
+        assert!(Operator::Walrus.is_right_associative());
+        assert!(!Operator::Colon.is_right_associative());
+        assert!(!Operator::Plus.is_right_associative());
+        assert!(!Operator::Minus.is_right_associative());
+        assert!(!Operator::Less.is_right_associative());
+    }
+
+    #[test]
+    fn test_matches_identifier() {
+        let token = Token::Identifier("foo".to_string());
+        assert!(token.matches(&Token::Identifier(String::new())));
+        assert!(token.matches(&Token::Identifier("bar".to_string())));
+    }
+
+    #[test]
+    fn test_matches_identifier_against_keyword() {
+        let keyword = Token::Keyword(Keyword::Fn);
+        assert!(!keyword.matches(&Token::Identifier(String::new())));
+        assert!(!Token::Identifier("fn".to_string()).matches(&keyword));
+    }
+
+    #[test]
+    fn test_matches_literal_kind() {
+        let number = Token::Literal(Literal::Number("1".to_string()));
+        assert!(number.matches(&Token::Literal(Literal::Number(String::new()))));
+        assert!(!number.matches(&Token::Literal(Literal::String(String::new()))));
+        assert!(!number.matches(&Token::Literal(Literal::Boolean(false))));
+    }
+
+    #[test]
+    fn test_unescape_char() {
+        assert_eq!(Literal::unescape_char(r"\n"), Some('\n'));
+        assert_eq!(Literal::unescape_char(r"\x41"), Some('A'));
+        assert_eq!(Literal::unescape_char(r"\u{41}"), Some('A'));
+        assert_eq!(Literal::unescape_char(r"\u{1F600}"), Some('😀'));
+        assert_eq!(Literal::unescape_char("é"), Some('é'));
+    }
+
+    #[test]
+    fn test_unescape_char_invalid() {
+        // Past the last unicode scalar value
+        assert_eq!(Literal::unescape_char(r"\u{110000}"), None);
+        // A surrogate
+        assert_eq!(Literal::unescape_char(r"\u{D800}"), None);
+        assert_eq!(Literal::unescape_char(r"\u{41"), None);
+        assert_eq!(Literal::unescape_char(r"\u{}"), None);
+        assert_eq!(Literal::unescape_char(r"\x4"), None);
+        assert_eq!(Literal::unescape_char(r"\x80"), None);
+        // `from_str_radix` alone would take a sign
+        assert_eq!(Literal::unescape_char(r"\x+7"), None);
+        assert_eq!(Literal::unescape_char(r"\u{+41}"), None);
+        assert_eq!(Literal::unescape_char(r"\q"), None);
+        assert_eq!(Literal::unescape_char(r"\"), None);
+        // More than one character
+        assert_eq!(Literal::unescape_char(r"\x41\x42"), None);
+        assert_eq!(Literal::unescape_char(""), None);
+    }
+
+    #[test]
+    fn test_unescape_string() {
+        assert_eq!(
+            Literal::unescape(r#"a\tb\\c\"d\u{e9}\n"#),
+            Some("a\tb\\c\"d\u{e9}\n".to_string())
+        );
+    }
+
+    #[test]
+    fn test_matches_exact_keyword() {
+        let token = Token::Keyword(Keyword::Module);
+        assert!(token.matches(&Token::Keyword(Keyword::Module)));
+        assert!(!token.matches(&Token::Keyword(Keyword::Import)));
+        assert!(Token::Comma.matches(&Token::Comma));
+    }
+}
@@ -0,0 +1,140 @@
+//! Questions about names in parsed code, for linters.
+
+use std::collections::HashSet;
+
+use lex::token::Token;
+
+use crate::{
+    ast::{Expression, Statement, Variable},
+    block::Block,
+    functions::Function,
+    scope::{ScopedVisitor, walk_scoped_expression, walk_scoped_statement},
+    visitor::{Visitor, walk_block, walk_variable},
+};
+
+/// Every name `function` uses without declaring it: not a parameter and not
+/// declared by a statement in scope at the use. Called functions count, so
+/// `print(x)` uses `print`.
+pub fn free_variables(function: &Function) -> HashSet<String> {
+    let parameters = function
+        .parameters
+        .iter()
+        .filter_map(|arg| match &arg.name {
+            Token::Identifier(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    let mut visitor = FreeVariables {
+        scopes: vec![parameters],
+        free: HashSet::new(),
+    };
+    visitor.visit_function(function);
+    visitor.free
+}
+
+struct FreeVariables<'a> {
+    /// Names declared in each enclosing scope, innermost last
+    scopes: Vec<HashSet<&'a str>>,
+    free: HashSet<String>,
+}
+
+impl<'a> ScopedVisitor<'a> for FreeVariables<'a> {
+    fn enter_scope(&mut self) {
+        self.scopes.push(HashSet::new());
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &'a str) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name);
+        }
+    }
+}
+
+impl FreeVariables<'_> {
+    fn is_declared(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name))
+    }
+}
+
+impl<'a> Visitor<'a> for FreeVariables<'a> {
+    fn visit_block(&mut self, block: &'a Block) {
+        self.enter_scope();
+        walk_block(self, block);
+        self.exit_scope();
+    }
+
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        walk_scoped_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        walk_scoped_expression(self, expression);
+    }
+
+    fn visit_variable(&mut self, variable: &'a Variable) {
+        // The initializer can't see the variable it declares
+        walk_variable(self, variable);
+        if variable.is_decl {
+            self.declare(&variable.name);
+        } else if !self.is_declared(&variable.name) {
+            self.free.insert(variable.name.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{main_with_body, parse};
+
+    fn free_in_main(body: &str) -> HashSet<String> {
+        let ast = parse(&main_with_body(body));
+        free_variables(&ast.root.unwrap().functions[0])
+    }
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parameter_is_not_free() {
+        assert_eq!(free_in_main("    x := n + 1\n"), names(&[]));
+    }
+
+    #[test]
+    fn test_global_is_free() {
+        assert_eq!(
+            free_in_main("    x := LIMIT * n\n    print(x)\n"),
+            names(&["LIMIT", "print"])
+        );
+    }
+
+    #[test]
+    fn test_local_is_not_free() {
+        assert_eq!(
+            free_in_main("    x := 1\n    a, b := pair(x)\n    y := a + b\n"),
+            names(&["pair"])
+        );
+    }
+
+    #[test]
+    fn test_local_out_of_scope_is_free() {
+        let body = "    {\n        x := 1\n    }\n    y := x\n    if z := find() {\n        w := z\n    }\n    v := z\n";
+        assert_eq!(free_in_main(body), names(&["x", "find", "z"]));
+    }
+
+    #[test]
+    fn test_initializer_cannot_see_its_variable() {
+        assert_eq!(free_in_main("    x := x + 1\n"), names(&["x"]));
+    }
+
+    #[test]
+    fn test_match_binding_is_scoped_to_its_arm() {
+        let body = "    x := match n {\n        m => m\n        _ => m\n    }\n";
+        assert_eq!(free_in_main(body), names(&["m"]));
+    }
+}
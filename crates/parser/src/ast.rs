@@ -1,5 +1,21 @@
-use crate::{block::Block, functions::TypeInfo, modules::Module};
-use lex::token::{Literal, Operator};
+use crate::{
+    block::Block,
+    enums::Enum,
+    functions::{Arg, Function, GenericParam, Type, TypeInfo},
+    impls::Impl,
+    modules::Module,
+    node::NodeId,
+    pattern::Pattern,
+    structs::{Struct, StructKind},
+    traits::{Trait, TraitMethod},
+};
+use std::collections::{BTreeMap, VecDeque};
+
+use lex::{
+    lexer::Comment,
+    span::Span,
+    token::{Literal, Operator},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Ast {
@@ -10,6 +26,29 @@ impl Ast {
     pub fn new() -> Self {
         Ast { root: None }
     }
+
+    /// Finds a function by its dotted path, such as
+    /// `Calculator.Utils.square`.
+    pub fn find_function(&self, qualified: &str) -> Option<&Function> {
+        let (module_name, function_name) = qualified.rsplit_once('.')?;
+        let module = find_nested_module(self.root.as_ref()?, module_name)?;
+        module
+            .functions
+            .iter()
+            .find(|function| function.name == function_name)
+    }
+}
+
+/// Nested module names are stored fully qualified, so a path matches a
+/// module's name directly.
+fn find_nested_module<'a>(module: &'a Module, name: &str) -> Option<&'a Module> {
+    if module.name == name {
+        return Some(module);
+    }
+    module
+        .modules
+        .iter()
+        .find_map(|inner| find_nested_module(inner, name))
 }
 
 impl Default for Ast {
@@ -18,19 +57,110 @@ impl Default for Ast {
     }
 }
 
+/// Several parsed files, each file's root module keyed by its filename.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    pub files: BTreeMap<String, Module>,
+}
+
+impl Program {
+    /// Finds the root module declared as `name` in any file.
+    pub fn find_module(&self, name: &str) -> Option<&Module> {
+        self.files.values().find(|module| module.name == name)
+    }
+}
+
+/// Equality ignores `id`, which says where a node was parsed rather than
+/// what it is.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub id: NodeId,
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+impl Statement {
+    pub fn new(kind: StatementKind, span: Span) -> Self {
+        Self::with_id(NodeId::default(), kind, span)
+    }
+
+    pub fn with_id(id: NodeId, kind: StatementKind, span: Span) -> Self {
+        Statement { id, kind, span }
+    }
+}
+
+impl PartialEq for Statement {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.span == other.span
+    }
+}
+
+impl From<StatementKind> for Statement {
+    fn from(kind: StatementKind) -> Self {
+        Statement::new(kind, Span::default())
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
-pub enum Statement {
+pub enum StatementKind {
     Expression(Expression),
     Block(Block),
+    /// `a, b := expression`
+    Destructure {
+        names: Vec<String>,
+        expression: Expression,
+    },
+    /// `return` or `return value`
+    Return(Option<Expression>),
+    /// `assert condition` or `assert condition, message`
+    Assert {
+        condition: Expression,
+        message: Option<Expression>,
+    },
+    /// `yield value`, handing `value` to the caller of a generator
+    Yield(Expression),
+}
+
+/// Equality ignores `id`, which says where a node was parsed rather than
+/// what it is.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    pub id: NodeId,
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self::with_id(NodeId::default(), kind, span)
+    }
+
+    pub fn with_id(id: NodeId, kind: ExpressionKind, span: Span) -> Self {
+        Expression { id, kind, span }
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.span == other.span
+    }
+}
+
+impl From<ExpressionKind> for Expression {
+    fn from(kind: ExpressionKind) -> Self {
+        Expression::new(kind, Span::default())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum Expression {
+pub enum ExpressionKind {
     Literal(Literal),
     LiteralNull,
     Group {
         inner: Box<Expression>,
     },
+    /// `(a, b)`. A single parenthesized expression is a `Group`.
+    Tuple(Vec<Expression>),
     Unary {
         operator: Operator,
         operand: Box<Expression>,
@@ -40,13 +170,1034 @@ pub enum Expression {
         right: Box<Expression>,
         operator: Operator,
     },
+    /// `left && right`. Kept apart from `Binary` because `right` is only
+    /// evaluated when `left` is true.
+    And {
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
+    /// `left || right`, evaluating `right` only when `left` is false
+    Or {
+        left: Box<Expression>,
+        right: Box<Expression>,
+    },
     Variable(Variable),
+    /// `std::io::println`, a name qualified by the modules it is in. A `.`
+    /// is always field access, so `math.sqrt` is a `FieldAccess` even when
+    /// `math` names a module.
+    Path(Vec<String>),
+    SelfRef,
+    FieldAccess {
+        object: Box<Expression>,
+        field: String,
+    },
+    /// `object?.field`, which is null when `object` is
+    OptionalAccess {
+        object: Box<Expression>,
+        field: String,
+    },
+    Call {
+        callee: Box<Expression>,
+        arguments: Vec<Expression>,
+    },
+    Index {
+        object: Box<Expression>,
+        index: Box<Expression>,
+    },
+    Slice {
+        object: Box<Expression>,
+        range: Range,
+    },
+    Try(Box<Expression>),
+    Ternary {
+        condition: Box<Expression>,
+        then_branch: Box<Expression>,
+        else_branch: Box<Expression>,
+    },
+    /// `if cond { 1 } else { 2 }`. Each branch evaluates to its
+    /// `Block::value`.
+    If {
+        condition: IfCondition,
+        then_branch: Block,
+        else_branch: Option<Block>,
+    },
+    /// `match x { 1 => a, _ => b }`. The first arm whose pattern matches
+    /// `scrutinee` is evaluated.
+    Match {
+        scrutinee: Box<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    /// `Point { x := 1, y := 2 }`, or `Point { ..base, x := 5 }` to copy the
+    /// fields not listed from `base`
+    StructLiteral {
+        name: String,
+        base: Option<Box<Expression>>,
+        fields: Vec<FieldInit>,
+    },
+    /// `{ x := 1, y := 2 }`, a struct literal without a name
+    Record(Vec<FieldInit>),
+}
+
+/// One `pattern => body` arm of a `match`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub body: Expression,
+}
+
+/// One `name := value` field of a struct literal or record.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit {
+    pub name: String,
+    pub value: Expression,
+}
+
+/// What an `if` tests.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IfCondition {
+    /// `if cond { }`
+    Expr(Box<Expression>),
+    /// `if x := optional() { }`, where `x` is only in scope in the then-block
+    Binding(Variable),
+}
+
+/// A `start..end` range where either side may be left open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub start: Option<Box<Expression>>,
+    pub end: Option<Box<Expression>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Variable {
     pub name: String,
     pub is_decl: bool,
+    pub is_const: bool,
     pub expression: Option<Box<Expression>>,
     pub type_info: Option<TypeInfo>,
 }
+
+/// Prints an AST back out as Aeon source.
+///
+/// Literals are printed with their original spelling, so `0xFF` and `1_000`
+/// survive a lex → parse → print round trip unchanged.
+///
+/// Comments given to `with_comments` are placed using the spans of the AST,
+/// so the AST should come from `Parser::parse_with_spans`. A comment is
+/// printed on its own line before the first node that starts after it, or at
+/// the end of the line it shares with a statement.
+#[derive(Debug, Default)]
+pub struct PrettyPrinter {
+    indent: usize,
+    output: String,
+    comments: VecDeque<Comment>,
+    minified: bool,
+    #[cfg(feature = "color")]
+    colored: bool,
+}
+
+impl PrettyPrinter {
+    const INDENT: &'static str = "    ";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_comments(comments: Vec<Comment>) -> Self {
+        PrettyPrinter {
+            comments: comments.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Prints the smallest source that lexes to the same tokens as the
+    /// pretty-printed one: no indentation, blank lines or comments, and
+    /// spaces only between tokens that would otherwise run together.
+    pub fn minified() -> Self {
+        PrettyPrinter {
+            minified: true,
+            ..Self::default()
+        }
+    }
+
+    /// Highlights keywords, literals and operators with ANSI escape codes,
+    /// for printing to a terminal.
+    #[cfg(feature = "color")]
+    pub fn colored() -> Self {
+        PrettyPrinter {
+            colored: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn print(&mut self, ast: &Ast) -> String {
+        self.output.clear();
+        if let Some(module) = &ast.root {
+            self.write_root_module(module);
+        }
+        self.write_comments_before(usize::MAX);
+        self.finish()
+    }
+
+    pub fn print_expression(&mut self, expression: &Expression) -> String {
+        self.output.clear();
+        self.write_expression(expression);
+        self.finish()
+    }
+
+    pub fn print_function(&mut self, function: &Function) -> String {
+        self.output.clear();
+        self.write_function(function);
+        self.finish()
+    }
+
+    /// A method of a trait, with its default body if it has one.
+    pub fn print_trait_method(&mut self, method: &TraitMethod) -> String {
+        self.output.clear();
+        self.write_trait_method(method);
+        self.finish()
+    }
+
+    /// The header of `function`, such as `fn add(a i32, b i32) i32`.
+    pub fn print_signature(&mut self, function: &Function) -> String {
+        self.output.clear();
+        self.write_signature(
+            function.is_const,
+            &function.name,
+            &function.generics,
+            &function.parameters,
+            &function.return_type,
+        );
+        self.finish()
+    }
+
+    fn finish(&mut self) -> String {
+        let output = std::mem::take(&mut self.output);
+        if self.minified {
+            return crate::minify::minify(&output);
+        }
+        #[cfg(feature = "color")]
+        if self.colored {
+            return crate::color::colorize(&output);
+        }
+        output
+    }
+
+    fn write_root_module(&mut self, module: &Module) {
+        self.write_comments_before(module.decl_span.start.line);
+        self.output.push_str(&format!("module {}\n", module.name));
+        self.write_module_items(module);
+    }
+
+    fn write_module_items(&mut self, module: &Module) {
+        if !module.imports.is_empty() {
+            self.output.push('\n');
+            for import in &module.imports {
+                self.write_comments_before(import.decl_span.start.line);
+                self.write_indent();
+                if let Some(cfg) = &import.cfg {
+                    self.output.push_str(&format!("{} ", cfg));
+                }
+                self.output.push_str(&format!("import {}", import.path));
+                if !import.items.is_empty() {
+                    self.output
+                        .push_str(&format!(" {{ {} }}", import.items.join(", ")));
+                }
+                self.write_trailing_comment(import.decl_span.start.line);
+                self.output.push('\n');
+            }
+        }
+
+        if !module.aliases.is_empty() {
+            self.output.push('\n');
+            for alias in &module.aliases {
+                self.write_comments_before(alias.decl_span.start.line);
+                self.write_indent();
+                self.output.push_str(&format!(
+                    "module {} = {}",
+                    alias.name,
+                    alias.target.join(".")
+                ));
+                self.write_trailing_comment(alias.decl_span.start.line);
+                self.output.push('\n');
+            }
+        }
+
+        if !module.variables.is_empty() {
+            self.output.push('\n');
+            for variable in &module.variables {
+                // Module variables have no span of their own, but their
+                // initializer starts on the same line
+                let line = variable.expression.as_ref().map(|e| e.span.start.line);
+                self.write_comments_before(line.unwrap_or_default());
+                self.write_indent();
+                self.write_variable(variable);
+                self.write_trailing_comment(line.unwrap_or_default());
+                self.output.push('\n');
+            }
+        }
+
+        if !module.statements.is_empty() {
+            self.output.push('\n');
+            for statement in &module.statements {
+                self.write_statement(statement);
+            }
+        }
+
+        for inner in &module.modules {
+            self.output.push('\n');
+            // Nested module names are stored fully qualified
+            let name = inner.name.rsplit('.').next().unwrap_or(&inner.name);
+            self.write_comments_before(inner.span.start.line);
+            self.write_line(&format!("module {} {{", name));
+            self.indent += 1;
+            self.write_module_items(inner);
+            self.write_comments_before(inner.span.end.line);
+            self.indent -= 1;
+            self.write_line("}");
+        }
+
+        for struct_def in &module.structs {
+            self.output.push('\n');
+            self.write_struct(struct_def);
+        }
+
+        for enum_def in &module.enums {
+            self.output.push('\n');
+            self.write_enum(enum_def);
+        }
+
+        for trait_def in &module.traits {
+            self.output.push('\n');
+            self.write_trait(trait_def);
+        }
+
+        for implementation in &module.impls {
+            self.output.push('\n');
+            self.write_impl(implementation);
+        }
+
+        for function in &module.functions {
+            self.output.push('\n');
+            self.write_function(function);
+        }
+    }
+
+    fn write_impl(&mut self, implementation: &Impl) {
+        self.write_comments_before(implementation.span.start.line);
+        self.write_line(&format!("impl {} {{", implementation.type_name));
+        self.indent += 1;
+        for (i, function) in implementation.functions.iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.write_function(function);
+        }
+        self.write_comments_before(implementation.span.end.line);
+        self.indent -= 1;
+        self.write_line("}");
+    }
+
+    fn write_enum(&mut self, enum_def: &Enum) {
+        self.write_comments_before(enum_def.span.start.line);
+        self.write_line(&format!("enum {} {{", enum_def.name));
+        self.indent += 1;
+        for variant in &enum_def.variants {
+            self.write_comments_before(variant.span.start.line);
+            self.write_indent();
+            self.output.push_str(&variant.name);
+            if let Some(value) = &variant.value {
+                self.output.push_str(" = ");
+                self.write_expression(value);
+            }
+            // The doc is the comment the printer was given, if it has any
+            let length = self.output.len();
+            self.write_trailing_comment(variant.span.end.line);
+            if self.output.len() == length
+                && let Some(doc) = &variant.doc
+            {
+                self.output.push_str(&format!(" // {}", doc));
+            }
+            self.output.push('\n');
+        }
+        self.write_comments_before(enum_def.span.end.line);
+        self.indent -= 1;
+        self.write_line("}");
+    }
+
+    fn write_struct(&mut self, struct_def: &Struct) {
+        self.write_comments_before(struct_def.span.start.line);
+        match &struct_def.kind {
+            StructKind::Named(fields) => {
+                self.write_line(&format!("struct {} {{", struct_def.name));
+                self.indent += 1;
+                for field in fields {
+                    self.write_indent();
+                    for attribute in &field.attributes {
+                        self.output.push_str(&format!("{} ", attribute));
+                    }
+                    self.output.push_str(&format!("{} ", field.name));
+                    self.write_type(&field.type_info);
+                    self.output.push('\n');
+                }
+                self.write_comments_before(struct_def.span.end.line);
+                self.indent -= 1;
+                self.write_line("}");
+            }
+            StructKind::Tuple(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .filter_map(|field| field.ty.as_ref().map(Type::to_string))
+                    .collect();
+                self.write_line(&format!(
+                    "struct {}({})",
+                    struct_def.name,
+                    fields.join(", ")
+                ));
+            }
+        }
+    }
+
+    fn write_trait(&mut self, trait_def: &Trait) {
+        self.write_comments_before(trait_def.span.start.line);
+        self.write_line(&format!("trait {} {{", trait_def.name));
+        self.indent += 1;
+        for (i, method) in trait_def.methods.iter().enumerate() {
+            if i > 0 {
+                self.output.push('\n');
+            }
+            self.write_trait_method(method);
+        }
+        self.write_comments_before(trait_def.span.end.line);
+        self.indent -= 1;
+        self.write_line("}");
+    }
+
+    fn write_trait_method(&mut self, method: &TraitMethod) {
+        let signature = &method.signature;
+        self.write_comments_before(signature.span.start.line);
+        self.write_indent();
+        self.write_signature(
+            signature.is_const,
+            &signature.name,
+            &signature.generics,
+            &signature.parameters,
+            &signature.return_type,
+        );
+        match &method.default {
+            Some(block) => {
+                self.output.push(' ');
+                self.write_block(block, method.span.end.line);
+            }
+            None => self.write_trailing_comment(signature.span.end.line),
+        }
+        self.output.push('\n');
+    }
+
+    fn write_function(&mut self, function: &Function) {
+        self.write_comments_before(function.span.start.line);
+        self.write_indent();
+        self.write_signature(
+            function.is_const,
+            &function.name,
+            &function.generics,
+            &function.parameters,
+            &function.return_type,
+        );
+        self.output.push(' ');
+        self.write_block(&function.block, function.span.end.line);
+        self.output.push('\n');
+    }
+
+    fn write_signature(
+        &mut self,
+        is_const: bool,
+        name: &str,
+        generics: &[GenericParam],
+        parameters: &[Arg],
+        return_type: &TypeInfo,
+    ) {
+        if is_const {
+            self.output.push_str("const ");
+        }
+        self.output.push_str(&format!("fn {}", name));
+        if !generics.is_empty() {
+            let generics: Vec<String> = generics.iter().map(|g| g.to_string()).collect();
+            self.output.push_str(&format!("<{}>", generics.join(", ")));
+        }
+        self.output.push('(');
+        for (i, arg) in parameters.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(", ");
+            }
+            for attribute in &arg.attributes {
+                self.output.push_str(&format!("{} ", attribute));
+            }
+            self.output.push_str(&format!("{} ", arg.name));
+            self.write_type(&arg.type_info);
+        }
+        self.output.push(')');
+        if !return_type.is_void() {
+            self.output.push(' ');
+            self.write_type(return_type);
+        }
+    }
+
+    fn write_type(&mut self, type_info: &TypeInfo) {
+        if let Some(ty) = &type_info.ty {
+            self.output.push_str(&ty.to_string());
+        }
+    }
+
+    /// `end_line` is the line of the closing brace.
+    fn write_block(&mut self, block: &Block, end_line: usize) {
+        self.output.push_str("{\n");
+        self.indent += 1;
+        for statement in &block.statements {
+            self.write_statement(statement);
+        }
+        self.write_comments_before(end_line);
+        self.indent -= 1;
+        self.write_indent();
+        self.output.push('}');
+    }
+
+    fn write_statement(&mut self, statement: &Statement) {
+        self.write_comments_before(statement.span.start.line);
+        self.write_indent();
+        match &statement.kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(variable),
+                ..
+            }) => self.write_variable(variable),
+            StatementKind::Expression(expression) => self.write_expression(expression),
+            StatementKind::Block(block) => self.write_block(block, statement.span.end.line),
+            StatementKind::Destructure { names, expression } => {
+                self.output.push_str(&names.join(", "));
+                self.output.push_str(" := ");
+                self.write_expression(expression);
+            }
+            StatementKind::Return(value) => {
+                self.output.push_str("return");
+                if let Some(value) = value {
+                    self.output.push(' ');
+                    self.write_expression(value);
+                }
+            }
+            StatementKind::Yield(value) => {
+                self.output.push_str("yield ");
+                self.write_expression(value);
+            }
+            StatementKind::Assert { condition, message } => {
+                self.output.push_str("assert ");
+                self.write_expression(condition);
+                if let Some(message) = message {
+                    self.output.push_str(", ");
+                    self.write_expression(message);
+                }
+            }
+        }
+        self.write_trailing_comment(statement.span.end.line);
+        self.output.push('\n');
+    }
+
+    fn write_variable(&mut self, variable: &Variable) {
+        if variable.is_const {
+            self.output.push_str("const ");
+        }
+        self.output.push_str(&variable.name);
+        if let Some(expression) = &variable.expression {
+            match &variable.type_info {
+                Some(type_info @ TypeInfo { ty: Some(_), .. }) if variable.is_decl => {
+                    self.output.push_str(" :");
+                    if type_info.is_mut {
+                        self.output.push_str("mut ");
+                    }
+                    self.write_type(type_info);
+                    self.output.push_str(" = ");
+                }
+                Some(TypeInfo { is_mut: true, .. }) if variable.is_decl => {
+                    self.output.push_str(" :mut = ")
+                }
+                _ if variable.is_decl => self.output.push_str(" := "),
+                _ => self.output.push_str(" = "),
+            }
+            self.write_expression(expression);
+        }
+    }
+
+    fn write_expression(&mut self, expression: &Expression) {
+        match &expression.kind {
+            ExpressionKind::Literal(literal) => self.output.push_str(&literal.to_string()),
+            ExpressionKind::LiteralNull => self.output.push_str("null"),
+            ExpressionKind::Group { inner } => {
+                self.output.push('(');
+                self.write_expression(inner);
+                self.output.push(')');
+            }
+            ExpressionKind::Tuple(elements) => {
+                self.output.push('(');
+                self.write_expressions(elements);
+                self.output.push(')');
+            }
+            ExpressionKind::Unary { operator, operand } => {
+                self.output.push_str(&operator.to_string());
+                // `- -x`, not `--x`
+                if matches!(&operand.kind, ExpressionKind::Unary { operator: inner, .. } if inner == operator)
+                {
+                    self.output.push(' ');
+                }
+                self.write_expression(operand);
+            }
+            ExpressionKind::Binary {
+                left,
+                right,
+                operator,
+            } => {
+                self.write_expression(left);
+                self.output.push_str(&format!(" {} ", operator));
+                self.write_expression(right);
+            }
+            ExpressionKind::And { left, right } => {
+                self.write_expression(left);
+                self.output.push_str(" && ");
+                self.write_expression(right);
+            }
+            ExpressionKind::Or { left, right } => {
+                self.write_expression(left);
+                self.output.push_str(" || ");
+                self.write_expression(right);
+            }
+            ExpressionKind::Variable(variable) => self.write_variable(variable),
+            ExpressionKind::Path(segments) => self.output.push_str(&segments.join("::")),
+            ExpressionKind::SelfRef => self.output.push_str("self"),
+            ExpressionKind::FieldAccess { object, field } => {
+                self.write_expression(object);
+                self.output.push('.');
+                self.output.push_str(field);
+            }
+            ExpressionKind::Call { callee, arguments } => {
+                self.write_expression(callee);
+                self.output.push('(');
+                self.write_expressions(arguments);
+                self.output.push(')');
+            }
+            ExpressionKind::Index { object, index } => {
+                self.write_expression(object);
+                self.output.push('[');
+                self.write_expression(index);
+                self.output.push(']');
+            }
+            ExpressionKind::Slice { object, range } => {
+                self.write_expression(object);
+                self.output.push('[');
+                if let Some(start) = &range.start {
+                    self.write_expression(start);
+                }
+                self.output.push_str("..");
+                if let Some(end) = &range.end {
+                    self.write_expression(end);
+                }
+                self.output.push(']');
+            }
+            ExpressionKind::Try(inner) => {
+                self.write_expression(inner);
+                self.output.push('?');
+            }
+            ExpressionKind::OptionalAccess { object, field } => {
+                self.write_expression(object);
+                self.output.push_str("?.");
+                self.output.push_str(field);
+            }
+            ExpressionKind::Ternary {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.write_expression(condition);
+                self.output.push_str(" ? ");
+                self.write_expression(then_branch);
+                self.output.push_str(" : ");
+                self.write_expression(else_branch);
+            }
+            ExpressionKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                // Branches have no spans of their own, so comments before
+                // their closing braces are placed by the end of the whole `if`
+                let end_line = expression.span.end.line;
+                self.output.push_str("if ");
+                match condition {
+                    IfCondition::Expr(condition) => self.write_expression(condition),
+                    IfCondition::Binding(variable) => self.write_variable(variable),
+                }
+                self.output.push(' ');
+                self.write_block(then_branch, end_line);
+                if let Some(else_branch) = else_branch {
+                    self.output.push_str(" else ");
+                    self.write_block(else_branch, end_line);
+                }
+            }
+            ExpressionKind::Match { scrutinee, arms } => {
+                self.output.push_str("match ");
+                self.write_expression(scrutinee);
+                self.output.push_str(" {\n");
+                self.indent += 1;
+                for arm in arms {
+                    self.write_comments_before(arm.body.span.start.line);
+                    self.write_indent();
+                    self.output.push_str(&format!("{} => ", arm.pattern));
+                    self.write_expression(&arm.body);
+                    self.write_trailing_comment(arm.body.span.end.line);
+                    self.output.push('\n');
+                }
+                self.write_comments_before(expression.span.end.line);
+                self.indent -= 1;
+                self.write_indent();
+                self.output.push('}');
+            }
+            ExpressionKind::StructLiteral { name, base, fields } => {
+                self.output.push_str(name);
+                if base.is_none() && fields.is_empty() {
+                    self.output.push_str(" {}");
+                    return;
+                }
+                self.output.push_str(" { ");
+                if let Some(base) = base {
+                    self.output.push_str("..");
+                    self.write_expression(base);
+                    if !fields.is_empty() {
+                        self.output.push_str(", ");
+                    }
+                }
+                self.write_field_inits(fields);
+                self.output.push_str(" }");
+            }
+            ExpressionKind::Record(fields) => {
+                self.output.push_str("{ ");
+                self.write_field_inits(fields);
+                self.output.push_str(" }");
+            }
+        }
+    }
+
+    /// Writes comma separated `name := value` fields.
+    fn write_field_inits(&mut self, fields: &[FieldInit]) {
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(", ");
+            }
+            self.output.push_str(&field.name);
+            self.output.push_str(" := ");
+            self.write_expression(&field.value);
+        }
+    }
+
+    /// Writes a comma separated list of expressions.
+    fn write_expressions(&mut self, expressions: &[Expression]) {
+        for (i, expression) in expressions.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(", ");
+            }
+            self.write_expression(expression);
+        }
+    }
+
+    /// Writes every pending comment that starts before `line`, each on its
+    /// own line.
+    fn write_comments_before(&mut self, line: usize) {
+        while self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.span.start.line < line)
+        {
+            let comment = self.comments.pop_front().expect("checked above");
+            self.write_line(&comment.text);
+        }
+    }
+
+    /// Appends a pending comment that starts on `line` to the current line.
+    fn write_trailing_comment(&mut self, line: usize) {
+        if self
+            .comments
+            .front()
+            .is_some_and(|comment| comment.span.start.line == line)
+        {
+            let comment = self.comments.pop_front().expect("checked above");
+            self.output.push(' ');
+            self.output.push_str(&comment.text);
+        }
+    }
+
+    fn write_line(&mut self, line: &str) {
+        self.write_indent();
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    fn write_indent(&mut self) {
+        for _ in 0..self.indent {
+            self.output.push_str(Self::INDENT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::BufReader;
+
+    use lex::lexer::Lexer;
+    use stringreader::StringReader;
+
+    use crate::{Parser, test_util::parse};
+
+    fn round_trip(source: &str) -> String {
+        PrettyPrinter::new().print(&parse(source))
+    }
+
+    fn round_trip_with_comments(source: &str) -> String {
+        let reader = BufReader::new(StringReader::new(source));
+        let (tokens, spans, comments) =
+            Lexer::lex_with_comments(reader, Default::default()).expect("failed to lex");
+        let ast = Parser::parse_with_spans(&tokens, &spans).expect("failed to parse");
+        PrettyPrinter::with_comments(comments).print(&ast)
+    }
+
+    #[test]
+    fn test_print_uncolored_by_default() {
+        let output =
+            PrettyPrinter::new().print(&parse("module Main\nfn main() {\n    x := 1\n}\n"));
+        assert!(!output.contains('\x1b'));
+    }
+
+    #[cfg(feature = "color")]
+    #[test]
+    fn test_print_colored() {
+        let ast = parse("module Main\nfn main() {\n    x := \"hi\"\n}\n");
+        let output = PrettyPrinter::colored().print(&ast);
+
+        assert!(output.starts_with("\x1b[35mmodule\x1b[0m Main\n"));
+        assert!(output.contains("\x1b[35mfn\x1b[0m main()"));
+        assert!(output.contains("x \x1b[33m:=\x1b[0m \x1b[32m\"hi\"\x1b[0m"));
+    }
+
+    fn assert_number_round_trips(number: &str) {
+        let source = format!("module Main\n\nx := {}\n", number);
+        assert_eq!(round_trip(&source), source);
+    }
+
+    #[test]
+    fn test_minified_lexes_like_original() {
+        let source = "module Main\n\nimport \"std/io\"\n\nmodule Util {\n\n    fn id<T>(x T) T {\n        return x\n    }\n}\n\nfn main() {\n    a := f(1, 2)[0..-1]\n    b :mut i32 = a - -1\n    c := if a >= b {\n        Point { x := 1.5, y := a?.y }\n    } else {\n        std::io::read()?\n    }\n}\n";
+        let lex = |source: &str| Lexer::lex(source.as_bytes()).expect("failed to lex");
+
+        let minified = PrettyPrinter::minified().print(&parse(source));
+        assert_eq!(lex(&minified), lex(source));
+        assert!(minified.len() < source.len());
+        assert!(minified.contains("\nb:mut i32=a--1\nc:=if a>=b{Point{x:=1.5,y:=a?.y}\n"));
+    }
+
+    #[test]
+    fn test_round_trip_hex() {
+        assert_number_round_trips("0xFF");
+    }
+
+    #[test]
+    fn test_round_trip_underscores() {
+        assert_number_round_trips("1_000");
+    }
+
+    #[test]
+    fn test_round_trip_exponent() {
+        assert_number_round_trips("1.5e-3");
+        assert_number_round_trips("1.5E-3");
+    }
+
+    #[test]
+    fn test_round_trip_binary() {
+        assert_number_round_trips("0b1010");
+    }
+
+    #[test]
+    fn test_round_trip_function() {
+        let source =
+            "module Main\n\nfn add(a i32, b i32) i32 {\n    sum := a + 0x10 * (b - 1_000)\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_compound_types() {
+        let source = "module Main\n\nfn split(xs []i32, y i32?) (i32, Map<string, i32>) {\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_module_statements() {
+        let source = "module Main\n\nLIMIT := 1\n\nprintln(\"hi\")\nobj.method()\n\nfn main() {\n    obj.method()\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_generics() {
+        let source = "module Main\n\ntrait Sorter {\n    fn sort<T: Ord>(items []T) []T\n}\n\nfn insert<K: Hash, V>(key K, value V) {\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_parameter_attributes() {
+        let source = "module Main\n\nfn greet(@required name string, count i32) {\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_match() {
+        let source = "module Main\n\nfn main() {\n    x := match y {\n        0 => \"zero\"\n        1 | 2 => \"few\"\n        3..10 => \"some\"\n        n => n\n        _ => \"many\"\n    }\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_unary() {
+        let source = "module Main\n\nfn main() {\n    a := +5\n    b := - -a\n    c := -+b\n    d := ~~c\n}\n";
+        assert_eq!(round_trip(source), source.replace("~~", "~ ~"));
+    }
+
+    #[test]
+    fn test_round_trip_optional_access() {
+        let source = "module Main\n\nfn main() {\n    name := user?.profile?.name()\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_imports() {
+        let source = "module Main\n\nimport \"std/io\"\nimport std.math\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_gated_imports() {
+        let source =
+            "module Main\n\n@cfg(os = \"linux\", debug) import \"std/linux\"\nimport std.math\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_module_aliases() {
+        let source = "module Main\n\nimport std.net\n\nmodule Net = std.net\nmodule Io = io\n\nmodule Util {\n\n    module Fmt = std.fmt\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_selective_imports() {
+        let source = "module Main\n\nimport \"std/math\" {\n    sqrt,\n    pow,\n}\nimport std.io { print }\n";
+        assert_eq!(
+            round_trip(source),
+            "module Main\n\nimport \"std/math\" { sqrt, pow }\nimport std.io { print }\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_enum() {
+        let source =
+            "module Main\n\nenum Status {\n    Ok = 0 // success\n    Err = 1\n    Unknown\n}\n";
+        assert_eq!(round_trip_with_comments(source), source);
+        assert_eq!(
+            round_trip("module Main\nenum Status { Ok, Err = 2 }\n"),
+            "module Main\n\nenum Status {\n    Ok\n    Err = 2\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_comments() {
+        let source = "// The entry point\nmodule Main\n\nimport \"std/io\" // for printf\n\n// Answer\nANSWER := 42\n\n// Helpers\nmodule Util {\n\n    fn id(x i32) i32 {\n        y := x\n        // trailing\n    }\n}\n\nfn main() {\n    // first\n    tmp := 1 // one\n    {\n        // nested\n    }\n}\n// end\n";
+        assert_eq!(round_trip_with_comments(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_destructure() {
+        let source = "module Main\n\nfn main() {\n    a, b := (1, 2)\n    c, d := pair(a)\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_struct_literal() {
+        let source = "module Main\n\nfn main() {\n    a := Point { x := 1, y := 2 }\n    b := Point { ..a, x := 5 }\n    c := Point { ..a }\n    d := Empty {}\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_path() {
+        let source = "module Main\n\nfn main() {\n    std::io::println(math::PI.value)\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_record() {
+        let source = "module Main\n\nfn main() {\n    a := { x := 1, y := { z := 2 } }\n    b := f({ x := a.x })\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_typed_declarations() {
+        let source = "module Main\n\nfn main() {\n    a := 1\n    b :i32 = 2\n    c :mut = 3\n    d :mut Map<string, i32> = e\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_yield() {
+        let source = "module Main\n\nfn count(n i32) {\n    yield n\n    yield (n + 1) * 2\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_assert() {
+        let source = "module Main\n\nfn check(x i32) {\n    assert x > 0\n    assert x < 10, \"too big\"\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_impl() {
+        let source = "module Main\n\nimpl Point {\n    fn x() f64 {\n    }\n\n    fn y() f64 {\n    }\n}\n\nimpl Empty {\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_if_expression() {
+        let source = "module Main\n\nfn main() {\n    x := if a > b {\n        a\n    } else {\n        b\n    }\n    y := 1 + if x {\n        2\n    }\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_if_binding() {
+        let source =
+            "module Main\n\nfn main() {\n    if x := optional() {\n        use(x)\n    }\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_complement() {
+        let source = "module Main\n\nfn main() {\n    y := ~x + 1\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_print_adds_empty_parens() {
+        let source = "module Main\n\nfn value i32 {\n}\n";
+        assert_eq!(round_trip(source), "module Main\n\nfn value() i32 {\n}\n");
+    }
+
+    #[test]
+    fn test_round_trip_trait() {
+        let source = "module Main\n\ntrait Comparable {\n    fn compare(other Self) i32\n\n    fn equals(other Self) bool {\n        same := 1\n    }\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn test_round_trip_struct() {
+        let source = "module Main\n\nstruct Pair(i32, i32)\n\nstruct Point {\n    x i32\n    @skip y i32\n}\n";
+        assert_eq!(round_trip(source), source);
+    }
+}
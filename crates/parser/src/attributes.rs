@@ -0,0 +1,93 @@
+use crate::{ParserError, functions::reject_keyword, token_stream::TokenStream};
+use lex::token::{Literal, Operator, Token};
+use std::fmt;
+
+/// An `@name` annotation on the item that follows it, with the arguments
+/// of `@name(...)` if it has any.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub arguments: Vec<AttributeArgument>,
+}
+
+impl fmt::Display for Attribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}", self.name)?;
+        if !self.arguments.is_empty() {
+            let arguments: Vec<String> = self.arguments.iter().map(|a| a.to_string()).collect();
+            write!(f, "({})", arguments.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A `key` or `key = "value"` argument, as in `@cfg(os = "linux")`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeArgument {
+    pub key: String,
+    pub value: Option<Literal>,
+}
+
+impl fmt::Display for AttributeArgument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} = {}", self.key, value),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
+pub struct AttributeParser;
+
+impl AttributeParser {
+    /// Consumes any attributes before an item, such as the `@required` of
+    /// `@required name string`.
+    pub fn parse(stream: &mut TokenStream) -> Result<Vec<Attribute>, ParserError> {
+        let mut attributes = Vec::new();
+        while stream.try_consume(Token::At).is_some() {
+            reject_keyword(stream)?;
+            let name = stream.consume_identifier()?;
+            let arguments = match stream.try_consume(Token::OpenParen) {
+                Some(_) => Self::parse_arguments(stream)?,
+                None => Vec::new(),
+            };
+            attributes.push(Attribute { name, arguments });
+        }
+        Ok(attributes)
+    }
+
+    /// Comma separated arguments up to and including the closing `)`
+    fn parse_arguments(stream: &mut TokenStream) -> Result<Vec<AttributeArgument>, ParserError> {
+        let mut arguments = Vec::new();
+        if stream.try_consume(Token::CloseParen).is_some() {
+            return Ok(arguments);
+        }
+
+        loop {
+            reject_keyword(stream)?;
+            let key = stream.consume_identifier()?;
+            let value = match stream.try_consume(Token::Operator(Operator::Reassign)) {
+                Some(_) => match stream.current()?.clone() {
+                    Token::Literal(literal) => {
+                        stream.advance(1)?;
+                        Some(literal)
+                    }
+                    token => {
+                        return Err(ParserError::expected(
+                            token,
+                            &[Token::Literal(Literal::String(String::new()))],
+                        ));
+                    }
+                },
+                None => None,
+            };
+            arguments.push(AttributeArgument { key, value });
+
+            if stream.try_consume(Token::Comma).is_none() {
+                stream.consume(Token::CloseParen)?;
+                break;
+            }
+        }
+        Ok(arguments)
+    }
+}
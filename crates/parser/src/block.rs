@@ -1,5 +1,8 @@
 use crate::{
-    ParserContext, ast::Statement, parser_error::ParserError, statement::StatementParser,
+    ParserContext,
+    ast::{Expression, ExpressionKind, Statement, StatementKind, Variable},
+    parser_error::ParserError,
+    statement::StatementParser,
     token_stream::TokenStream,
 };
 
@@ -10,6 +13,25 @@ pub struct Block {
     pub statements: Vec<Statement>,
 }
 
+impl Block {
+    /// The value a block evaluates to when used as an expression: its last
+    /// statement, unless that statement declares or assigns a variable.
+    pub fn value(&self) -> Option<&Expression> {
+        match &self.statements.last()?.kind {
+            StatementKind::Expression(Expression {
+                kind:
+                    ExpressionKind::Variable(Variable {
+                        expression: Some(_),
+                        ..
+                    }),
+                ..
+            }) => None,
+            StatementKind::Expression(expression) => Some(expression),
+            _ => None,
+        }
+    }
+}
+
 pub struct BlockParser;
 
 impl BlockParser {
@@ -17,6 +39,7 @@ impl BlockParser {
         // Consume opening brace
         stream.consume(Token::OpenBrace)?;
         stream.try_consume(Token::Newline);
+        ctx.enter_scope();
 
         let mut statements = Vec::new();
 
@@ -26,14 +49,56 @@ impl BlockParser {
                     stream.advance(1)?;
                     break;
                 }
+                // Left behind by statements that end in a block
+                Some(Token::Newline) => stream.advance(1)?,
                 Some(_) => {
-                    let statement = StatementParser::parse(ctx, stream)?;
-                    statements.push(statement);
+                    let scope_depth = ctx.scope_depth();
+                    match StatementParser::parse(ctx, stream) {
+                        Ok(statement) => {
+                            let terminator =
+                                StatementParser::parse_terminator(ctx, stream, &statement);
+                            statements.push(statement);
+                            match terminator {
+                                Ok(()) => {}
+                                // The statement itself parsed, so there is
+                                // nothing to skip
+                                Err(error) if ctx.is_recovering() => ctx.recover(error, stream),
+                                Err(error) => return Err(error),
+                            }
+                        }
+                        // A scope the statement opened before failing is
+                        // still open
+                        Err(error) if ctx.is_recovering() => {
+                            ctx.exit_scopes_to(scope_depth);
+                            ctx.recover(error, stream);
+                            Self::skip_statement(stream);
+                        }
+                        Err(error) => return Err(error),
+                    }
                 }
                 None => break,
             }
         }
 
+        ctx.exit_scope();
         Ok(Block { statements })
     }
+
+    /// Skips the rest of a statement that failed to parse: up to the end of
+    /// its line, or the `}` closing this block, including any nested braces.
+    fn skip_statement(stream: &mut TokenStream) {
+        let mut depth = 0usize;
+        stream.skip_to(|token| match token {
+            Token::Newline | Token::CloseBrace if depth == 0 => true,
+            Token::OpenBrace => {
+                depth += 1;
+                false
+            }
+            Token::CloseBrace => {
+                depth -= 1;
+                false
+            }
+            _ => false,
+        });
+    }
 }
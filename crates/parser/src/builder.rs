@@ -0,0 +1,624 @@
+//! Fluent builders for constructing ASTs by hand.
+//!
+//! Every node built here gets a default span, so a built AST compares equal
+//! to one produced by `Parser::parse` from the equivalent source.
+//!
+//! ```
+//! use lex::token::Operator;
+//! use parser::builder::{ExprBuilder, FunctionBuilder, ModuleBuilder, VariableBuilder};
+//!
+//! let module = ModuleBuilder::new("Main")
+//!     .function(FunctionBuilder::new("main").variable(VariableBuilder::declare(
+//!         "a",
+//!         ExprBuilder::binary(ExprBuilder::number("5"), Operator::Plus, ExprBuilder::number("1")),
+//!     )))
+//!     .build();
+//!
+//! assert_eq!(module.functions[0].name, "main");
+//! ```
+
+use crate::{
+    ast::{Expression, ExpressionKind, Statement, StatementKind, Variable},
+    block::Block,
+    enums::Enum,
+    functions::{Arg, Function, Type, TypeInfo},
+    impls::Impl,
+    modules::{Import, ImportPath, Module},
+    structs::Struct,
+    traits::Trait,
+};
+use lex::{
+    span::Span,
+    token::{Keyword, Literal, Operator, Token},
+};
+
+#[derive(Debug, Clone)]
+pub struct ModuleBuilder {
+    name: String,
+    imports: Vec<ImportPath>,
+    modules: Vec<ModuleBuilder>,
+    functions: Vec<Function>,
+    impls: Vec<Impl>,
+    traits: Vec<Trait>,
+    structs: Vec<Struct>,
+    enums: Vec<Enum>,
+    variables: Vec<Variable>,
+    statements: Vec<Statement>,
+}
+
+impl ModuleBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        ModuleBuilder {
+            name: name.into(),
+            imports: Vec::new(),
+            modules: Vec::new(),
+            functions: Vec::new(),
+            impls: Vec::new(),
+            traits: Vec::new(),
+            structs: Vec::new(),
+            enums: Vec::new(),
+            variables: Vec::new(),
+            statements: Vec::new(),
+        }
+    }
+
+    /// `import "path"`
+    pub fn import(mut self, path: impl Into<String>) -> Self {
+        self.imports.push(ImportPath::File(path.into()));
+        self
+    }
+
+    /// `import a.b.c`
+    pub fn import_module(mut self, segments: &[&str]) -> Self {
+        self.imports.push(ImportPath::Module(
+            segments.iter().map(|s| s.to_string()).collect(),
+        ));
+        self
+    }
+
+    /// Adds a nested module. Its name is qualified with the parent's name on
+    /// `build`, matching what the parser produces.
+    pub fn module(mut self, module: ModuleBuilder) -> Self {
+        self.modules.push(module);
+        self
+    }
+
+    pub fn function(mut self, function: impl Into<Function>) -> Self {
+        self.functions.push(function.into());
+        self
+    }
+
+    pub fn implementation(mut self, implementation: impl Into<Impl>) -> Self {
+        self.impls.push(implementation.into());
+        self
+    }
+
+    pub fn trait_def(mut self, trait_def: Trait) -> Self {
+        self.traits.push(trait_def);
+        self
+    }
+
+    pub fn struct_def(mut self, struct_def: Struct) -> Self {
+        self.structs.push(struct_def);
+        self
+    }
+
+    pub fn enum_def(mut self, enum_def: Enum) -> Self {
+        self.enums.push(enum_def);
+        self
+    }
+
+    pub fn variable(mut self, variable: impl Into<Variable>) -> Self {
+        self.variables.push(variable.into());
+        self
+    }
+
+    /// Adds a top-level statement other than a variable declaration.
+    pub fn statement(mut self, statement: impl Into<Statement>) -> Self {
+        self.statements.push(statement.into());
+        self
+    }
+
+    pub fn build(self) -> Module {
+        let name = self.name.clone();
+        self.build_qualified(name)
+    }
+
+    fn build_qualified(self, name: String) -> Module {
+        Module {
+            decl: Token::Keyword(Keyword::Module),
+            decl_span: Span::default(),
+            imports: self
+                .imports
+                .into_iter()
+                .map(|path| Import {
+                    path,
+                    items: vec![],
+                    cfg: None,
+                    decl: Token::Keyword(Keyword::Import),
+                    decl_span: Span::default(),
+                })
+                .collect(),
+            aliases: vec![],
+            modules: self
+                .modules
+                .into_iter()
+                .map(|module| {
+                    let inner = format!("{}.{}", name, module.name);
+                    module.build_qualified(inner)
+                })
+                .collect(),
+            functions: self.functions,
+            impls: self.impls,
+            traits: self.traits,
+            structs: self.structs,
+            enums: self.enums,
+            variables: self.variables,
+            statements: self.statements,
+            name,
+            span: Span::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FunctionBuilder {
+    name: String,
+    is_const: bool,
+    parameters: Vec<Arg>,
+    return_type: Option<Type>,
+    statements: Vec<Statement>,
+}
+
+impl FunctionBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        FunctionBuilder {
+            name: name.into(),
+            is_const: false,
+            parameters: Vec::new(),
+            return_type: None,
+            statements: Vec::new(),
+        }
+    }
+
+    pub fn constant(mut self) -> Self {
+        self.is_const = true;
+        self
+    }
+
+    pub fn parameter(mut self, name: impl Into<String>, ty: Type) -> Self {
+        self.parameters.push(Arg {
+            attributes: vec![],
+            name: Token::Identifier(name.into()),
+            type_info: TypeInfo {
+                ty: Some(ty),
+                is_mut: false,
+            },
+        });
+        self
+    }
+
+    /// Sets the return type. Functions without one return `void`.
+    pub fn returns(mut self, ty: Type) -> Self {
+        self.return_type = Some(ty);
+        self
+    }
+
+    pub fn statement(mut self, statement: impl Into<Statement>) -> Self {
+        self.statements.push(statement.into());
+        self
+    }
+
+    pub fn variable(self, variable: impl Into<Variable>) -> Self {
+        let expression = Expression::from(ExpressionKind::Variable(variable.into()));
+        self.statement(StatementKind::Expression(expression))
+    }
+
+    pub fn build(self) -> Function {
+        Function {
+            decl: Token::Keyword(Keyword::Fn),
+            decl_span: Span::default(),
+            is_const: self.is_const,
+            name: self.name,
+            name_span: Span::default(),
+            generics: Vec::new(),
+            parameters: self.parameters,
+            return_type: match self.return_type {
+                Some(ty) => TypeInfo {
+                    ty: Some(ty),
+                    is_mut: false,
+                },
+                None => TypeInfo::void(),
+            },
+            block: Block {
+                statements: self.statements,
+            },
+            span: Span::default(),
+        }
+    }
+}
+
+impl From<FunctionBuilder> for Function {
+    fn from(builder: FunctionBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImplBuilder {
+    type_name: String,
+    functions: Vec<Function>,
+}
+
+impl ImplBuilder {
+    pub fn new(type_name: impl Into<String>) -> Self {
+        ImplBuilder {
+            type_name: type_name.into(),
+            functions: Vec::new(),
+        }
+    }
+
+    pub fn function(mut self, function: impl Into<Function>) -> Self {
+        self.functions.push(function.into());
+        self
+    }
+
+    pub fn build(self) -> Impl {
+        Impl {
+            decl: Token::Keyword(Keyword::Impl),
+            decl_span: Span::default(),
+            type_name: self.type_name,
+            functions: self.functions,
+            span: Span::default(),
+        }
+    }
+}
+
+impl From<ImplBuilder> for Impl {
+    fn from(builder: ImplBuilder) -> Self {
+        builder.build()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VariableBuilder {
+    variable: Variable,
+}
+
+impl VariableBuilder {
+    /// `name := expression`
+    pub fn declare(name: impl Into<String>, expression: Expression) -> Self {
+        VariableBuilder {
+            variable: Variable {
+                name: name.into(),
+                is_decl: true,
+                is_const: false,
+                expression: Some(Box::new(expression)),
+                type_info: Some(TypeInfo {
+                    ty: None,
+                    is_mut: false,
+                }),
+            },
+        }
+    }
+
+    /// `name = expression`
+    pub fn assign(name: impl Into<String>, expression: Expression) -> Self {
+        VariableBuilder {
+            variable: Variable {
+                name: name.into(),
+                is_decl: false,
+                is_const: false,
+                expression: Some(Box::new(expression)),
+                type_info: None,
+            },
+        }
+    }
+
+    /// `name :mut = expression`. Only meaningful for declarations.
+    pub fn mutable(mut self) -> Self {
+        if let Some(type_info) = self.variable.type_info.as_mut() {
+            type_info.is_mut = true;
+        }
+        self
+    }
+
+    /// `const name := expression`. Only meaningful for declarations.
+    pub fn constant(mut self) -> Self {
+        self.variable.is_const = true;
+        self
+    }
+
+    pub fn build(self) -> Variable {
+        self.variable
+    }
+}
+
+impl From<VariableBuilder> for Variable {
+    fn from(builder: VariableBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Shorthand constructors for expression nodes.
+pub struct ExprBuilder;
+
+impl ExprBuilder {
+    pub fn literal(literal: Literal) -> Expression {
+        ExpressionKind::Literal(literal).into()
+    }
+
+    pub fn number(number: impl Into<String>) -> Expression {
+        Self::literal(Literal::Number(number.into()))
+    }
+
+    pub fn string(string: impl Into<String>) -> Expression {
+        Self::literal(Literal::String(string.into()))
+    }
+
+    pub fn boolean(value: bool) -> Expression {
+        Self::literal(Literal::Boolean(value))
+    }
+
+    pub fn null() -> Expression {
+        ExpressionKind::LiteralNull.into()
+    }
+
+    pub fn var(name: impl Into<String>) -> Expression {
+        ExpressionKind::Variable(Variable {
+            name: name.into(),
+            is_decl: false,
+            is_const: false,
+            expression: None,
+            type_info: None,
+        })
+        .into()
+    }
+
+    pub fn unary(operator: Operator, operand: Expression) -> Expression {
+        ExpressionKind::Unary {
+            operator,
+            operand: Box::new(operand),
+        }
+        .into()
+    }
+
+    /// `left operator right`. `&&` and `||` build `and` and `or`, as the
+    /// parser does.
+    pub fn binary(left: Expression, operator: Operator, right: Expression) -> Expression {
+        match operator {
+            Operator::And => Self::and(left, right),
+            Operator::Or => Self::or(left, right),
+            operator => ExpressionKind::Binary {
+                left: Box::new(left),
+                right: Box::new(right),
+                operator,
+            }
+            .into(),
+        }
+    }
+
+    pub fn and(left: Expression, right: Expression) -> Expression {
+        ExpressionKind::And {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+        .into()
+    }
+
+    pub fn or(left: Expression, right: Expression) -> Expression {
+        ExpressionKind::Or {
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+        .into()
+    }
+
+    pub fn group(inner: Expression) -> Expression {
+        ExpressionKind::Group {
+            inner: Box::new(inner),
+        }
+        .into()
+    }
+
+    pub fn call(callee: Expression, arguments: Vec<Expression>) -> Expression {
+        ExpressionKind::Call {
+            callee: Box::new(callee),
+            arguments,
+        }
+        .into()
+    }
+
+    pub fn field(object: Expression, field: impl Into<String>) -> Expression {
+        ExpressionKind::FieldAccess {
+            object: Box::new(object),
+            field: field.into(),
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{ast::Ast, test_util};
+
+    fn parse(path: &str) -> Module {
+        let source = std::fs::read_to_string(path).expect("failed to load test source");
+        test_util::parse(&source).root.unwrap()
+    }
+
+    #[test]
+    fn test_build_math_example() {
+        let built = ModuleBuilder::new("Main")
+            .function(
+                FunctionBuilder::new("main").variable(VariableBuilder::declare(
+                    "a",
+                    ExprBuilder::binary(
+                        ExprBuilder::number("5"),
+                        Operator::Plus,
+                        ExprBuilder::number("1"),
+                    ),
+                )),
+            )
+            .build();
+
+        assert_eq!(built, parse("../../examples/math.aeon"));
+    }
+
+    #[test]
+    fn test_build_modules_example() {
+        let built = ModuleBuilder::new("Calculator")
+            .import("std/math")
+            .variable(VariableBuilder::declare("PI", ExprBuilder::number("3")))
+            .module(
+                ModuleBuilder::new("Utils").function(
+                    FunctionBuilder::new("square")
+                        .parameter("x", Type::named("f64"))
+                        .returns(Type::named("f64"))
+                        .variable(VariableBuilder::declare(
+                            "result",
+                            ExprBuilder::binary(
+                                ExprBuilder::var("x"),
+                                Operator::Star,
+                                ExprBuilder::var("x"),
+                            ),
+                        )),
+                ),
+            )
+            .function(
+                FunctionBuilder::new("main").variable(VariableBuilder::declare(
+                    "answer",
+                    ExprBuilder::number("42"),
+                )),
+            )
+            .build();
+
+        assert_eq!(built, parse("../../examples/modules.aeon"));
+    }
+
+    #[test]
+    fn test_build_return_type() {
+        assert!(FunctionBuilder::new("f").build().return_type.is_void());
+        assert!(
+            FunctionBuilder::new("f")
+                .returns(Type::Void)
+                .build()
+                .return_type
+                .is_void()
+        );
+        assert!(
+            !FunctionBuilder::new("f")
+                .returns(Type::named("void"))
+                .build()
+                .return_type
+                .is_void()
+        );
+    }
+
+    #[test]
+    fn test_build_matches_hand_built() {
+        let hand_built = Ast {
+            root: Some(Module {
+                decl: Token::Keyword(Keyword::Module),
+                decl_span: Span::default(),
+                name: "Main".to_string(),
+                imports: vec![],
+                aliases: vec![],
+                modules: vec![],
+                functions: vec![Function {
+                    decl: Token::Keyword(Keyword::Fn),
+                    decl_span: Span::default(),
+                    is_const: true,
+                    name: "negate".to_string(),
+                    name_span: Span::default(),
+                    generics: vec![],
+                    parameters: vec![Arg {
+                        attributes: vec![],
+                        name: Token::Identifier("n".to_string()),
+                        type_info: TypeInfo {
+                            ty: Some(Type::named("i32")),
+                            is_mut: false,
+                        },
+                    }],
+                    return_type: TypeInfo {
+                        ty: Some(Type::named("i32")),
+                        is_mut: false,
+                    },
+                    block: Block {
+                        statements: vec![
+                            StatementKind::Expression(
+                                ExpressionKind::Variable(Variable {
+                                    name: "m".to_string(),
+                                    is_decl: true,
+                                    is_const: false,
+                                    expression: Some(Box::new(
+                                        ExpressionKind::Unary {
+                                            operator: Operator::Minus,
+                                            operand: Box::new(
+                                                ExpressionKind::Variable(Variable {
+                                                    name: "n".to_string(),
+                                                    is_decl: false,
+                                                    is_const: false,
+                                                    expression: None,
+                                                    type_info: None,
+                                                })
+                                                .into(),
+                                            ),
+                                        }
+                                        .into(),
+                                    )),
+                                    type_info: Some(TypeInfo {
+                                        ty: None,
+                                        is_mut: true,
+                                    }),
+                                })
+                                .into(),
+                            )
+                            .into(),
+                        ],
+                    },
+                    span: Span::default(),
+                }],
+                impls: vec![],
+                traits: vec![],
+                structs: vec![],
+                enums: vec![],
+                variables: vec![Variable {
+                    name: "LIMIT".to_string(),
+                    is_decl: true,
+                    is_const: true,
+                    expression: Some(Box::new(
+                        ExpressionKind::Literal(Literal::Number("10".to_string())).into(),
+                    )),
+                    type_info: Some(TypeInfo {
+                        ty: None,
+                        is_mut: false,
+                    }),
+                }],
+                statements: vec![],
+                span: Span::default(),
+            }),
+        };
+
+        let built = ModuleBuilder::new("Main")
+            .variable(VariableBuilder::declare("LIMIT", ExprBuilder::number("10")).constant())
+            .function(
+                FunctionBuilder::new("negate")
+                    .constant()
+                    .parameter("n", Type::named("i32"))
+                    .returns(Type::named("i32"))
+                    .variable(
+                        VariableBuilder::declare(
+                            "m",
+                            ExprBuilder::unary(Operator::Minus, ExprBuilder::var("n")),
+                        )
+                        .mutable(),
+                    ),
+            )
+            .build();
+
+        assert_eq!(Ast { root: Some(built) }, hand_built);
+    }
+}
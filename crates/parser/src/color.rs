@@ -0,0 +1,84 @@
+//! ANSI highlighting of printed source, for `PrettyPrinter::colored`.
+
+use lex::{
+    lexer::{Lexer, LexerConfig},
+    token::TokenKind,
+};
+
+const KEYWORD: &str = "\x1b[35m";
+const LITERAL: &str = "\x1b[32m";
+const OPERATOR: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+fn color(kind: TokenKind) -> Option<&'static str> {
+    match kind {
+        TokenKind::Keyword => Some(KEYWORD),
+        TokenKind::Literal => Some(LITERAL),
+        TokenKind::Operator => Some(OPERATOR),
+        TokenKind::Identifier | TokenKind::Punctuation | TokenKind::Newline | TokenKind::Eof => {
+            None
+        }
+    }
+}
+
+/// Wraps each keyword, literal and operator in `source` in the escape code
+/// for its kind. Comments and whitespace are left as they are. Source that
+/// doesn't lex is returned unchanged.
+pub(crate) fn colorize(source: &str) -> String {
+    let Ok((tokens, spans)) = Lexer::lex_with_spans(source.as_bytes(), LexerConfig::default())
+    else {
+        return source.to_string();
+    };
+
+    let mut highlights = tokens
+        .iter()
+        .zip(&spans)
+        .filter_map(|(token, span)| Some((color(token.kind())?, span)))
+        .peekable();
+    let mut output = String::with_capacity(source.len());
+    // The open escape code and where it ends
+    let mut open: Option<(&str, usize)> = None;
+    for (offset, c) in source.char_indices() {
+        if let Some((code, end)) = open
+            && end == offset
+        {
+            // Adjacent tokens of the same kind, such as the `..` and `-` of
+            // `0..-1`, share one escape code
+            match highlights.next_if(|(next, span)| *next == code && span.start.offset == offset) {
+                Some((_, span)) => open = Some((code, span.end.offset)),
+                None => {
+                    output.push_str(RESET);
+                    open = None;
+                }
+            }
+        }
+        if open.is_none()
+            && let Some((code, span)) = highlights.next_if(|(_, span)| span.start.offset == offset)
+        {
+            output.push_str(code);
+            open = Some((code, span.end.offset));
+        }
+        output.push(c);
+    }
+    if open.is_some() {
+        output.push_str(RESET);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_colorize() {
+        assert_eq!(
+            colorize("x := 1 // one\n"),
+            format!("x {OPERATOR}:={RESET} {LITERAL}1{RESET} // one\n")
+        );
+        assert_eq!(
+            colorize("fn main() {}"),
+            format!("{KEYWORD}fn{RESET} main() {{}}")
+        );
+    }
+}
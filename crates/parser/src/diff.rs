@@ -0,0 +1,174 @@
+use crate::{
+    ast::{Ast, PrettyPrinter},
+    functions::Function,
+    modules::Module,
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A difference between two versions of an AST, keyed by qualified name
+/// such as `Main.Util` or `Main.Util.id`. Methods of an `impl` and default
+/// methods of a trait count as functions named after their type or trait,
+/// as in `Main.Point::distance`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AstChange {
+    ModuleAdded(String),
+    ModuleRemoved(String),
+    FunctionAdded(String),
+    FunctionRemoved(String),
+    FunctionModified(String),
+}
+
+/// Report which modules and functions were added, removed or modified
+/// between `old` and `new`, so a watcher only has to recompile what changed.
+///
+/// Functions are compared by their printed source, so moving a function
+/// without editing it is not a modification.
+pub fn diff(old: &Ast, new: &Ast) -> Vec<AstChange> {
+    let old = SymbolTable::from_ast(old);
+    let new = SymbolTable::from_ast(new);
+    let mut changes = Vec::new();
+
+    for name in &old.modules {
+        if !new.modules.contains(name) {
+            changes.push(AstChange::ModuleRemoved(name.clone()));
+        }
+    }
+    for name in &new.modules {
+        if !old.modules.contains(name) {
+            changes.push(AstChange::ModuleAdded(name.clone()));
+        }
+    }
+
+    for (name, source) in &old.functions {
+        match new.functions.get(name) {
+            None => changes.push(AstChange::FunctionRemoved(name.clone())),
+            Some(updated) if updated != source => {
+                changes.push(AstChange::FunctionModified(name.clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    for name in new.functions.keys() {
+        if !old.functions.contains_key(name) {
+            changes.push(AstChange::FunctionAdded(name.clone()));
+        }
+    }
+
+    changes
+}
+
+/// Every module, function and method in an AST by qualified name.
+/// Functions and methods map to their printed source.
+#[derive(Debug, Default)]
+struct SymbolTable {
+    modules: BTreeSet<String>,
+    functions: BTreeMap<String, String>,
+}
+
+impl SymbolTable {
+    fn from_ast(ast: &Ast) -> Self {
+        let mut table = SymbolTable::default();
+        if let Some(module) = &ast.root {
+            table.insert_module(module);
+        }
+        table
+    }
+
+    fn insert_module(&mut self, module: &Module) {
+        // Nested module names are already fully qualified
+        self.modules.insert(module.name.clone());
+        for function in &module.functions {
+            self.insert_function(format!("{}.{}", module.name, function.name), function);
+        }
+        for implementation in &module.impls {
+            for function in &implementation.functions {
+                let name = format!(
+                    "{}.{}::{}",
+                    module.name, implementation.type_name, function.name
+                );
+                self.insert_function(name, function);
+            }
+        }
+        for trait_def in &module.traits {
+            for method in trait_def.methods.iter().filter(|m| m.default.is_some()) {
+                let name = format!(
+                    "{}.{}::{}",
+                    module.name, trait_def.name, method.signature.name
+                );
+                let source = PrettyPrinter::new().print_trait_method(method);
+                self.functions.insert(name, source);
+            }
+        }
+        for inner in &module.modules {
+            self.insert_module(inner);
+        }
+    }
+
+    fn insert_function(&mut self, name: String, function: &Function) {
+        let source = PrettyPrinter::new().print_function(function);
+        self.functions.insert(name, source);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::parse;
+
+    #[test]
+    fn test_diff_unchanged() {
+        let source = "module Main\nfn main() {\n    x := 1\n}\n";
+        assert_eq!(diff(&parse(source), &parse(source)), vec![]);
+    }
+
+    #[test]
+    fn test_diff_added_function() {
+        let old = parse("module Main\nfn main() {\n}\n");
+        let new = parse("module Main\nfn main() {\n}\nfn helper() {\n}\n");
+        assert_eq!(
+            diff(&old, &new),
+            vec![AstChange::FunctionAdded("Main.helper".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_diff_removed_module() {
+        let old = parse("module Main\nmodule Util {\n    fn id(x i32) i32 {\n    }\n}\n");
+        let new = parse("module Main\n");
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                AstChange::ModuleRemoved("Main.Util".to_string()),
+                AstChange::FunctionRemoved("Main.Util.id".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_modified_function_body() {
+        let old = parse("module Main\nfn main() {\n    x := 1\n}\nfn other() {\n}\n");
+        let new = parse("module Main\n\nfn other() {\n}\nfn main() {\n    x := 2\n}\n");
+        assert_eq!(
+            diff(&old, &new),
+            vec![AstChange::FunctionModified("Main.main".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_diff_methods() {
+        let old = parse(
+            "module Main\nimpl Point {\n    fn norm() i32 {\n        1\n    }\n}\ntrait Shape {\n    fn area() i32 {\n        0\n    }\n    fn name() string\n}\n",
+        );
+        let new = parse(
+            "module Main\nimpl Point {\n    fn norm() i32 {\n        2\n    }\n    fn zero() i32 {\n        0\n    }\n}\ntrait Shape {\n    fn name() string\n}\n",
+        );
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                AstChange::FunctionModified("Main.Point::norm".to_string()),
+                AstChange::FunctionRemoved("Main.Shape::area".to_string()),
+                AstChange::FunctionAdded("Main.Point::zero".to_string()),
+            ]
+        );
+    }
+}
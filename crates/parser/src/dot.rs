@@ -0,0 +1,179 @@
+//! Graphviz DOT export of an AST, for teaching and debugging.
+//!
+//! `dot -Tsvg` renders the output; each node is labelled with its kind and
+//! has an edge to each of its children.
+
+use crate::{
+    ast::{Ast, Expression, ExpressionKind, Statement, StatementKind, Variable},
+    block::Block,
+    functions::Function,
+    impls::Impl,
+    modules::Module,
+    traits::{Trait, TraitMethod},
+    visitor::{
+        Visitor, walk_ast, walk_block, walk_expression, walk_function, walk_impl, walk_module,
+        walk_statement, walk_trait, walk_trait_method, walk_variable,
+    },
+};
+
+/// Renders `ast` as a DOT `digraph`. Nodes are numbered `n0`, `n1`, ... in
+/// the order they are visited.
+pub fn to_dot(ast: &Ast) -> String {
+    let mut exporter = DotExporter::default();
+    walk_ast(&mut exporter, ast);
+
+    let mut output = String::from("digraph Ast {\n");
+    for line in exporter.lines {
+        output.push_str("    ");
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output.push_str("}\n");
+    output
+}
+
+#[derive(Default)]
+struct DotExporter {
+    lines: Vec<String>,
+    /// The nodes being visited, innermost last
+    parents: Vec<usize>,
+    next_id: usize,
+}
+
+impl DotExporter {
+    /// Adds a node under the current parent and visits its children with
+    /// `walk`.
+    fn node(&mut self, label: &str, walk: impl FnOnce(&mut Self)) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.lines
+            .push(format!("n{} [label=\"{}\"];", id, escape(label)));
+        if let Some(parent) = self.parents.last() {
+            self.lines.push(format!("n{} -> n{};", parent, id));
+        }
+
+        self.parents.push(id);
+        walk(self);
+        self.parents.pop();
+    }
+}
+
+impl<'a> Visitor<'a> for DotExporter {
+    fn visit_module(&mut self, module: &'a Module) {
+        self.node(&format!("Module {}", module.name), |this| {
+            walk_module(this, module)
+        });
+    }
+
+    fn visit_impl(&mut self, implementation: &'a Impl) {
+        self.node(&format!("Impl {}", implementation.type_name), |this| {
+            walk_impl(this, implementation)
+        });
+    }
+
+    fn visit_trait(&mut self, trait_def: &'a Trait) {
+        self.node(&format!("Trait {}", trait_def.name), |this| {
+            walk_trait(this, trait_def)
+        });
+    }
+
+    fn visit_trait_method(&mut self, method: &'a TraitMethod) {
+        self.node(&format!("Method {}", method.signature.name), |this| {
+            walk_trait_method(this, method)
+        });
+    }
+
+    fn visit_function(&mut self, function: &'a Function) {
+        self.node(&format!("Function {}", function.name), |this| {
+            walk_function(this, function)
+        });
+    }
+
+    fn visit_block(&mut self, block: &'a Block) {
+        self.node("Block", |this| walk_block(this, block));
+    }
+
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        let label = match &statement.kind {
+            StatementKind::Expression(_) => "Expression".to_string(),
+            StatementKind::Block(_) => "Block".to_string(),
+            StatementKind::Destructure { names, .. } => format!("Destructure {}", names.join(", ")),
+            StatementKind::Return(_) => "Return".to_string(),
+            StatementKind::Assert { .. } => "Assert".to_string(),
+            StatementKind::Yield(_) => "Yield".to_string(),
+        };
+        self.node(&label, |this| walk_statement(this, statement));
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        let label = match &expression.kind {
+            // Labelled by `visit_variable`
+            ExpressionKind::Variable(_) => return walk_expression(self, expression),
+            ExpressionKind::Literal(literal) => format!("Literal {}", literal),
+            ExpressionKind::LiteralNull => "Null".to_string(),
+            ExpressionKind::Group { .. } => "Group".to_string(),
+            ExpressionKind::Tuple(_) => "Tuple".to_string(),
+            ExpressionKind::Unary { operator, .. } => format!("Unary {}", operator),
+            ExpressionKind::Binary { operator, .. } => format!("Binary {}", operator),
+            ExpressionKind::And { .. } => "And".to_string(),
+            ExpressionKind::Or { .. } => "Or".to_string(),
+            ExpressionKind::SelfRef => "Self".to_string(),
+            ExpressionKind::Path(segments) => format!("Path {}", segments.join("::")),
+            ExpressionKind::FieldAccess { field, .. } => format!("FieldAccess .{}", field),
+            ExpressionKind::OptionalAccess { field, .. } => format!("OptionalAccess ?.{}", field),
+            ExpressionKind::Call { .. } => "Call".to_string(),
+            ExpressionKind::Index { .. } => "Index".to_string(),
+            ExpressionKind::Slice { .. } => "Slice".to_string(),
+            ExpressionKind::Try(_) => "Try".to_string(),
+            ExpressionKind::Ternary { .. } => "Ternary".to_string(),
+            ExpressionKind::If { .. } => "If".to_string(),
+            ExpressionKind::Match { .. } => "Match".to_string(),
+            ExpressionKind::StructLiteral { name, .. } => format!("StructLiteral {}", name),
+            ExpressionKind::Record(_) => "Record".to_string(),
+        };
+        self.node(&label, |this| walk_expression(this, expression));
+    }
+
+    fn visit_variable(&mut self, variable: &'a Variable) {
+        let label = match (variable.is_decl, &variable.expression) {
+            (true, _) => format!("Declare {}", variable.name),
+            (false, Some(_)) => format!("Assign {}", variable.name),
+            (false, None) => format!("Variable {}", variable.name),
+        };
+        self.node(&label, |this| walk_variable(this, variable));
+    }
+}
+
+/// Escapes `label` for a double-quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::parse;
+
+    #[test]
+    fn test_to_dot_expression() {
+        let dot = to_dot(&parse("module Main\nfn main() {\n    x := a + 1\n}\n"));
+
+        assert_eq!(
+            dot,
+            "digraph Ast {\n    n0 [label=\"Module Main\"];\n    n1 [label=\"Function main\"];\n    n0 -> n1;\n    n2 [label=\"Block\"];\n    n1 -> n2;\n    n3 [label=\"Expression\"];\n    n2 -> n3;\n    n4 [label=\"Declare x\"];\n    n3 -> n4;\n    n5 [label=\"Binary +\"];\n    n4 -> n5;\n    n6 [label=\"Variable a\"];\n    n5 -> n6;\n    n7 [label=\"Literal 1\"];\n    n5 -> n7;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_nesting() {
+        let dot = to_dot(&parse(
+            "module Main\nmodule Util {\n    fn greet() {\n        print(\"hi\")\n    }\n}\n",
+        ));
+
+        assert!(dot.contains("n1 [label=\"Module Main.Util\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("n2 [label=\"Function greet\"];"));
+        assert!(dot.contains("n1 -> n2;"));
+        assert!(dot.contains("[label=\"Literal \\\"hi\\\"\"];"));
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    ParserContext,
+    ast::{Ast, Expression},
+    expression::ExpressionParser,
+    functions::reject_keyword,
+    modules::Module,
+    parser_error::ParserError,
+    token_stream::TokenStream,
+};
+use lex::{
+    lexer::Comment,
+    span::Span,
+    token::{Keyword, Operator, Token},
+};
+
+/// `enum Status { Ok = 0, Err = 1 }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enum {
+    pub decl: Token,
+    pub decl_span: Span,
+    pub name: String,
+    /// Separated by commas or newlines
+    pub variants: Vec<EnumVariant>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    /// The discriminant after `=`, if it is given explicitly
+    pub value: Option<Expression>,
+    /// The text of a `//` comment after the variant on its line, filled in
+    /// by `Ast::attach_docs`
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+pub struct EnumParser;
+
+impl EnumParser {
+    pub fn parse(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Enum, ParserError> {
+        let start = stream.position();
+        let decl = stream.consume_exact(Token::Keyword(Keyword::Enum))?;
+        let decl_span = stream.span_at(start);
+
+        reject_keyword(stream)?;
+        let name = stream.consume_identifier()?;
+        let variants = Self::parse_variants(ctx, stream)?;
+
+        Ok(Enum {
+            decl,
+            decl_span,
+            name,
+            variants,
+            span: stream.span_from(start),
+        })
+    }
+
+    /// `{ Ok = 0, Err }`, from the `{` up to and including the `}`
+    fn parse_variants(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Vec<EnumVariant>, ParserError> {
+        stream.consume(Token::OpenBrace)?;
+        let mut variants = Vec::new();
+        loop {
+            match stream.peek() {
+                Some(Token::Newline | Token::Comma) => stream.advance(1)?,
+                Some(Token::CloseBrace) => {
+                    stream.advance(1)?;
+                    break;
+                }
+                Some(_) => {
+                    let start = stream.position();
+                    reject_keyword(stream)?;
+                    let name = stream.consume_identifier()?;
+                    let value = match stream.try_consume(Token::Operator(Operator::Reassign)) {
+                        Some(_) => Some(ExpressionParser::parse(ctx, stream)?),
+                        None => None,
+                    };
+                    variants.push(EnumVariant {
+                        name,
+                        value,
+                        doc: None,
+                        span: stream.span_from(start),
+                    });
+
+                    // A discriminant takes the line break after it
+                    match stream.peek() {
+                        _ if stream.previous() == Ok(&Token::Newline) => {}
+                        Some(Token::Newline | Token::Comma | Token::CloseBrace) => {}
+                        Some(token) => {
+                            return Err(ParserError::expected(
+                                token.clone(),
+                                &[Token::Comma, Token::CloseBrace],
+                            ));
+                        }
+                        None => return Err(ParserError::UnexpectedEndOfInput),
+                    }
+                }
+                None => return Err(ParserError::UnexpectedEndOfInput),
+            }
+        }
+        Ok(variants)
+    }
+}
+
+impl Ast {
+    /// Sets the `doc` of every enum variant followed on its line by one of
+    /// `comments`, as returned by `Lexer::lex_with_comments`. The AST should
+    /// come from `Parser::parse_with_spans`, since comments are matched by
+    /// line.
+    pub fn attach_docs(&mut self, comments: &[Comment]) {
+        if let Some(module) = &mut self.root {
+            attach_module_docs(module, comments);
+        }
+    }
+}
+
+fn attach_module_docs(module: &mut Module, comments: &[Comment]) {
+    for variant in module.enums.iter_mut().flat_map(|e| &mut e.variants) {
+        let end = variant.span.end;
+        variant.doc = comments
+            .iter()
+            .find(|comment| comment.span.start.line == end.line && comment.span.start >= end)
+            .map(|comment| comment.text.trim_start_matches('/').trim().to_string());
+    }
+    for inner in &mut module.modules {
+        attach_module_docs(inner, comments);
+    }
+}
@@ -1,11 +1,14 @@
 use crate::{
     ParserContext,
-    ast::{Expression, Variable},
+    ast::{Expression, ExpressionKind, FieldInit, IfCondition, MatchArm, Range, Variable},
+    block::BlockParser,
+    functions::TypeInfo,
     parser_error::ParserError,
+    pattern::{Pattern, PatternParser},
     token_stream::TokenStream,
 };
 
-use lex::token::Token;
+use lex::token::{Keyword, Operator, Token};
 
 pub struct ExpressionParser;
 
@@ -14,139 +17,628 @@ impl ExpressionParser {
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
     ) -> Result<Expression, ParserError> {
-        let result = Self::equality(ctx, stream);
+        let result = Self::ternary(ctx, stream);
         stream.try_consume(Token::Newline);
         result
     }
 
-    fn equality(
+    /// Every nested expression starts here, so this is where nesting depth
+    /// is limited.
+    fn ternary(
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
     ) -> Result<Expression, ParserError> {
-        let mut expr = Self::comparison(ctx, stream)?;
-        while stream.current()?.is_equality() {
-            stream.advance(1)?;
-            let Token::Operator(operator) = stream.previous()?.clone() else {
-                return Err(ParserError::UnexpectedToken(stream.previous()?.clone()));
-            };
+        ctx.nested(|ctx| Self::ternary_unbounded(ctx, stream))
+    }
 
-            let right = Self::comparison(ctx, stream)?;
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+    fn ternary_unbounded(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Expression, ParserError> {
+        let start = stream.position();
+        let condition = Self::binary(ctx, stream, 0)?;
+        if stream
+            .try_consume(Token::Operator(Operator::Question))
+            .is_none()
+        {
+            return Ok(condition);
         }
+        Self::ternary_branches(ctx, stream, start, condition)
+    }
 
-        Ok(expr)
+    /// `then : else`, after the `?`. Kept out of `ternary_unbounded`, like
+    /// `postfix` out of `call`, so that nesting through the operand doesn't
+    /// also hold this frame on the stack.
+    fn ternary_branches(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+        condition: Expression,
+    ) -> Result<Expression, ParserError> {
+        let then_branch = Self::ternary(ctx, stream)?;
+        stream.consume(Token::Operator(Operator::Colon))?;
+        let else_branch = Self::ternary(ctx, stream)?;
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            ExpressionKind::Ternary {
+                condition: Box::new(condition),
+                then_branch: Box::new(then_branch),
+                else_branch: Box::new(else_branch),
+            },
+            stream.span_from(start),
+        ))
     }
 
-    fn comparison(
+    /// Binary operators, by precedence climbing: operators binding at
+    /// `level` or tighter are consumed here, each taking a right operand
+    /// parsed one level tighter than itself, so they are left associative,
+    /// apart from the right-associative `^`, whose right operand is parsed
+    /// at its own level. Nesting costs one frame however many levels there
+    /// are.
+    fn binary(
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
+        level: u8,
     ) -> Result<Expression, ParserError> {
-        let mut expr = Self::term(ctx, stream)?;
-        while stream.current()?.is_comparison() {
+        let start = stream.position();
+        let expr = Self::unary(ctx, stream)?;
+        Self::binary_operators(ctx, stream, level, start, expr)
+    }
+
+    /// The operators binding at `level` or tighter after `expr`, the left
+    /// operand that started at `start`
+    fn binary_operators(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        level: u8,
+        start: usize,
+        mut expr: Expression,
+    ) -> Result<Expression, ParserError> {
+        while let Some(Token::Operator(operator)) = stream.peek()
+            && let Some(binding) = Self::binding(operator)
+            && binding >= level
+        {
+            let operator = operator.clone();
             stream.advance(1)?;
-            let Token::Operator(operator) = stream.previous()?.clone() else {
-                return Err(ParserError::UnexpectedToken(stream.previous()?.clone()));
+            // A right-associative operator takes the rest of a chain like
+            // `2 ^ 3 ^ 2` as its right operand, recursing once per operator
+            let right = if operator.is_right_associative() {
+                ctx.nested(|ctx| Self::binary(ctx, stream, binding))?
+            } else {
+                Self::binary(ctx, stream, binding + 1)?
             };
-
-            let right = Self::term(ctx, stream)?;
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
+            let (left, right) = (Box::new(expr), Box::new(right));
+            let kind = match operator {
+                Operator::Or => ExpressionKind::Or { left, right },
+                Operator::And => ExpressionKind::And { left, right },
+                operator => ExpressionKind::Binary {
+                    left,
+                    operator,
+                    right,
+                },
             };
+            expr = Expression::with_id(ctx.next_node_id(), kind, stream.span_from(start));
         }
 
         Ok(expr)
     }
 
-    fn term(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Expression, ParserError> {
-        let mut expr = Self::factor(ctx, stream)?;
-        while stream.current()?.is_term() {
-            stream.advance(1)?;
-            let Token::Operator(operator) = stream.previous()?.clone() else {
-                return Err(ParserError::UnexpectedToken(stream.previous()?.clone()));
-            };
+    /// How tightly a binary operator binds, from `||` at 0 through `&&`,
+    /// equality, comparison and `* / %` up to the exponent `^`. Its operands
+    /// are unary expressions, so `-2 ^ 2` is `(-2) ^ 2`.
+    fn binding(operator: &Operator) -> Option<u8> {
+        match operator {
+            Operator::Or => Some(0),
+            Operator::And => Some(1),
+            Operator::Equal | Operator::NotEqual => Some(2),
+            Operator::Less | Operator::Greater | Operator::LessEqual | Operator::GreaterEqual => {
+                Some(3)
+            }
+            Operator::Plus | Operator::Minus => Some(4),
+            Operator::Star | Operator::Slash | Operator::Percent => Some(5),
+            Operator::Caret => Some(6),
+            _ => None,
+        }
+    }
 
-            let right = Self::factor(ctx, stream)?;
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+    fn unary(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Expression, ParserError> {
+        if stream.current()?.is_unary() {
+            return Self::unary_operator(ctx, stream);
         }
 
-        Ok(expr)
+        Self::call(ctx, stream)
     }
 
-    fn factor(
+    /// `-x`, `+x` or `~x`, from the operator
+    fn unary_operator(
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
     ) -> Result<Expression, ParserError> {
-        let mut expr = Self::unary(ctx, stream)?;
-        while stream.current()?.is_factor() {
-            stream.advance(1)?;
-            let Token::Operator(operator) = stream.previous()?.clone() else {
-                return Err(ParserError::UnexpectedToken(stream.previous()?.clone()));
-            };
+        let start = stream.position();
+        stream.advance(1)?;
+        let Token::Operator(operator) = stream.previous()?.clone() else {
+            return Err(ParserError::unexpected(stream.previous()?.clone()));
+        };
 
-            let right = Self::unary(ctx, stream)?;
-            expr = Expression::Binary {
-                left: Box::new(expr),
-                operator,
-                right: Box::new(right),
-            };
+        let operand = Box::new(ctx.nested(|ctx| Self::unary(ctx, stream))?);
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            ExpressionKind::Unary { operator, operand },
+            stream.span_from(start),
+        ))
+    }
+
+    /// Postfix operators: field access, calls and the `?` try operator.
+    ///
+    /// `?` is shared with the ternary operator and optional access. A `?`
+    /// immediately followed by `.` is optional access, e.g. `user?.name`. A
+    /// `?` that is followed by an expression and then a `:` is left for
+    /// `ternary`; any other `?` is a postfix try, e.g. `parse()?`.
+    fn call(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Expression, ParserError> {
+        let start = stream.position();
+        let expr = Self::primary(ctx, stream)?;
+        Self::postfix(ctx, stream, start, expr)
+    }
+
+    /// The postfix operators after `expr`, which started at `start`
+    fn postfix(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+        mut expr: Expression,
+    ) -> Result<Expression, ParserError> {
+        loop {
+            match stream.peek() {
+                Some(Token::Dot) => {
+                    stream.advance(1)?;
+                    let field = stream.consume_identifier()?;
+                    expr = Expression::with_id(
+                        ctx.next_node_id(),
+                        ExpressionKind::FieldAccess {
+                            object: Box::new(expr),
+                            field,
+                        },
+                        stream.span_from(start),
+                    );
+                }
+                Some(Token::OpenParen) => {
+                    stream.advance(1)?;
+                    let arguments =
+                        ctx.with_struct_literals(true, |ctx| Self::arguments(ctx, stream))?;
+                    expr = Expression::with_id(
+                        ctx.next_node_id(),
+                        ExpressionKind::Call {
+                            callee: Box::new(expr),
+                            arguments,
+                        },
+                        stream.span_from(start),
+                    );
+                }
+                Some(Token::OpenBracket) => {
+                    stream.advance(1)?;
+                    expr = Self::index(ctx, stream, expr, start)?;
+                }
+                Some(Token::Operator(Operator::Question))
+                    if stream.peek_next() == Some(&Token::Dot) =>
+                {
+                    stream.advance(2)?;
+                    let field = stream.consume_identifier()?;
+                    expr = Expression::with_id(
+                        ctx.next_node_id(),
+                        ExpressionKind::OptionalAccess {
+                            object: Box::new(expr),
+                            field,
+                        },
+                        stream.span_from(start),
+                    );
+                }
+                Some(Token::Operator(Operator::Question)) => {
+                    if Self::is_ternary(stream) {
+                        break;
+                    }
+                    stream.advance(1)?;
+                    expr = Expression::with_id(
+                        ctx.next_node_id(),
+                        ExpressionKind::Try(Box::new(expr)),
+                        stream.span_from(start),
+                    );
+                }
+                _ => break,
+            }
         }
 
         Ok(expr)
     }
 
-    fn unary(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Expression, ParserError> {
-        if stream.current()?.is_unary() {
-            stream.advance(1)?;
-            let Token::Operator(operator) = stream.previous()?.clone() else {
-                return Err(ParserError::UnexpectedToken(stream.previous()?.clone()));
-            };
+    /// Parses the inside of `[ ]` as either an index or a slice. Slices use
+    /// the range operator and may leave either end open: `a[1..3]`, `a[..n]`,
+    /// `a[n..]` and `a[..]`. Indices and bounds are full expressions, so
+    /// `a[-1]` and `a[n - 1]` work too.
+    fn index(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        object: Expression,
+        object_start: usize,
+    ) -> Result<Expression, ParserError> {
+        let start = match stream.peek() {
+            Some(Token::Operator(Operator::Range)) => None,
+            _ => Some(Box::new(Self::ternary(ctx, stream)?)),
+        };
+
+        let kind = match start {
+            Some(index) if stream.peek() != Some(&Token::Operator(Operator::Range)) => {
+                ExpressionKind::Index {
+                    object: Box::new(object),
+                    index,
+                }
+            }
+            start => {
+                stream.consume(Token::Operator(Operator::Range))?;
+                let end = match stream.peek() {
+                    Some(Token::CloseBracket) => None,
+                    _ => Some(Box::new(Self::ternary(ctx, stream)?)),
+                };
+                ExpressionKind::Slice {
+                    object: Box::new(object),
+                    range: Range { start, end },
+                }
+            }
+        };
 
-            return Ok(Expression::Unary {
-                operator,
-                operand: Box::new(Self::unary(ctx, stream)?),
-            });
+        stream.consume(Token::CloseBracket)?;
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            kind,
+            stream.span_from(object_start),
+        ))
+    }
+
+    fn arguments(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Vec<Expression>, ParserError> {
+        let mut arguments = Vec::new();
+        loop {
+            stream.try_consume(Token::Newline);
+            if stream.try_consume(Token::CloseParen).is_some() {
+                break;
+            }
+
+            arguments.push(Self::ternary(ctx, stream)?);
+            stream.try_consume(Token::Newline);
+            if stream.try_consume(Token::Comma).is_none() {
+                stream.consume(Token::CloseParen)?;
+                break;
+            }
+        }
+
+        Ok(arguments)
+    }
+
+    /// Looks past the `?` at the current position to see whether it starts
+    /// the `? then : else` tail of a ternary, i.e. whether a `:` pairs with
+    /// it before the end of the statement or of the enclosing brackets. A
+    /// `?` that isn't followed by an operand never is. Brackets after the
+    /// `?` are skipped whole. A later `?` followed by something that can
+    /// start an operand opens a ternary of its own and takes the next `:`;
+    /// any other `?` is a postfix try.
+    fn is_ternary(stream: &TokenStream) -> bool {
+        let tokens = stream.remaining();
+        if !Self::starts_operand(&tokens[1..]) {
+            return false;
         }
+        let mut depth = 0usize;
+        let mut open = 0usize;
+        for (i, token) in tokens.iter().enumerate().skip(1) {
+            match token {
+                Token::OpenParen | Token::OpenBracket | Token::OpenBrace => depth += 1,
+                Token::CloseParen | Token::CloseBracket | Token::CloseBrace => {
+                    if depth == 0 {
+                        return false;
+                    }
+                    depth -= 1;
+                }
+                _ if depth > 0 => {}
+                Token::Newline | Token::Semicolon | Token::Comma | Token::Eof => return false,
+                Token::Operator(Operator::Question)
+                    if tokens.get(i + 1..).is_some_and(Self::starts_operand) =>
+                {
+                    open += 1
+                }
+                Token::Operator(Operator::Colon) if open == 0 => return true,
+                Token::Operator(Operator::Colon) => open -= 1,
+                _ => {}
+            }
+        }
+        false
+    }
 
-        Self::primary(ctx, stream)
+    /// Whether an operand can start at the first of `tokens`, so that a `?`
+    /// before it may be the `?` of a ternary. A `{` only starts one if it
+    /// opens a record.
+    fn starts_operand(tokens: &[Token]) -> bool {
+        match tokens.first() {
+            Some(Token::OpenBrace) => Self::is_record(&mut TokenStream::new(&tokens[1..])),
+            Some(token) => {
+                matches!(
+                    token,
+                    Token::Identifier(_)
+                        | Token::Literal(_)
+                        | Token::Keyword(_)
+                        | Token::OpenParen
+                        | Token::OpenBracket
+                ) || token.is_unary()
+            }
+            None => false,
+        }
     }
 
     fn primary(
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
     ) -> Result<Expression, ParserError> {
+        let start = stream.position();
         let token = stream.current()?.clone();
         stream.advance(1)?;
-        match token {
-            Token::OpenParen => Self::grouped(ctx, stream),
-            Token::Literal(literal) => Ok(Expression::Literal(literal.clone())),
-            Token::Identifier(name) => Ok(Expression::Variable(Variable {
+        let kind = match token {
+            // Parentheses allow struct literals again, as in `if (p == Point {}) {`
+            Token::OpenParen => {
+                return ctx.with_struct_literals(true, |ctx| Self::grouped(ctx, stream, start));
+            }
+            Token::Literal(literal) => ExpressionKind::Literal(literal.clone()),
+            Token::Identifier(name)
+                if ctx.struct_literals() && stream.peek() == Some(&Token::OpenBrace) =>
+            {
+                return Self::struct_literal(ctx, stream, start, name);
+            }
+            Token::OpenBrace if ctx.struct_literals() && Self::is_record(stream) => {
+                let fields =
+                    ctx.with_struct_literals(true, |ctx| Self::field_inits(ctx, stream))?;
+                ExpressionKind::Record(fields)
+            }
+            Token::Identifier(name)
+                if stream.peek() == Some(&Token::Operator(Operator::PathSep)) =>
+            {
+                let mut segments = vec![name];
+                while stream
+                    .try_consume(Token::Operator(Operator::PathSep))
+                    .is_some()
+                {
+                    segments.push(stream.consume_identifier()?);
+                }
+                ExpressionKind::Path(segments)
+            }
+            Token::Identifier(name) => ExpressionKind::Variable(Variable {
                 name: name.clone(),
                 is_decl: false,
+                is_const: false,
                 expression: None,
                 type_info: None,
-            })),
-            _ => Err(ParserError::UnexpectedToken(token.clone())),
+            }),
+            Token::Keyword(Keyword::SelfKw) => ExpressionKind::SelfRef,
+            Token::Keyword(Keyword::If) => return Self::if_expression(ctx, stream, start),
+            Token::Keyword(Keyword::Match) => return Self::match_expression(ctx, stream, start),
+            _ => {
+                // Leave the stream on the offending token, as `consume` does
+                stream.restore(start);
+                return Err(ParserError::UnexpectedToken {
+                    found: token,
+                    expected: vec!["expression".to_string()],
+                });
+            }
+        };
+
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            kind,
+            stream.span_from(start),
+        ))
+    }
+
+    /// `if cond { 1 } else { 2 }` or `if x := optional() { x }`, with the
+    /// `if` already consumed. The `else` must follow the closing brace of the
+    /// first branch on the same line. The condition needs no parentheses;
+    /// `if (cond) {` is just a grouped condition.
+    fn if_expression(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+    ) -> Result<Expression, ParserError> {
+        // A binding gets a scope of its own around the then-block
+        ctx.enter_scope();
+        let condition = match stream.window(2).as_slice() {
+            [
+                Some(Token::Identifier(name)),
+                Some(Token::Operator(Operator::Walrus)),
+            ] => {
+                let name = name.clone();
+                stream.advance(2)?;
+                let expression =
+                    ctx.with_struct_literals(false, |ctx| Self::ternary(ctx, stream))?;
+                ctx.declare(&name);
+                IfCondition::Binding(Variable {
+                    name,
+                    is_decl: true,
+                    is_const: false,
+                    expression: Some(Box::new(expression)),
+                    type_info: Some(TypeInfo {
+                        ty: None,
+                        is_mut: false,
+                    }),
+                })
+            }
+            _ => IfCondition::Expr(Box::new(
+                ctx.with_struct_literals(false, |ctx| Self::ternary(ctx, stream))?,
+            )),
+        };
+        let then_branch = BlockParser::parse(ctx, stream)?;
+        ctx.exit_scope();
+        let else_branch = match stream.try_consume(Token::Keyword(Keyword::Else)) {
+            Some(_) => Some(BlockParser::parse(ctx, stream)?),
+            None => None,
+        };
+
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            ExpressionKind::If {
+                condition,
+                then_branch,
+                else_branch,
+            },
+            stream.span_from(start),
+        ))
+    }
+
+    /// `match x { 1 | 2 => a, n => n }`, with the `match` already consumed.
+    /// Arms are separated by newlines or commas.
+    fn match_expression(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+    ) -> Result<Expression, ParserError> {
+        let scrutinee =
+            Box::new(ctx.with_struct_literals(false, |ctx| Self::ternary(ctx, stream))?);
+        stream.consume(Token::OpenBrace)?;
+
+        let mut arms = Vec::new();
+        loop {
+            while stream.try_consume(Token::Newline).is_some() {}
+            if stream.try_consume(Token::CloseBrace).is_some() {
+                break;
+            }
+
+            let pattern = PatternParser::parse(stream)?;
+            stream.consume(Token::Operator(Operator::FatArrow))?;
+            ctx.enter_scope();
+            if let Pattern::Binding(name) = &pattern {
+                ctx.declare(name);
+            }
+            let body = Self::ternary(ctx, stream)?;
+            ctx.exit_scope();
+            arms.push(MatchArm { pattern, body });
+
+            if stream.try_consume(Token::Comma).is_none()
+                && stream.peek() != Some(&Token::CloseBrace)
+            {
+                stream.consume(Token::Newline)?;
+            }
         }
+
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            ExpressionKind::Match { scrutinee, arms },
+            stream.span_from(start),
+        ))
+    }
+
+    /// `Name { ..base, field := value }`, with the name already consumed.
+    /// The `..base` spread is optional and must come first. Fields are
+    /// separated by commas or newlines.
+    fn struct_literal(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+        name: String,
+    ) -> Result<Expression, ParserError> {
+        stream.consume(Token::OpenBrace)?;
+        let (base, fields) = ctx.with_struct_literals(true, |ctx| {
+            while stream.try_consume(Token::Newline).is_some() {}
+            let base = match stream.try_consume(Token::Operator(Operator::Range)) {
+                Some(_) => {
+                    let base = Self::ternary(ctx, stream)?;
+                    Self::field_separator(stream)?;
+                    Some(Box::new(base))
+                }
+                None => None,
+            };
+
+            Ok((base, Self::field_inits(ctx, stream)?))
+        })?;
+
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            ExpressionKind::StructLiteral { name, base, fields },
+            stream.span_from(start),
+        ))
+    }
+
+    /// Whether the `{` just consumed opens a record rather than a block: its
+    /// first entry, on the same line or a later one, must be `name :=`. So
+    /// `{ x := 1 }` is a record wherever an expression is expected, while a
+    /// `{` that starts a statement never gets here and is always a block.
+    /// `{}` has no entries to tell by, so it isn't a record. The stream is
+    /// left untouched.
+    fn is_record(stream: &mut TokenStream) -> bool {
+        let checkpoint = stream.checkpoint();
+        while stream.try_consume(Token::Newline).is_some() {}
+        let is_record = matches!(
+            stream.window(2).as_slice(),
+            [
+                Some(Token::Identifier(_)),
+                Some(Token::Operator(Operator::Walrus))
+            ]
+        );
+        stream.restore(checkpoint);
+        is_record
+    }
+
+    /// `name := value` fields up to and including the closing `}`, separated
+    /// by commas or newlines
+    fn field_inits(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Vec<FieldInit>, ParserError> {
+        let mut fields = Vec::new();
+        loop {
+            while stream.try_consume(Token::Newline).is_some() {}
+            if stream.try_consume(Token::CloseBrace).is_some() {
+                break;
+            }
+
+            let name = stream.consume_identifier()?;
+            stream.consume(Token::Operator(Operator::Walrus))?;
+            let value = Self::ternary(ctx, stream)?;
+            fields.push(FieldInit { name, value });
+            Self::field_separator(stream)?;
+        }
+        Ok(fields)
+    }
+
+    /// The `,` or newline after a struct literal or record entry, unless it is the
+    /// last one before the `}`
+    fn field_separator(stream: &mut TokenStream) -> Result<(), ParserError> {
+        if stream.try_consume(Token::Comma).is_none() && stream.peek() != Some(&Token::CloseBrace) {
+            stream.consume(Token::Newline)?;
+        }
+        Ok(())
     }
 
     fn grouped(
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
+        start: usize,
     ) -> Result<Expression, ParserError> {
         let expr = Self::parse(ctx, stream)?;
+        if stream.peek() != Some(&Token::Comma) {
+            stream.consume(Token::CloseParen)?;
+            return Ok(Expression::with_id(
+                ctx.next_node_id(),
+                ExpressionKind::Group {
+                    inner: Box::new(expr),
+                },
+                stream.span_from(start),
+            ));
+        }
+
+        let mut elements = vec![expr];
+        while stream.try_consume(Token::Comma).is_some() {
+            elements.push(Self::parse(ctx, stream)?);
+        }
         stream.consume(Token::CloseParen)?;
-        Ok(Expression::Group {
-            inner: Box::new(expr),
-        })
+        Ok(Expression::with_id(
+            ctx.next_node_id(),
+            ExpressionKind::Tuple(elements),
+            stream.span_from(start),
+        ))
     }
 }
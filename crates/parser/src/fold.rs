@@ -0,0 +1,418 @@
+//! Constant folding of arithmetic on number literals, such as `1 + 2` to `3`.
+//!
+//! Literals are untyped, so integers are assumed to be `i32`, the default
+//! integer type. Integer arithmetic is done in `i128`, which can't overflow
+//! for `i32` operands, and a result outside the `i32` range is handled by the
+//! `OverflowPolicy`. Float arithmetic is done in `f64`.
+
+use crate::{
+    ast::{Ast, Expression, ExpressionKind, IfCondition, StatementKind, Variable},
+    block::Block,
+    modules::Module,
+};
+use lex::token::{Literal, Operator};
+
+/// What to do when folding an integer expression overflows `i32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Wrap around, so `2147483647 + 1` folds to `-2147483648`
+    Wrap,
+    /// Clamp to the nearest bound, so `2147483647 + 1` folds to `2147483647`
+    Saturate,
+    /// Leave the expression as written, for the runtime to deal with
+    #[default]
+    LeaveUnfolded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FoldOptions {
+    pub overflow: OverflowPolicy,
+}
+
+/// Folds every constant expression in `ast`.
+pub fn fold_constants(ast: &mut Ast, options: &FoldOptions) {
+    if let Some(module) = &mut ast.root {
+        fold_module(module, options);
+    }
+}
+
+fn fold_module(module: &mut Module, options: &FoldOptions) {
+    for variable in &mut module.variables {
+        fold_variable(variable, options);
+    }
+    for statement in &mut module.statements {
+        fold_statement(&mut statement.kind, options);
+    }
+    for variant in module.enums.iter_mut().flat_map(|e| &mut e.variants) {
+        if let Some(value) = &mut variant.value {
+            fold_expression(value, options);
+        }
+    }
+    for inner in &mut module.modules {
+        fold_module(inner, options);
+    }
+    for implementation in &mut module.impls {
+        for function in &mut implementation.functions {
+            fold_block(&mut function.block, options);
+        }
+    }
+    for trait_def in &mut module.traits {
+        for method in &mut trait_def.methods {
+            if let Some(block) = &mut method.default {
+                fold_block(block, options);
+            }
+        }
+    }
+    for function in &mut module.functions {
+        fold_block(&mut function.block, options);
+    }
+}
+
+fn fold_block(block: &mut Block, options: &FoldOptions) {
+    for statement in &mut block.statements {
+        fold_statement(&mut statement.kind, options);
+    }
+}
+
+fn fold_statement(statement: &mut StatementKind, options: &FoldOptions) {
+    match statement {
+        StatementKind::Expression(expression) | StatementKind::Destructure { expression, .. } => {
+            fold_expression(expression, options)
+        }
+        StatementKind::Block(block) => fold_block(block, options),
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                fold_expression(value, options);
+            }
+        }
+        StatementKind::Yield(value) => fold_expression(value, options),
+        StatementKind::Assert { condition, message } => {
+            fold_expression(condition, options);
+            if let Some(message) = message {
+                fold_expression(message, options);
+            }
+        }
+    }
+}
+
+fn fold_variable(variable: &mut Variable, options: &FoldOptions) {
+    if let Some(initializer) = &mut variable.expression {
+        fold_expression(initializer, options);
+    }
+}
+
+/// Folds the constant parts of `expression`, innermost first, keeping the
+/// id and span of each folded node.
+pub fn fold_expression(expression: &mut Expression, options: &FoldOptions) {
+    match &mut expression.kind {
+        ExpressionKind::Literal(_)
+        | ExpressionKind::LiteralNull
+        | ExpressionKind::Path(_)
+        | ExpressionKind::SelfRef => {}
+        ExpressionKind::Group { inner: operand }
+        | ExpressionKind::Unary { operand, .. }
+        | ExpressionKind::FieldAccess {
+            object: operand, ..
+        }
+        | ExpressionKind::OptionalAccess {
+            object: operand, ..
+        }
+        | ExpressionKind::Try(operand) => fold_expression(operand, options),
+        ExpressionKind::Tuple(elements) => {
+            for element in elements {
+                fold_expression(element, options);
+            }
+        }
+        ExpressionKind::Binary { left, right, .. }
+        | ExpressionKind::And { left, right }
+        | ExpressionKind::Or { left, right }
+        | ExpressionKind::Index {
+            object: left,
+            index: right,
+        } => {
+            fold_expression(left, options);
+            fold_expression(right, options);
+        }
+        ExpressionKind::Variable(variable) => fold_variable(variable, options),
+        ExpressionKind::Call { callee, arguments } => {
+            fold_expression(callee, options);
+            for argument in arguments {
+                fold_expression(argument, options);
+            }
+        }
+        ExpressionKind::Slice { object, range } => {
+            fold_expression(object, options);
+            for bound in [&mut range.start, &mut range.end].into_iter().flatten() {
+                fold_expression(bound, options);
+            }
+        }
+        ExpressionKind::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            fold_expression(condition, options);
+            fold_expression(then_branch, options);
+            fold_expression(else_branch, options);
+        }
+        ExpressionKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            match condition {
+                IfCondition::Expr(condition) => fold_expression(condition, options),
+                IfCondition::Binding(variable) => fold_variable(variable, options),
+            }
+            fold_block(then_branch, options);
+            if let Some(else_branch) = else_branch {
+                fold_block(else_branch, options);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            fold_expression(scrutinee, options);
+            for arm in arms {
+                fold_expression(&mut arm.body, options);
+            }
+        }
+        ExpressionKind::StructLiteral { base, fields, .. } => {
+            if let Some(base) = base {
+                fold_expression(base, options);
+            }
+            for field in fields {
+                fold_expression(&mut field.value, options);
+            }
+        }
+        ExpressionKind::Record(fields) => {
+            for field in fields {
+                fold_expression(&mut field.value, options);
+            }
+        }
+    }
+
+    if let Some(value) = evaluate(&expression.kind, options) {
+        expression.kind = ExpressionKind::Literal(value.into_literal());
+    }
+}
+
+/// A folded number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Int(i32),
+    Float(f64),
+}
+
+impl Value {
+    /// A number literal with a decimal point or exponent is a float, as it
+    /// doesn't parse as an integer.
+    fn of(expression: &Expression) -> Option<Value> {
+        let ExpressionKind::Literal(literal) = &expression.kind else {
+            return None;
+        };
+        match literal.as_i64() {
+            Some(int) => i32::try_from(int).ok().map(Value::Int),
+            None => literal.as_f64().map(Value::Float),
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Value::Int(int) => int as f64,
+            Value::Float(float) => float,
+        }
+    }
+
+    fn into_literal(self) -> Literal {
+        match self {
+            Value::Int(int) => Literal::Number(int.to_string()),
+            // `{:?}` keeps the `.0` of a whole float, so it stays a float
+            Value::Float(float) => Literal::Number(format!("{:?}", float)),
+        }
+    }
+}
+
+/// The value of `kind` if its operands are already folded, or `None` if it
+/// can't be folded.
+fn evaluate(kind: &ExpressionKind, options: &FoldOptions) -> Option<Value> {
+    match kind {
+        ExpressionKind::Group { inner } => match Value::of(inner)? {
+            Value::Float(float) => finite(float),
+            value => Some(value),
+        },
+        ExpressionKind::Unary {
+            operator: operator @ (Operator::Minus | Operator::Plus),
+            operand,
+        } => match (operator, Value::of(operand)?) {
+            (Operator::Plus, Value::Int(int)) => Some(Value::Int(int)),
+            (Operator::Plus, Value::Float(float)) => finite(float),
+            (_, Value::Int(int)) => int_result(-(int as i128), options),
+            (_, Value::Float(float)) => finite(-float),
+        },
+        ExpressionKind::Binary {
+            left,
+            operator,
+            right,
+        } => match (Value::of(left)?, Value::of(right)?) {
+            (Value::Int(left), Value::Int(right)) => int_result(
+                int_operation(operator, left as i128, right as i128)?,
+                options,
+            ),
+            (left, right) => float_operation(operator, left.as_f64(), right.as_f64()),
+        },
+        _ => None,
+    }
+}
+
+fn int_operation(operator: &Operator, left: i128, right: i128) -> Option<i128> {
+    match operator {
+        Operator::Plus => Some(left + right),
+        Operator::Minus => Some(left - right),
+        Operator::Star => Some(left * right),
+        // Dividing by zero is left for the program to fail at
+        Operator::Slash => left.checked_div(right),
+        Operator::Percent => left.checked_rem(right),
+        _ => None,
+    }
+}
+
+fn float_operation(operator: &Operator, left: f64, right: f64) -> Option<Value> {
+    let result = match operator {
+        Operator::Plus => left + right,
+        Operator::Minus => left - right,
+        Operator::Star => left * right,
+        Operator::Slash => left / right,
+        Operator::Percent => left % right,
+        _ => return None,
+    };
+    finite(result)
+}
+
+/// `float` as a value, unless it is infinite or NaN, as `x / 0.0`, `x % 0.0`
+/// or an overlong literal like `1e999` are. `into_literal` would print those
+/// as `inf` or `NaN`, which lex back as names rather than numbers.
+fn finite(float: f64) -> Option<Value> {
+    float.is_finite().then_some(Value::Float(float))
+}
+
+/// Fits `result` to an `i32` according to the overflow policy.
+fn int_result(result: i128, options: &FoldOptions) -> Option<Value> {
+    if let Ok(int) = i32::try_from(result) {
+        return Some(Value::Int(int));
+    }
+    match options.overflow {
+        OverflowPolicy::Wrap => Some(Value::Int(result as i32)),
+        OverflowPolicy::Saturate => Some(Value::Int(
+            result.clamp(i32::MIN as i128, i32::MAX as i128) as i32,
+        )),
+        OverflowPolicy::LeaveUnfolded => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::PrettyPrinter, test_util::parse};
+
+    /// Folds the initializer of `x := <source>` and prints it.
+    fn fold(source: &str, overflow: OverflowPolicy) -> String {
+        let source = format!("module Main\nx := {}\n", source);
+        let mut ast = parse(&source);
+        fold_constants(&mut ast, &FoldOptions { overflow });
+
+        let module = ast.root.unwrap();
+        let initializer = module.variables[0].expression.as_ref().unwrap();
+        PrettyPrinter::new().print_expression(initializer)
+    }
+
+    #[test]
+    fn test_fold_arithmetic() {
+        let fold = |source| fold(source, OverflowPolicy::default());
+
+        assert_eq!(fold("1 + 2 * 3"), "7");
+        assert_eq!(fold("(1 + 2) * 3"), "9");
+        assert_eq!(fold("-(4 - 10) % 4"), "2");
+        assert_eq!(fold("7 / 2"), "3");
+        assert_eq!(fold("1.5 * 2"), "3.0");
+        assert_eq!(fold("0xFF + 1"), "256");
+    }
+
+    #[test]
+    fn test_fold_modulo() {
+        let fold = |source| fold(source, OverflowPolicy::default());
+
+        assert_eq!(fold("5 % 2"), "1");
+        assert_eq!(fold("-5 % 2"), "-1");
+        assert_eq!(fold("5.5 % 2.0"), "1.5");
+        assert_eq!(fold("7 % 2.5"), "2.0");
+        assert_eq!(fold("1e1 % 3"), "1.0");
+
+        assert_eq!(fold("5 % 0"), "5 % 0");
+        assert_eq!(fold("5.5 % 0.0"), "5.5 % 0.0");
+    }
+
+    #[test]
+    fn test_fold_leaves_non_finite_floats() {
+        let fold = |source| fold(source, OverflowPolicy::default());
+
+        assert_eq!(fold("(1e999)"), "(1e999)");
+        assert_eq!(fold("-1e999"), "-1e999");
+        assert_eq!(fold("+1e999"), "+1e999");
+        assert_eq!(fold("(1.5)"), "1.5");
+
+        // Only a hand-built AST can spell NaN as a number literal
+        let nan = || {
+            Box::new(Expression::from(ExpressionKind::Literal(Literal::Number(
+                "nan".to_string(),
+            ))))
+        };
+        let mut group = Expression::from(ExpressionKind::Group { inner: nan() });
+        let mut negated = Expression::from(ExpressionKind::Unary {
+            operator: Operator::Minus,
+            operand: nan(),
+        });
+        fold_expression(&mut group, &FoldOptions::default());
+        fold_expression(&mut negated, &FoldOptions::default());
+        assert_eq!(PrettyPrinter::new().print_expression(&group), "(nan)");
+        assert_eq!(PrettyPrinter::new().print_expression(&negated), "-nan");
+    }
+
+    #[test]
+    fn test_fold_leaves_non_constants() {
+        let fold = |source| fold(source, OverflowPolicy::default());
+
+        assert_eq!(fold("a + 1 * 2"), "a + 2");
+        assert_eq!(fold("f(2 - 1)"), "f(1)");
+        assert_eq!(fold("1 / 0"), "1 / 0");
+        assert_eq!(fold("1 == 1"), "1 == 1");
+    }
+
+    #[test]
+    fn test_fold_overflow_wrap() {
+        assert_eq!(fold("2147483647 + 1", OverflowPolicy::Wrap), "-2147483648");
+        assert_eq!(fold("-2147483647 - 2", OverflowPolicy::Wrap), "2147483647");
+    }
+
+    #[test]
+    fn test_fold_overflow_saturate() {
+        assert_eq!(
+            fold("2147483647 + 1", OverflowPolicy::Saturate),
+            "2147483647"
+        );
+        assert_eq!(
+            fold("-2147483647 - 2", OverflowPolicy::Saturate),
+            "-2147483648"
+        );
+    }
+
+    #[test]
+    fn test_fold_overflow_leave_unfolded() {
+        assert_eq!(
+            fold("2147483647 + 1", OverflowPolicy::LeaveUnfolded),
+            "2147483647 + 1"
+        );
+        assert_eq!(
+            fold("2147483640 + (5 + 5)", OverflowPolicy::LeaveUnfolded),
+            "2147483640 + 10"
+        );
+    }
+}
@@ -1,29 +1,143 @@
 use crate::{
     ParserContext, ParserError,
+    attributes::{Attribute, AttributeParser},
     block::{Block, BlockParser},
     token_stream::TokenStream,
 };
-use lex::token::{Keyword, Token};
+use lex::{
+    span::Span,
+    token::{Keyword, Operator, Token},
+};
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Arg {
+    pub attributes: Vec<Attribute>,
     pub name: Token,
     pub type_info: TypeInfo,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct TypeInfo {
-    pub name: Option<String>,
+    /// The declared type, or None if it is left to be inferred
+    pub ty: Option<Type>,
     pub is_mut: bool,
 }
 
+impl TypeInfo {
+    /// The return type of a function that returns nothing, whether written
+    /// as `void` or left out.
+    pub fn void() -> Self {
+        TypeInfo {
+            ty: Some(Type::Void),
+            is_mut: false,
+        }
+    }
+
+    /// Whether this is `TypeInfo::void`. A variable declared without a type
+    /// has none, and isn't void.
+    pub fn is_void(&self) -> bool {
+        self.ty == Some(Type::Void)
+    }
+}
+
+/// A type as written in a declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    /// A name such as `i32` or `Self`
+    Named(String),
+    /// `[]T`
+    Array(Box<Type>),
+    /// `T?`
+    Optional(Box<Type>),
+    /// `(A, B)`
+    Tuple(Vec<Type>),
+    /// `Name<A, B>`
+    Generic { name: String, arguments: Vec<Type> },
+    /// The type of nothing. `void` is a keyword, so it is never `Named`.
+    Void,
+}
+
+impl Type {
+    pub fn named(name: impl Into<String>) -> Self {
+        Type::Named(name.into())
+    }
+
+    pub fn array(element: Type) -> Self {
+        Type::Array(Box::new(element))
+    }
+
+    pub fn optional(inner: Type) -> Self {
+        Type::Optional(Box::new(inner))
+    }
+}
+
+/// The type as it is written, normalized to single spaces, such as
+/// `[]string`, `i32?` or `Map<string, i32>`.
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Array(element) => write!(f, "[]{}", element),
+            Type::Optional(inner) => write!(f, "{}?", inner),
+            Type::Tuple(types) => write!(f, "({})", join(types)),
+            Type::Generic { name, arguments } => write!(f, "{}<{}>", name, join(arguments)),
+            Type::Void => write!(f, "void"),
+        }
+    }
+}
+
+fn join(types: &[Type]) -> String {
+    types
+        .iter()
+        .map(Type::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A type parameter such as `T` or `T: Ord` in `fn sort<T: Ord>(...)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenericParam {
+    pub name: String,
+    pub bound: Option<String>,
+}
+
+impl fmt::Display for GenericParam {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.bound {
+            Some(bound) => write!(f, "{}: {}", self.name, bound),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Function {
+    /// The `fn` keyword, also for `const fn`.
     pub decl: Token,
+    pub decl_span: Span,
+    pub is_const: bool,
     pub name: String,
+    pub name_span: Span,
+    pub generics: Vec<GenericParam>,
     pub parameters: Vec<Arg>,
     pub return_type: TypeInfo,
     pub block: Block,
+    pub span: Span,
+}
+
+/// A function header without its body, as declared in a trait.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSignature {
+    pub decl: Token,
+    pub decl_span: Span,
+    pub is_const: bool,
+    pub name: String,
+    pub name_span: Span,
+    pub generics: Vec<GenericParam>,
+    pub parameters: Vec<Arg>,
+    pub return_type: TypeInfo,
+    pub span: Span,
 }
 
 pub struct FunctionParser;
@@ -32,86 +146,269 @@ impl FunctionParser {
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
     ) -> Result<Function, ParserError> {
-        let decl = stream.consume(Token::Keyword(Keyword::Fn))?;
+        let depth = ctx.depth();
+        Self::parse_function(ctx, stream).map_err(|error| ctx.unwind(depth, error))
+    }
 
-        let name_token = stream.consume(Token::Identifier(String::new()))?;
-        let name = match name_token {
-            Token::Identifier(name) => {
-                ctx.enter_function(name.clone());
-                name
-            }
-            _ => {
-                return Err(ParserError::UnexpectedToken(name_token));
-            }
-        };
+    fn parse_function(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Function, ParserError> {
+        let start = stream.position();
+        let signature = Self::parse_signature(ctx, stream)?;
+        if stream.current()? != &Token::OpenBrace {
+            return Err(ParserError::expected(
+                stream.current()?.clone(),
+                &[Token::OpenBrace],
+            ));
+        }
+        let block = BlockParser::parse(ctx, stream)?;
 
-        stream.consume(Token::OpenParen)?;
+        Ok(Function {
+            decl: signature.decl,
+            decl_span: signature.decl_span,
+            is_const: signature.is_const,
+            name: signature.name,
+            name_span: signature.name_span,
+            generics: signature.generics,
+            parameters: signature.parameters,
+            return_type: signature.return_type,
+            block,
+            span: stream.span_from(start),
+        })
+    }
 
-        let mut parameters = Vec::new();
-        while !stream.is_at_end() {
-            match stream.peek() {
-                Some(Token::CloseParen) => {
-                    stream.advance(1)?;
-                    break;
-                }
-                Some(Token::OpenBrace) => break,
-                _ => {
-                    let arg = parse_arg(stream)?;
-                    parameters.push(arg);
+    /// Parses everything up to, but not including, the function body. The
+    /// function is entered in `ctx` so its parameters are in scope for a
+    /// body that may follow.
+    pub fn parse_signature(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<FunctionSignature, ParserError> {
+        let start = stream.position();
+        let is_const = stream.try_consume(Token::Keyword(Keyword::Const)).is_some();
+        let decl_span = stream.span_at(stream.position());
+        let decl = stream.consume_exact(Token::Keyword(Keyword::Fn))?;
 
-                    // Consume comma if present
-                    stream.try_consume(Token::Comma);
-                }
-            }
-        }
+        reject_keyword(stream)?;
+        let name_span = stream.span_at(stream.position());
+        let name = stream.consume_identifier()?;
+        ctx.check_function_name(&name)?;
+        ctx.enter_function(name.clone());
 
-        // Parse the return type
-        // TODO: This is hacky. Update the parser to convert keywords
-        // and identifiers to a Type enum.
-        let return_type = match stream.peek() {
-            Some(Token::Identifier(_)) => {
-                let type_token = stream.consume(Token::Identifier(String::new()))?;
-                match type_token {
-                    Token::Identifier(type_name) => TypeInfo {
-                        name: Some(type_name),
-                        is_mut: true,
-                    },
-                    _ => unreachable!(),
-                }
+        let generics = match stream.try_consume(Token::Operator(Operator::Less)) {
+            Some(_) => parse_generics(stream)?,
+            None => Vec::new(),
+        };
+
+        // Empty parens may be left out, as in `fn main { }` or
+        // `fn value i32 { }`. A parameter list always starts with `(`, so a
+        // `{` or a type name right after the function name can't be confused
+        // with one. A tuple return type needs the parens: `fn pair() (i32, i32)`.
+        let parameters = match stream.peek() {
+            Some(Token::OpenParen) => {
+                stream.advance(1)?;
+                parse_parameters(ctx, stream)?
             }
-            Some(Token::OpenBrace) => TypeInfo {
-                name: Some("void".to_string()),
-                is_mut: false,
-            },
-            _ => {
-                return Err(ParserError::UnexpectedToken(stream.current()?.clone()));
+            Some(
+                Token::OpenBrace
+                | Token::OpenBracket
+                | Token::Identifier(_)
+                | Token::Keyword(Keyword::SelfKw | Keyword::Void),
+            ) => Vec::new(),
+            Some(token) => {
+                return Err(ParserError::expected(
+                    token.clone(),
+                    &[
+                        Token::OpenParen,
+                        Token::OpenBrace,
+                        Token::Identifier(String::new()),
+                    ],
+                ));
             }
+            None => return Err(ParserError::UnexpectedEndOfInput),
         };
 
-        let block = BlockParser::parse(ctx, stream)?;
+        // Parse the return type, optionally introduced by `->`. Without
+        // either, the function returns void.
+        let has_arrow = stream
+            .try_consume(Token::Operator(Operator::Arrow))
+            .is_some();
+        let ty = if stream.try_consume(Token::Keyword(Keyword::Void)).is_some() {
+            None
+        } else if has_arrow {
+            Some(parse_type(ctx, stream)?)
+        } else {
+            try_parse_type(ctx, stream)?
+        };
+        let return_type = match ty {
+            Some(ty) => TypeInfo {
+                ty: Some(ty),
+                is_mut: false,
+            },
+            None => TypeInfo::void(),
+        };
 
-        Ok(Function {
+        Ok(FunctionSignature {
             decl,
+            decl_span,
+            is_const,
             name,
+            name_span,
+            generics,
             parameters,
             return_type,
-            block,
+            span: stream.span_from(start),
         })
     }
 }
 
-fn parse_arg(stream: &mut TokenStream) -> Result<Arg, ParserError> {
+/// Parses type parameters up to and including the closing `>`. A bound
+/// follows its parameter after a `:`.
+fn parse_generics(stream: &mut TokenStream) -> Result<Vec<GenericParam>, ParserError> {
+    let mut generics = Vec::new();
+    loop {
+        reject_keyword(stream)?;
+        let name = stream.consume_identifier()?;
+        let bound = match stream.try_consume(Token::Operator(Operator::Colon)) {
+            Some(_) => {
+                reject_keyword(stream)?;
+                Some(stream.consume_identifier()?)
+            }
+            None => None,
+        };
+        generics.push(GenericParam { name, bound });
+
+        if stream.try_consume(Token::Comma).is_none() {
+            break;
+        }
+    }
+    stream.consume(Token::Operator(Operator::Greater))?;
+    Ok(generics)
+}
+
+/// Parses parameters up to and including the closing `)`, declaring each
+/// one in the current scope.
+fn parse_parameters(
+    ctx: &mut ParserContext,
+    stream: &mut TokenStream,
+) -> Result<Vec<Arg>, ParserError> {
+    let mut parameters = Vec::new();
+    while !stream.is_at_end() {
+        match stream.peek() {
+            Some(Token::CloseParen) => {
+                stream.advance(1)?;
+                break;
+            }
+            Some(Token::OpenBrace) => break,
+            _ => {
+                let arg = parse_arg(ctx, stream)?;
+                if let Token::Identifier(name) = &arg.name {
+                    ctx.declare(name);
+                }
+                parameters.push(arg);
+
+                // Consume comma if present
+                stream.try_consume(Token::Comma);
+            }
+        }
+    }
+
+    Ok(parameters)
+}
+
+/// Parses a type, failing if none is next.
+pub(crate) fn parse_type(
+    ctx: &mut ParserContext,
+    stream: &mut TokenStream,
+) -> Result<Type, ParserError> {
+    match try_parse_type(ctx, stream)? {
+        Some(ty) => Ok(ty),
+        None => Err(ParserError::expected(
+            stream.current()?.clone(),
+            &[Token::Identifier(String::new())],
+        )),
+    }
+}
+
+/// Consumes a type if one is next: a name such as `i32` or `Self`, a slice
+/// `[]T`, a tuple `(A, B)` or a generic `Name<A, B>`, each optionally
+/// followed by `?`. `Self` lexes as a keyword, but is a valid type.
+fn try_parse_type(
+    ctx: &mut ParserContext,
+    stream: &mut TokenStream,
+) -> Result<Option<Type>, ParserError> {
+    ctx.nested(|ctx| try_parse_type_unbounded(ctx, stream))
+}
+
+fn try_parse_type_unbounded(
+    ctx: &mut ParserContext,
+    stream: &mut TokenStream,
+) -> Result<Option<Type>, ParserError> {
+    let mut ty = match stream.peek() {
+        Some(Token::OpenBracket) => {
+            stream.advance(1)?;
+            stream.consume(Token::CloseBracket)?;
+            Type::array(parse_type(ctx, stream)?)
+        }
+        Some(Token::OpenParen) => {
+            stream.advance(1)?;
+            Type::Tuple(parse_type_list(ctx, stream, Token::CloseParen)?)
+        }
+        Some(token @ (Token::Identifier(_) | Token::Keyword(Keyword::SelfKw))) => {
+            let name = match token {
+                Token::Identifier(name) => name.clone(),
+                _ => "Self".to_string(),
+            };
+            stream.advance(1)?;
+            match stream.try_consume(Token::Operator(Operator::Less)) {
+                Some(_) => Type::Generic {
+                    name,
+                    arguments: parse_type_list(ctx, stream, Token::Operator(Operator::Greater))?,
+                },
+                None => Type::Named(name),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    while stream
+        .try_consume(Token::Operator(Operator::Question))
+        .is_some()
+    {
+        ty = Type::optional(ty);
+    }
+    Ok(Some(ty))
+}
+
+/// Comma separated types up to and including `close`.
+fn parse_type_list(
+    ctx: &mut ParserContext,
+    stream: &mut TokenStream,
+    close: Token,
+) -> Result<Vec<Type>, ParserError> {
+    let mut types = Vec::new();
+    loop {
+        types.push(parse_type(ctx, stream)?);
+        if stream.try_consume(Token::Comma).is_none() {
+            break;
+        }
+    }
+    stream.consume(close)?;
+    Ok(types)
+}
+
+fn parse_arg(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Arg, ParserError> {
+    let attributes = AttributeParser::parse(stream)?;
+
     // Parse argument name
+    reject_keyword(stream)?;
     let name = stream.consume(Token::Identifier(String::new()))?;
 
     // Parse argument type
-    let type_token = stream.consume(Token::Identifier(String::new()))?;
-    let type_info = match type_token {
-        Token::Identifier(type_name) => TypeInfo {
-            name: Some(type_name),
-            is_mut: false,
-        },
-        _ => return Err(ParserError::UnexpectedToken(type_token)),
+    let type_info = TypeInfo {
+        ty: Some(parse_type(ctx, stream)?),
+        is_mut: false,
     };
 
     // Check that we have a valid delimiter after the argument
@@ -119,9 +416,27 @@ fn parse_arg(stream: &mut TokenStream) -> Result<Arg, ParserError> {
         Some(Token::Comma) | Some(Token::CloseParen) => {
             // Don't consume the delimiter - let the caller handle it
         }
-        Some(token) => return Err(ParserError::UnexpectedToken(token.clone())),
+        Some(token) => {
+            return Err(ParserError::expected(
+                token.clone(),
+                &[Token::Comma, Token::CloseParen],
+            ));
+        }
         None => return Err(ParserError::UnexpectedEndOfInput),
     }
 
-    Ok(Arg { name, type_info })
+    Ok(Arg {
+        attributes,
+        name,
+        type_info,
+    })
+}
+
+/// Keywords never lex as identifiers, so give a clearer error than
+/// `UnexpectedToken` when one is used where a name is expected.
+pub(crate) fn reject_keyword(stream: &TokenStream) -> Result<(), ParserError> {
+    match stream.peek() {
+        Some(Token::Keyword(keyword)) => Err(ParserError::ReservedKeyword(keyword.clone())),
+        _ => Ok(()),
+    }
 }
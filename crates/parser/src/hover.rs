@@ -0,0 +1,252 @@
+//! What is under the cursor, for editor hovers.
+
+use lex::{span::Span, token::Token};
+
+use crate::{
+    ast::{Expression, ExpressionKind, PrettyPrinter, Program, Variable},
+    functions::{Function, Type, TypeInfo},
+    modules::Module,
+    node::{Node, NodeIndex},
+    visitor::{Visitor, walk_expression, walk_module},
+};
+
+/// What a hover points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoverKind {
+    /// The name of a function, where it is declared or called
+    Function,
+    /// A use of a function parameter
+    Parameter,
+    /// A variable declaration, or a use of a variable
+    Variable,
+    Statement,
+    Expression,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoverInfo {
+    pub kind: HoverKind,
+    /// The signature of a function, such as `fn add(a i32, b i32) i32`, or
+    /// the declared type of a parameter or variable. `None` for a variable
+    /// whose type is inferred, and for any other node.
+    pub detail: Option<String>,
+    /// The node pointed at
+    pub span: Span,
+}
+
+/// What is at `offset`, counted in bytes from the start of `file`, one of
+/// the files of `program`. Nodes are found by their spans, so `program`
+/// should come from `Parser::parse_files_with_spans`.
+///
+/// A variable resolves to the parameter of the function around it, or else
+/// to its last declaration before `offset` in that function, or else to a
+/// module variable or function of the same name.
+pub fn hover_at(program: &Program, file: &str, offset: usize) -> Option<HoverInfo> {
+    let module = program.files.get(file)?;
+    let mut functions = Vec::new();
+    collect_functions(module, &mut functions);
+    let function = functions
+        .iter()
+        .copied()
+        .find(|function| function.span.contains(offset));
+
+    if let Some(function) = function
+        && function.name_span.contains(offset)
+    {
+        return Some(function_hover(function, function.name_span));
+    }
+
+    let mut index = NodeIndex::default();
+    index.visit_module(module);
+    let node = *index.nodes.get(&index.node_at(offset)?)?;
+    let expression = match node {
+        Node::Statement(statement) => {
+            return Some(HoverInfo {
+                kind: HoverKind::Statement,
+                detail: None,
+                span: statement.span,
+            });
+        }
+        Node::Expression(expression) => expression,
+    };
+    let ExpressionKind::Variable(variable) = &expression.kind else {
+        return Some(HoverInfo {
+            kind: HoverKind::Expression,
+            detail: None,
+            span: expression.span,
+        });
+    };
+
+    let variable_hover = |declaration: &Variable| HoverInfo {
+        kind: HoverKind::Variable,
+        detail: declaration.type_info.as_ref().and_then(type_name),
+        span: expression.span,
+    };
+    if variable.is_decl {
+        return Some(variable_hover(variable));
+    }
+
+    if let Some(function) = function {
+        let parameter = function
+            .parameters
+            .iter()
+            .find(|arg| arg.name == Token::Identifier(variable.name.clone()));
+        if let Some(parameter) = parameter {
+            return Some(HoverInfo {
+                kind: HoverKind::Parameter,
+                detail: type_name(&parameter.type_info),
+                span: expression.span,
+            });
+        }
+
+        let mut declarations = Declarations {
+            name: &variable.name,
+            before: offset,
+            last: None,
+        };
+        declarations.visit_function(function);
+        if let Some(declaration) = declarations.last {
+            return Some(variable_hover(declaration));
+        }
+    }
+
+    if let Some(declaration) = module
+        .variables
+        .iter()
+        .find(|declaration| declaration.name == variable.name)
+    {
+        return Some(variable_hover(declaration));
+    }
+    match functions
+        .iter()
+        .find(|function| function.name == variable.name)
+    {
+        Some(function) => Some(function_hover(function, expression.span)),
+        None => Some(variable_hover(variable)),
+    }
+}
+
+fn function_hover(function: &Function, span: Span) -> HoverInfo {
+    HoverInfo {
+        kind: HoverKind::Function,
+        detail: Some(PrettyPrinter::new().print_signature(function)),
+        span,
+    }
+}
+
+fn type_name(type_info: &TypeInfo) -> Option<String> {
+    type_info.ty.as_ref().map(Type::to_string)
+}
+
+/// The functions of `module`, its impls and its nested modules
+fn collect_functions<'a>(module: &'a Module, functions: &mut Vec<&'a Function>) {
+    functions.extend(&module.functions);
+    for implementation in &module.impls {
+        functions.extend(&implementation.functions);
+    }
+    for inner in &module.modules {
+        collect_functions(inner, functions);
+    }
+}
+
+/// Finds the last declaration of `name` that starts before `before`.
+struct Declarations<'a, 'n> {
+    name: &'n str,
+    before: usize,
+    last: Option<&'a Variable>,
+}
+
+impl<'a> Visitor<'a> for Declarations<'a, '_> {
+    fn visit_module(&mut self, module: &'a Module) {
+        walk_module(self, module);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        if let ExpressionKind::Variable(variable) = &expression.kind
+            && variable.is_decl
+            && variable.name == self.name
+            && expression.span.start.offset < self.before
+        {
+            self.last = Some(variable);
+        }
+        walk_expression(self, expression);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Parser, test_util::lex_with_spans};
+
+    const SOURCE: &str = "module Main\n\nfn add(a i32, b i32) i32 {\n    total :i32 = a + b\n    total\n}\n\nfn main() {\n    sum := add(1, 2)\n}\n";
+
+    fn program(source: &str) -> Program {
+        programs(&[("main.aeon", source)])
+    }
+
+    fn programs(files: &[(&str, &str)]) -> Program {
+        let files: Vec<_> = files
+            .iter()
+            .map(|(filename, source)| {
+                let (tokens, spans) = lex_with_spans(source);
+                (filename.to_string(), tokens, spans)
+            })
+            .collect();
+        Parser::parse_files_with_spans(&files).expect("failed to parse")
+    }
+
+    fn hover(needle: &str) -> Option<HoverInfo> {
+        let offset = SOURCE.find(needle).expect("needle not in source");
+        hover_at(&program(SOURCE), "main.aeon", offset)
+    }
+
+    #[test]
+    fn test_hover_function_name() {
+        let info = hover("add(a").unwrap();
+        assert_eq!(info.kind, HoverKind::Function);
+        assert_eq!(info.detail.as_deref(), Some("fn add(a i32, b i32) i32"));
+
+        // Called, it resolves to the declaration
+        let info = hover("add(1").unwrap();
+        assert_eq!(info.kind, HoverKind::Function);
+        assert_eq!(info.detail.as_deref(), Some("fn add(a i32, b i32) i32"));
+    }
+
+    #[test]
+    fn test_hover_variable_reference() {
+        let info = hover("total\n}").unwrap();
+        assert_eq!(info.kind, HoverKind::Variable);
+        assert_eq!(info.detail.as_deref(), Some("i32"));
+        assert_eq!(info.span.start.line, 5);
+
+        let info = hover("b\n").unwrap();
+        assert_eq!(info.kind, HoverKind::Parameter);
+        assert_eq!(info.detail.as_deref(), Some("i32"));
+
+        // Declared without a type
+        let info = hover("sum").unwrap();
+        assert_eq!(info.kind, HoverKind::Variable);
+        assert_eq!(info.detail, None);
+    }
+
+    #[test]
+    fn test_hover_whitespace() {
+        assert_eq!(hover("    total :i32"), None);
+        assert_eq!(hover("\n\nfn main"), None);
+        assert_eq!(hover_at(&program(SOURCE), "other.aeon", 0), None);
+    }
+
+    #[test]
+    fn test_hover_in_second_file() {
+        let util = "module Util\n\nfn id(x i32) i32 {\n    x\n}\n";
+        let program = programs(&[("main.aeon", SOURCE), ("util.aeon", util)]);
+
+        let info = hover_at(&program, "util.aeon", util.find("id").unwrap()).unwrap();
+        assert_eq!(info.kind, HoverKind::Function);
+        assert_eq!(info.detail.as_deref(), Some("fn id(x i32) i32"));
+
+        let info = hover_at(&program, "util.aeon", util.find("x\n").unwrap()).unwrap();
+        assert_eq!(info.kind, HoverKind::Parameter);
+        assert_eq!(info.span.start.line, 4);
+    }
+}
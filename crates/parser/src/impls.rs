@@ -0,0 +1,69 @@
+use crate::{
+    ParserContext,
+    functions::{Function, FunctionParser, reject_keyword},
+    parser_error::ParserError,
+    token_stream::TokenStream,
+};
+use lex::{
+    span::Span,
+    token::{Keyword, Token},
+};
+
+/// `impl Point { fn distance() f64 { ... } }`, grouping methods under the
+/// type they belong to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Impl {
+    pub decl: Token,
+    pub decl_span: Span,
+    pub type_name: String,
+    pub functions: Vec<Function>,
+    pub span: Span,
+}
+
+pub struct ImplParser;
+
+impl ImplParser {
+    pub fn parse(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Impl, ParserError> {
+        let start = stream.position();
+        let decl = stream.consume_exact(Token::Keyword(Keyword::Impl))?;
+        let decl_span = stream.span_at(start);
+
+        reject_keyword(stream)?;
+        let type_name = stream.consume_identifier()?;
+
+        stream.consume(Token::OpenBrace)?;
+        let mut functions = Vec::new();
+        loop {
+            match stream.peek() {
+                Some(Token::Newline) => stream.advance(1)?,
+                Some(Token::CloseBrace) => {
+                    stream.advance(1)?;
+                    break;
+                }
+                Some(Token::Keyword(Keyword::Fn | Keyword::Const)) => {
+                    functions.push(FunctionParser::parse(ctx, stream)?);
+                    ctx.exit_function();
+                }
+                Some(token) => {
+                    return Err(ParserError::expected(
+                        token.clone(),
+                        &[
+                            Token::Keyword(Keyword::Fn),
+                            Token::Keyword(Keyword::Const),
+                            Token::CloseBrace,
+                        ],
+                    ));
+                }
+                None => return Err(ParserError::UnexpectedEndOfInput),
+            }
+        }
+
+        Ok(Impl {
+            decl,
+            decl_span,
+            type_name,
+            functions,
+            span: stream.span_from(start),
+        })
+    }
+}
@@ -1,27 +1,131 @@
+pub mod analysis;
 pub mod ast;
+pub mod attributes;
 pub mod block;
+pub mod builder;
+#[cfg(feature = "color")]
+mod color;
+pub mod diff;
+pub mod dot;
+pub mod enums;
 pub mod expression;
+pub mod fold;
 pub mod functions;
+pub mod hover;
+pub mod impls;
+pub mod lint;
+mod minify;
 pub mod modules;
+pub mod naming;
+pub mod node;
+pub mod normalize;
 pub mod parser_error;
+pub mod pattern;
+pub mod rename;
+pub mod scope;
 pub mod statement;
+pub mod structs;
 pub mod token_stream;
+pub mod traits;
+pub mod visitor;
 // pub mod variables;
 
+use std::{collections::HashSet, sync::Arc};
+
 use crate::{
-    ast::Ast, modules::ModuleParser, parser_error::ParserError, token_stream::TokenStream,
+    ast::{Ast, Program},
+    modules::ModuleParser,
+    node::NodeId,
+    parser_error::{ContextFrame, ParserError},
+    statement::StatementExtension,
+    token_stream::TokenStream,
 };
-use lex::token::Token;
+use lex::{span::Span, token::Token};
+
+/// The target name that discards a value, as in `_ := sideEffect()`
+pub const DISCARD: &str = "_";
+
+/// How deeply expressions, bare blocks, modules and types may nest by
+/// default. An `if` or a pair of parentheses is one level. Deeper input
+/// fails with `ParserError::RecursionLimitExceeded` rather than overflowing
+/// the stack; this default stays well within the 2 MiB stack of a spawned
+/// thread even in a debug build.
+pub const DEFAULT_RECURSION_LIMIT: usize = 64;
+
+/// What ends a statement, chosen with `Parser::terminator`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Terminator {
+    /// A line break. A `;` after a statement is an error.
+    #[default]
+    Newline,
+    /// A `;`. It may be left out after a statement that ends in a block,
+    /// such as an `if`, and before the `}` or end of input closing the
+    /// statement's block or module.
+    Semicolon,
+    /// A line break or a `;`
+    Either,
+}
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParserState {
     current_module: String,
     current_function: Option<String>,
+    /// How many scopes were open before this module or function opened its
+    /// own
+    scope_depth: usize,
+}
+
+impl ParserState {
+    /// How an error inside this module or function is located
+    fn frame(&self) -> ContextFrame {
+        match &self.current_function {
+            Some(function) => ContextFrame::Function(function.clone()),
+            None => ContextFrame::Module(self.current_module.clone()),
+        }
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Debug, Clone)]
 pub struct ParserContext {
     stack: Vec<ParserState>,
+    /// Variables declared in each enclosing scope, innermost last. Modules,
+    /// functions and blocks each open a scope.
+    scopes: Vec<HashSet<String>>,
+    /// Enforce `naming` conventions on module and function names
+    naming_rules: bool,
+    /// The last `NodeId` handed out
+    last_node_id: u32,
+    /// Record statements that fail to parse in `errors` and skip them,
+    /// rather than failing the whole parse
+    recovering: bool,
+    errors: Vec<ParserError>,
+    /// How many `nested` calls are in progress, and how many may be
+    depth: usize,
+    recursion_limit: usize,
+    terminator: Terminator,
+    /// Whether `Name { ... }` is a struct literal. Off in `if` conditions and
+    /// `match` scrutinees, where the brace opens the body instead.
+    struct_literals: bool,
+    /// Offered statements that no built-in form matches, in order
+    statement_extensions: Vec<Arc<dyn StatementExtension>>,
+}
+
+impl Default for ParserContext {
+    fn default() -> Self {
+        ParserContext {
+            stack: Vec::new(),
+            scopes: Vec::new(),
+            naming_rules: false,
+            last_node_id: 0,
+            recovering: false,
+            errors: Vec::new(),
+            depth: 0,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            terminator: Terminator::default(),
+            struct_literals: true,
+            statement_extensions: Vec::new(),
+        }
+    }
 }
 
 impl ParserContext {
@@ -29,15 +133,105 @@ impl ParserContext {
         Self::default()
     }
 
+    /// Runs `parse` one level deeper, failing instead if that would exceed
+    /// the recursion limit.
+    pub fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        if self.depth >= self.recursion_limit {
+            return Err(ParserError::RecursionLimitExceeded {
+                limit: self.recursion_limit,
+            });
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// A `NodeId` not yet used in this parse
+    pub fn next_node_id(&mut self) -> NodeId {
+        self.last_node_id += 1;
+        NodeId(self.last_node_id)
+    }
+
+    /// Whether errors in statements are recorded with `recover` rather than
+    /// returned
+    pub fn is_recovering(&self) -> bool {
+        self.recovering
+    }
+
+    pub fn terminator(&self) -> Terminator {
+        self.terminator
+    }
+
+    pub fn struct_literals(&self) -> bool {
+        self.struct_literals
+    }
+
+    /// Runs `parse` with struct literals allowed or not, restoring the
+    /// previous setting afterwards.
+    pub fn with_struct_literals<T>(
+        &mut self,
+        allowed: bool,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParserError>,
+    ) -> Result<T, ParserError> {
+        let previous = std::mem::replace(&mut self.struct_literals, allowed);
+        let result = parse(self);
+        self.struct_literals = previous;
+        result
+    }
+
+    /// The extensions to offer a statement no built-in form matches.
+    pub fn statement_extensions(&self) -> &[Arc<dyn StatementExtension>] {
+        &self.statement_extensions
+    }
+
+    /// Records `error`, located on the token `stream` stopped on and in
+    /// every module and function currently entered.
+    pub fn recover(&mut self, mut error: ParserError, stream: &TokenStream) {
+        for state in self.stack.iter().rev() {
+            error = error.with_context(state.frame());
+        }
+        self.errors.push(error.at(stream.current_span().start));
+    }
+
+    /// Reject a module name that isn't PascalCase, if naming rules are on
+    pub fn check_module_name(&self, name: &str) -> Result<(), ParserError> {
+        if self.naming_rules && !naming::is_pascal_case(name) {
+            return Err(ParserError::NamingConvention {
+                name: name.to_string(),
+                convention: "PascalCase",
+            });
+        }
+        Ok(())
+    }
+
+    /// Reject a function name that isn't snake_case or camelCase, if naming
+    /// rules are on
+    pub fn check_function_name(&self, name: &str) -> Result<(), ParserError> {
+        if self.naming_rules && !naming::is_snake_or_camel_case(name) {
+            return Err(ParserError::NamingConvention {
+                name: name.to_string(),
+                convention: "snake_case or camelCase",
+            });
+        }
+        Ok(())
+    }
+
     pub fn enter_module(&mut self, module_name: String) {
         self.stack.push(ParserState {
             current_module: module_name,
             current_function: None,
+            scope_depth: self.scopes.len(),
         });
+        self.enter_scope();
     }
 
     pub fn exit_module(&mut self) {
         self.stack.pop();
+        self.exit_scope();
     }
 
     pub fn enter_function(&mut self, function_name: String) {
@@ -49,11 +243,82 @@ impl ParserContext {
                 .current_module
                 .clone(),
             current_function: Some(function_name),
+            scope_depth: self.scopes.len(),
         });
+        self.enter_scope();
     }
 
     pub fn exit_function(&mut self) {
         self.stack.pop();
+        self.exit_scope();
+    }
+
+    pub fn enter_scope(&mut self) {
+        self.scopes.push(HashSet::new());
+    }
+
+    pub fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// How many scopes are open
+    pub fn scope_depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Closes every scope opened since there were `depth`, such as those a
+    /// failed parse left open
+    pub fn exit_scopes_to(&mut self, depth: usize) {
+        self.scopes.truncate(depth);
+    }
+
+    /// Declares `name` in the innermost scope. The discard `_` is never
+    /// declared, so it can be bound any number of times but never reassigned.
+    pub fn declare(&mut self, name: &str) {
+        if name == DISCARD {
+            return;
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string());
+        }
+    }
+
+    /// Whether `name` is declared in the current scope or any scope
+    /// enclosing it.
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.scopes.iter().any(|scope| scope.contains(name))
+    }
+
+    /// How many modules and functions are currently entered
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Leaves every module and function entered since the stack was `depth`
+    /// deep, along with the scopes opened inside them, attaching each to
+    /// `error` innermost first. Parsers call this as a failure unwinds out
+    /// of what they entered.
+    pub fn unwind(&mut self, depth: usize, mut error: ParserError) -> ParserError {
+        while self.stack.len() > depth
+            && let Some(state) = self.stack.pop()
+        {
+            self.scopes.truncate(state.scope_depth);
+            error = error.with_context(state.frame());
+        }
+        error
+    }
+
+    /// Every frame of the stack, outermost first, as `module` or
+    /// `module::function`. Useful for reporting where in the nesting a
+    /// parse went wrong.
+    pub fn scope_path(&self) -> Vec<String> {
+        self.stack
+            .iter()
+            .map(|state| match &state.current_function {
+                Some(function) => format!("{}::{}", state.current_module, function),
+                None => state.current_module.clone(),
+            })
+            .collect()
     }
 
     pub fn get_fully_qualified_module_name(&self) -> String {
@@ -86,15 +351,148 @@ impl ParserContext {
     }
 }
 
-pub struct Parser;
+/// Parses tokens into an `Ast`. `Parser::parse` and
+/// `Parser::parse_with_spans` use the default options; build a `Parser` to
+/// change them.
+#[derive(Debug, Clone)]
+pub struct Parser {
+    naming_rules: bool,
+    recursion_limit: usize,
+    terminator: Terminator,
+    statement_extensions: Vec<Arc<dyn StatementExtension>>,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser {
+            naming_rules: false,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            terminator: Terminator::default(),
+            statement_extensions: Vec::new(),
+        }
+    }
+}
 
 impl Parser {
     pub fn parse(tokens: &[Token]) -> Result<Ast, ParserError> {
-        let mut ctx = ParserContext::new();
-        let mut stream = TokenStream::new(tokens);
-        let module = ModuleParser::parse(&mut ctx, &mut stream)?;
+        Self::parse_with_spans(tokens, &[])
+    }
+
+    /// Parses `tokens`, attaching the matching entry of `spans` (as produced
+    /// by `Lexer::lex_with_spans`) to every node. Nodes get a default span
+    /// when `spans` is empty.
+    pub fn parse_with_spans(tokens: &[Token], spans: &[Span]) -> Result<Ast, ParserError> {
+        Self::default().parse_tokens(tokens, spans)
+    }
+
+    /// Parses each `(filename, tokens)` pair into one `Program`. Stops at the
+    /// first file that fails to parse, or that has the name of an earlier
+    /// one, with the error in the context of that file.
+    pub fn parse_files(files: &[(String, Vec<Token>)]) -> Result<Program, ParserError> {
+        Self::parse_each(
+            files
+                .iter()
+                .map(|(filename, tokens)| (filename, tokens.as_slice(), &[][..])),
+        )
+    }
+
+    /// Like `Parser::parse_files`, with the spans of each file's tokens as
+    /// `Parser::parse_with_spans` takes them.
+    pub fn parse_files_with_spans(
+        files: &[(String, Vec<Token>, Vec<Span>)],
+    ) -> Result<Program, ParserError> {
+        Self::parse_each(
+            files
+                .iter()
+                .map(|(filename, tokens, spans)| (filename, tokens.as_slice(), spans.as_slice())),
+        )
+    }
+
+    fn parse_each<'a>(
+        files: impl Iterator<Item = (&'a String, &'a [Token], &'a [Span])>,
+    ) -> Result<Program, ParserError> {
+        let mut program = Program::default();
+        for (filename, tokens, spans) in files {
+            if program.files.contains_key(filename) {
+                return Err(ParserError::DuplicateFile(filename.clone()));
+            }
+            let ast = Self::parse_with_spans(tokens, spans)
+                .map_err(|error| error.with_context(ContextFrame::File(filename.clone())))?;
+            if let Some(module) = ast.root {
+                program.files.insert(filename.clone(), module);
+            }
+        }
+        Ok(program)
+    }
+
+    /// Require PascalCase module names and snake_case or camelCase function
+    /// names, failing with `ParserError::NamingConvention`. Off by default.
+    pub fn with_naming_rules(naming_rules: bool) -> Self {
+        Parser {
+            naming_rules,
+            ..Self::default()
+        }
+    }
+
+    /// Sets how deeply expressions, blocks and modules may nest, in place of
+    /// `DEFAULT_RECURSION_LIMIT`.
+    pub fn recursion_limit(mut self, limit: usize) -> Self {
+        self.recursion_limit = limit;
+        self
+    }
+
+    /// Sets what ends a statement, in place of `Terminator::Newline`.
+    pub fn terminator(mut self, terminator: Terminator) -> Self {
+        self.terminator = terminator;
+        self
+    }
+
+    /// Adds `extension` after any already added, to parse statements none of
+    /// the built-in forms match.
+    pub fn statement_extension(mut self, extension: impl StatementExtension + 'static) -> Self {
+        self.statement_extensions.push(Arc::new(extension));
+        self
+    }
+
+    /// Like `Parser::parse_with_spans`, using this parser's options.
+    pub fn parse_tokens(&self, tokens: &[Token], spans: &[Span]) -> Result<Ast, ParserError> {
+        let mut ctx = self.context();
+        let mut stream = TokenStream::with_spans(tokens, spans);
+        // The parser stops on the offending token
+        let module = ModuleParser::parse(&mut ctx, &mut stream)
+            .map_err(|error| error.at(stream.current_span().start))?;
         Ok(Ast { root: Some(module) })
     }
+
+    /// Like `Parser::parse_tokens`, but a statement that fails to parse is
+    /// recorded and skipped up to the end of its line, so one pass reports
+    /// every broken statement. An error outside a block still ends the
+    /// parse, leaving the `Ast` empty.
+    pub fn parse_recovering(&self, tokens: &[Token], spans: &[Span]) -> (Ast, Vec<ParserError>) {
+        let mut ctx = ParserContext {
+            recovering: true,
+            ..self.context()
+        };
+        let mut stream = TokenStream::with_spans(tokens, spans);
+        let root = match ModuleParser::parse(&mut ctx, &mut stream) {
+            Ok(module) => Some(module),
+            Err(error) => {
+                ctx.recover(error, &stream);
+                None
+            }
+        };
+        (Ast { root }, ctx.errors)
+    }
+
+    fn context(&self) -> ParserContext {
+        ParserContext {
+            naming_rules: self.naming_rules,
+            recursion_limit: self.recursion_limit,
+            terminator: self.terminator,
+            statement_extensions: self.statement_extensions.clone(),
+            ..ParserContext::new()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -103,6 +501,9 @@ mod token_stream_test;
 #[cfg(test)]
 mod parser_test;
 
+#[cfg(test)]
+mod test_util;
+
 #[cfg(test)]
 mod tests {
 
@@ -122,18 +523,53 @@ mod tests {
         Parser::parse(&tokens).map_err(Box::from)
     }
 
+    #[test]
+    fn test_scope_path() {
+        let mut ctx = ParserContext::new();
+        assert!(ctx.scope_path().is_empty());
+
+        ctx.enter_module("Main".to_string());
+        ctx.enter_module("Util".to_string());
+        ctx.enter_function("id".to_string());
+        assert_eq!(ctx.scope_path(), vec!["Main", "Util", "Util::id"]);
+
+        ctx.exit_function();
+        ctx.exit_module();
+        assert_eq!(ctx.scope_path(), vec!["Main"]);
+    }
+
+    #[test]
+    fn test_unwind_closes_scopes() {
+        let mut ctx = ParserContext::new();
+        ctx.enter_module("Main".to_string());
+        let depth = ctx.depth();
+        let scope_depth = ctx.scope_depth();
+
+        ctx.enter_function("f".to_string());
+        ctx.enter_scope();
+        ctx.declare("x");
+        let error = ctx.unwind(depth, ParserError::UnexpectedEndOfInput);
+
+        assert_eq!(
+            error.context(),
+            vec![&ContextFrame::Function("f".to_string())]
+        );
+        assert_eq!(ctx.scope_depth(), scope_depth);
+        assert!(!ctx.is_declared("x"));
+    }
+
     #[test]
     fn test_hello_world() {
-        assert_debug_snapshot!(parse("examples/hello_world.aeon"));
+        assert_debug_snapshot!(parse("../../examples/hello_world.aeon"));
     }
 
     #[test]
     fn test_math() {
-        assert_debug_snapshot!(parse("examples/math.aeon"));
+        assert_debug_snapshot!(parse("../../examples/math.aeon"));
     }
 
     #[test]
     fn test_modules() {
-        assert_debug_snapshot!(parse("examples/modules.aeon"));
+        assert_debug_snapshot!(parse("../../examples/modules.aeon"));
     }
 }
@@ -0,0 +1,81 @@
+//! Warnings about code that parses but is probably a mistake.
+
+use lex::span::Span;
+
+use crate::{
+    ast::{Ast, Statement, StatementKind},
+    block::Block,
+    visitor::{Visitor, walk_ast, walk_block},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LintWarning {
+    /// Statements after a `return` in the same block, which can never run.
+    /// `span` is that of the first of them.
+    Unreachable { span: Span },
+}
+
+/// Every warning for `ast`, in source order.
+pub fn lint(ast: &Ast) -> Vec<LintWarning> {
+    let mut lints = Lints {
+        warnings: Vec::new(),
+    };
+    walk_ast(&mut lints, ast);
+    lints.warnings
+}
+
+struct Lints {
+    warnings: Vec<LintWarning>,
+}
+
+/// Whether control never continues past `statement` to the next one.
+fn is_terminating(statement: &Statement) -> bool {
+    matches!(statement.kind, StatementKind::Return(_))
+}
+
+impl<'a> Visitor<'a> for Lints {
+    fn visit_block(&mut self, block: &'a Block) {
+        let mut statements = block.statements.iter();
+        if statements.any(is_terminating)
+            && let Some(unreachable) = statements.next()
+        {
+            self.warnings.push(LintWarning::Unreachable {
+                span: unreachable.span,
+            });
+        }
+        walk_block(self, block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{main_with_body, parse_with_spans};
+    use lex::span::Position;
+
+    fn lint_main(body: &str) -> Vec<LintWarning> {
+        lint(&parse_with_spans(&main_with_body(body)))
+    }
+
+    #[test]
+    fn test_statement_after_return_is_unreachable() {
+        let warnings = lint_main("    return n\n    x := 1\n    y := 2\n");
+
+        let [LintWarning::Unreachable { span }] = warnings.as_slice() else {
+            panic!("Expected one warning, got {:?}", warnings);
+        };
+        assert_eq!(span.start, Position::new(46, 4, 5));
+    }
+
+    #[test]
+    fn test_return_as_last_statement() {
+        assert_eq!(lint_main("    x := n + 1\n    return x\n"), vec![]);
+        assert_eq!(lint_main("    return\n"), vec![]);
+    }
+
+    #[test]
+    fn test_unreachable_in_nested_block() {
+        let warnings = lint_main("    if n > 0 {\n        return\n        n\n    }\n    n\n");
+        assert_eq!(warnings.len(), 1);
+    }
+}
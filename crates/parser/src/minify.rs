@@ -0,0 +1,60 @@
+//! Whitespace stripping of printed source, for `PrettyPrinter::minified`.
+
+use lex::{
+    lexer::{Lexer, LexerConfig},
+    token::Token,
+};
+
+/// Rewrites `source` with its tokens separated by as little as possible:
+/// a line break wherever the lexer produced a `Newline`, a single space
+/// between tokens that would otherwise lex differently, such as `fn main`
+/// or `- -x`, and nothing anywhere else. Comments are dropped. Source that
+/// doesn't lex is returned unchanged.
+pub(crate) fn minify(source: &str) -> String {
+    let Ok((tokens, spans)) = Lexer::lex_with_spans(source.as_bytes(), LexerConfig::default())
+    else {
+        return source.to_string();
+    };
+
+    let mut output = String::with_capacity(source.len());
+    let mut previous: Option<&str> = None;
+    for (token, span) in tokens.iter().zip(&spans) {
+        if token == &Token::Newline {
+            output.push('\n');
+            previous = None;
+            continue;
+        }
+
+        let text = &source[span.start.offset..span.end.offset];
+        if let Some(previous) = previous
+            && !can_touch(previous, text)
+        {
+            output.push(' ');
+        }
+        output.push_str(text);
+        previous = Some(text);
+    }
+    output
+}
+
+/// Whether `left` and `right` lex the same with nothing between them as
+/// with a space, i.e. they don't merge into one token like `a` and `b`
+/// into `ab`, or `:` and `=` into `:=`.
+fn can_touch(left: &str, right: &str) -> bool {
+    let lex = |source: String| Lexer::lex(source.as_bytes()).ok();
+    let touching = lex(format!("{}{}", left, right));
+    touching.is_some() && touching == lex(format!("{} {}", left, right))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minify() {
+        assert_eq!(
+            minify("module Main\n\nfn main() {\n    x := -1 // one\n    y := x - -x\n}\n"),
+            "module Main\nfn main(){x:=-1\ny:=x--x\n}\n"
+        );
+    }
+}
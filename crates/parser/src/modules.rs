@@ -1,73 +1,180 @@
 use crate::{
     ParserContext,
-    ast::{Expression, Statement, Variable},
-    functions::{Function, FunctionParser},
+    ast::{Expression, ExpressionKind, Statement, StatementKind, Variable},
+    attributes::{Attribute, AttributeParser},
+    enums::{Enum, EnumParser},
+    functions::{Function, FunctionParser, reject_keyword},
+    impls::{Impl, ImplParser},
     parser_error::ParserError,
     statement::StatementParser,
+    structs::{Struct, StructParser},
     token_stream::TokenStream,
+    traits::{Trait, TraitParser},
 };
 
-use lex::token::{Keyword, Literal, Token};
+use std::fmt;
+
+use lex::{
+    span::Span,
+    token::{Keyword, Literal, Operator, Token},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Import {
-    pub path: String,
+    pub path: ImportPath,
+    /// The names picked out by `import "std/math" { sqrt, pow }`, or empty
+    /// when everything is imported
+    pub items: Vec<String>,
+    /// The `@cfg(os = "linux")` that gates the import, if any
+    pub cfg: Option<Attribute>,
+    pub decl: Token,
+    pub decl_span: Span,
+}
+
+/// What an `import` refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportPath {
+    /// `import "std/math"`
+    File(String),
+    /// `import std.math`, resolved against nested modules
+    Module(Vec<String>),
+}
+
+impl fmt::Display for ImportPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportPath::File(path) => write!(f, "\"{}\"", path),
+            ImportPath::Module(segments) => write!(f, "{}", segments.join(".")),
+        }
+    }
+}
+
+/// `module Net = std.net`, making another module available under a local
+/// name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleAlias {
+    pub name: String,
+    /// The path of the aliased module, such as `["std", "net"]`
+    pub target: Vec<String>,
     pub decl: Token,
+    pub decl_span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Module {
     pub decl: Token,
+    pub decl_span: Span,
     pub name: String,
     pub imports: Vec<Import>,
+    pub aliases: Vec<ModuleAlias>,
     pub modules: Vec<Module>,
     pub functions: Vec<Function>,
+    pub impls: Vec<Impl>,
+    pub traits: Vec<Trait>,
+    pub structs: Vec<Struct>,
+    pub enums: Vec<Enum>,
     pub variables: Vec<Variable>,
+    /// Top-level statements other than variable declarations, such as calls
+    pub statements: Vec<Statement>,
+    pub span: Span,
+}
+
+/// Everything declared between a module's header and its end.
+#[derive(Default)]
+struct ModuleBody {
+    imports: Vec<Import>,
+    aliases: Vec<ModuleAlias>,
+    modules: Vec<Module>,
+    impls: Vec<Impl>,
+    traits: Vec<Trait>,
+    structs: Vec<Struct>,
+    enums: Vec<Enum>,
+    functions: Vec<Function>,
+    variables: Vec<Variable>,
+    statements: Vec<Statement>,
 }
 
 pub struct ModuleParser;
 
 impl ModuleParser {
     pub fn parse(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Module, ParserError> {
+        let depth = ctx.depth();
+        Self::parse_module(ctx, stream).map_err(|error| ctx.unwind(depth, error))
+    }
+
+    fn parse_module(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Module, ParserError> {
+        let start = stream.position();
         let decl = stream
             .consume_exact(Token::Keyword(Keyword::Module))
             .or(Err(ParserError::ModuleNotFound))?;
-        let name_token = stream
-            .consume(Token::Identifier(String::new()))
+        let decl_span = stream.span_at(start);
+        let name = stream
+            .consume_identifier()
             .or(Err(ParserError::ModuleWithoutName))?;
-        let name = match name_token {
-            Token::Identifier(name) => {
-                ctx.enter_module(name.clone());
-                name
-            }
-            _ => return Err(ParserError::ModuleWithoutName),
-        };
+        ctx.check_module_name(&name)?;
+        ctx.enter_module(name.clone());
 
-        let (imports, modules, functions, variables) = Self::parse_module_body(ctx, stream)?;
+        // The body either runs to the end of the input, or is wrapped in
+        // braces as in `module Foo { }`, after which nothing may follow
+        let braced = stream.try_consume(Token::OpenBrace).is_some();
+        let body = Self::parse_module_body(ctx, stream, braced)?;
+        if braced {
+            while stream.try_consume(Token::Newline).is_some() {}
+            if let Some(token) = stream.peek()
+                && !stream.is_at_end()
+            {
+                return Err(ParserError::unexpected(token.clone()));
+            }
+        }
         Ok(Module {
             decl,
+            decl_span,
             name,
-            imports,
-            modules,
-            functions,
-            variables,
+            imports: body.imports,
+            aliases: body.aliases,
+            modules: body.modules,
+            impls: body.impls,
+            traits: body.traits,
+            structs: body.structs,
+            enums: body.enums,
+            functions: body.functions,
+            variables: body.variables,
+            statements: body.statements,
+            span: stream.span_from(start),
         })
     }
 
-    #[allow(clippy::type_complexity)]
+    /// The items of a module. A `braced` body must end with a `}`, which is
+    /// consumed; any other body runs to the end of the input, and a `}` in
+    /// it is an error.
     fn parse_module_body(
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
-    ) -> Result<(Vec<Import>, Vec<Module>, Vec<Function>, Vec<Variable>), ParserError> {
-        let mut imports = Vec::<Import>::new();
-        let mut modules = Vec::<Module>::new();
-        let mut functions = Vec::<Function>::new();
-        let mut variables = Vec::<Variable>::new();
+        braced: bool,
+    ) -> Result<ModuleBody, ParserError> {
+        let mut body = ModuleBody::default();
+        let ends: &[Token] = if braced {
+            &[Token::Newline, Token::CloseBrace]
+        } else {
+            &[Token::Newline]
+        };
 
         loop {
             if stream.is_at_end() {
+                if braced {
+                    return Err(ParserError::UnexpectedEndOfInput);
+                }
                 break;
             }
+            // Blank lines are skipped, and a `}` ends a braced body
+            match stream.try_consume_one_of(ends) {
+                Some(Token::CloseBrace) => break,
+                Some(_) => continue,
+                None => {}
+            }
 
             let token = stream
                 .peek()
@@ -76,15 +183,30 @@ impl ModuleParser {
 
             match token {
                 Token::Keyword(Keyword::Module) => {
+                    let start = stream.position();
                     let decl = stream.consume_exact(Token::Keyword(Keyword::Module))?;
+                    let decl_span = stream.span_at(start);
+
+                    let short_name = stream.consume_identifier()?;
+                    ctx.check_module_name(&short_name)?;
+
+                    if stream
+                        .try_consume(Token::Operator(Operator::Reassign))
+                        .is_some()
+                    {
+                        body.aliases.push(ModuleAlias {
+                            name: short_name,
+                            target: Self::parse_module_path(stream)?,
+                            decl,
+                            decl_span,
+                        });
+                        stream.expect_newline_or_end()?;
+                        continue;
+                    }
 
-                    let name = match stream.consume(Token::Identifier(String::new()))? {
-                        Token::Identifier(n) => {
-                            ctx.enter_module(n.clone());
-                            ctx.get_fully_qualified_module_name()
-                        }
-                        token => return Err(ParserError::UnexpectedToken(token)),
-                    };
+                    let depth = ctx.depth();
+                    ctx.enter_module(short_name);
+                    let name = ctx.get_fully_qualified_module_name();
 
                     stream.consume(Token::OpenBrace)?;
                     _ = stream.try_consume(Token::Newline);
@@ -93,62 +215,187 @@ impl ModuleParser {
                     // -2 due to the stream having advanced to the closing brace
                     // then to the next token
                     // let mut body_stream = stream.substream(body_start, stream.position() - 2);
-                    let (inner_imports, inner_modules, inner_functions, inner_variables) =
-                        Self::parse_module_body(ctx, stream)?;
+                    let inner = ctx
+                        .nested(|ctx| Self::parse_module_body(ctx, stream, true))
+                        .map_err(|error| ctx.unwind(depth, error))?;
 
-                    modules.push(Module {
+                    body.modules.push(Module {
                         decl,
+                        decl_span,
                         name,
-                        imports: inner_imports,
-                        modules: inner_modules,
-                        functions: inner_functions,
-                        variables: inner_variables,
+                        imports: inner.imports,
+                        aliases: inner.aliases,
+                        modules: inner.modules,
+                        impls: inner.impls,
+                        traits: inner.traits,
+                        structs: inner.structs,
+                        enums: inner.enums,
+                        functions: inner.functions,
+                        variables: inner.variables,
+                        statements: inner.statements,
+                        span: stream.span_from(start),
                     });
 
                     continue;
                 }
-                token @ Token::Identifier(_) => {
-                    match StatementParser::parse(ctx, stream)? {
-                        Statement::Expression(Expression::Variable(variable)) => {
-                            variables.push(variable);
-                            stream.try_consume(Token::Newline);
-                        }
-                        _ => {
-                            return Err(ParserError::UnexpectedToken(token.clone()));
-                        }
+                Token::Keyword(Keyword::Const)
+                    if stream.peek_next() == Some(&Token::Keyword(Keyword::Fn)) =>
+                {
+                    let function = FunctionParser::parse(ctx, stream)?;
+                    body.functions.push(function);
+                    ctx.exit_function();
+                    stream.expect_newline_or_end()?;
+                    continue;
+                }
+                Token::Identifier(_) | Token::Keyword(Keyword::Const) => {
+                    let statement = StatementParser::parse(ctx, stream)?;
+                    StatementParser::parse_terminator(ctx, stream, &statement)?;
+                    match statement.kind {
+                        StatementKind::Expression(Expression {
+                            kind: ExpressionKind::Variable(variable),
+                            ..
+                        }) => body.variables.push(variable),
+                        _ => body.statements.push(statement),
                     }
-                    // TODO: Match for function calls
+                    stream.try_consume(Token::Newline);
                     continue;
                 }
                 Token::Keyword(Keyword::Fn) => {
                     let function = FunctionParser::parse(ctx, stream)?;
-                    functions.push(function);
-                    stream.try_consume(Token::Newline);
+                    body.functions.push(function);
                     ctx.exit_function();
+                    stream.expect_newline_or_end()?;
+                    continue;
+                }
+                Token::Keyword(Keyword::Impl) => {
+                    let implementation = ImplParser::parse(ctx, stream)?;
+                    body.impls.push(implementation);
+                    stream.expect_newline_or_end()?;
+                    continue;
+                }
+                Token::Keyword(Keyword::Trait) => {
+                    let declaration = TraitParser::parse(ctx, stream)?;
+                    body.traits.push(declaration);
+                    stream.expect_newline_or_end()?;
+                    continue;
+                }
+                Token::Keyword(Keyword::Struct) => {
+                    body.structs.push(StructParser::parse(ctx, stream)?);
+                    stream.expect_newline_or_end()?;
+                    continue;
+                }
+                Token::Keyword(Keyword::Enum) => {
+                    body.enums.push(EnumParser::parse(ctx, stream)?);
+                    stream.expect_newline_or_end()?;
                     continue;
                 }
                 Token::Keyword(Keyword::Import) => {
-                    let decl = stream.consume(Token::Keyword(Keyword::Import))?;
-
-                    let Token::Literal(Literal::String(path)) =
-                        stream.consume(Token::Literal(Literal::String(String::new())))?
-                    else {
-                        return Err(ParserError::UnexpectedToken(stream.current()?.clone()));
-                    };
-
-                    imports.push(Import { path, decl });
-
+                    body.imports.push(Self::parse_import(stream, None)?);
+                    stream.expect_newline_or_end()?;
+                    continue;
+                }
+                // Only an import can be gated, by a single `@cfg(...)`
+                Token::At => {
+                    let mut attributes = AttributeParser::parse(stream)?.into_iter();
+                    let cfg = attributes.next().expect("`@` starts an attribute");
+                    if cfg.name != "cfg" {
+                        return Err(ParserError::UnexpectedAttribute(cfg));
+                    }
+                    if let Some(extra) = attributes.next() {
+                        return Err(ParserError::UnexpectedAttribute(extra));
+                    }
+                    if stream.peek() != Some(&Token::Keyword(Keyword::Import)) {
+                        return Err(ParserError::expected(
+                            stream.current()?.clone(),
+                            &[Token::Keyword(Keyword::Import)],
+                        ));
+                    }
+                    body.imports.push(Self::parse_import(stream, Some(cfg))?);
+                    stream.expect_newline_or_end()?;
                     continue;
                 }
-                Token::CloseBrace => {
-                    stream.advance(1)?;
-                    break;
+                // Only a function has anything to return or yield to
+                Token::Keyword(Keyword::Return | Keyword::Yield) => {
+                    return Err(ParserError::unexpected(token));
                 }
+                Token::Keyword(Keyword::Else) => return Err(ParserError::DanglingElse),
+                Token::CloseBrace => return Err(ParserError::unexpected(token)),
                 _ => stream.advance(1)?,
             }
         }
 
         ctx.exit_module();
-        Ok((imports, modules, functions, variables))
+        Ok(body)
+    }
+
+    /// `import path` or `import path { items }`, gated by `cfg`
+    fn parse_import(
+        stream: &mut TokenStream,
+        cfg: Option<Attribute>,
+    ) -> Result<Import, ParserError> {
+        let decl_span = stream.span_at(stream.position());
+        let decl = stream.consume_exact(Token::Keyword(Keyword::Import))?;
+        let path = Self::parse_import_path(stream)?;
+        let items = Self::parse_import_items(stream)?;
+        Ok(Import {
+            path,
+            items,
+            cfg,
+            decl,
+            decl_span,
+        })
+    }
+
+    /// The `{ sqrt, pow }` after an import path, if any. The braces may span
+    /// lines and the last name may be followed by a comma.
+    fn parse_import_items(stream: &mut TokenStream) -> Result<Vec<String>, ParserError> {
+        let mut items = Vec::new();
+        if stream.try_consume(Token::OpenBrace).is_none() {
+            return Ok(items);
+        }
+
+        loop {
+            while stream.try_consume(Token::Newline).is_some() {}
+            if stream.try_consume(Token::CloseBrace).is_some() {
+                break;
+            }
+            reject_keyword(stream)?;
+            items.push(stream.consume_identifier()?);
+
+            while stream.try_consume(Token::Newline).is_some() {}
+            if stream.try_consume(Token::Comma).is_none() {
+                stream.consume(Token::CloseBrace)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    /// A dotted module path such as `std.math`
+    fn parse_module_path(stream: &mut TokenStream) -> Result<Vec<String>, ParserError> {
+        let mut segments = Vec::new();
+        loop {
+            segments.push(stream.consume_identifier()?);
+            if stream.try_consume(Token::Dot).is_none() {
+                break;
+            }
+        }
+        Ok(segments)
+    }
+
+    fn parse_import_path(stream: &mut TokenStream) -> Result<ImportPath, ParserError> {
+        match stream.current()?.clone() {
+            Token::Literal(Literal::String(_)) => {
+                Ok(ImportPath::File(stream.consume_string_literal()?))
+            }
+            Token::Identifier(_) => Ok(ImportPath::Module(Self::parse_module_path(stream)?)),
+            token => Err(ParserError::expected(
+                token,
+                &[
+                    Token::Literal(Literal::String(String::new())),
+                    Token::Identifier(String::new()),
+                ],
+            )),
+        }
     }
 }
@@ -0,0 +1,41 @@
+//! Naming conventions checked by `Parser::with_naming_rules`.
+
+/// `Main`, `HttpServer`
+pub fn is_pascal_case(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// `main`, `parse_args` or `parseArgs`, but not a mix like `parse_Args`
+pub fn is_snake_or_camel_case(name: &str) -> bool {
+    let is_snake = name.chars().all(|c| !c.is_ascii_uppercase());
+    let is_camel = !name.contains('_');
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && (is_snake || is_camel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pascal_case() {
+        assert!(is_pascal_case("Main"));
+        assert!(is_pascal_case("HttpServer2"));
+        assert!(!is_pascal_case("main"));
+        assert!(!is_pascal_case("Http_Server"));
+        assert!(!is_pascal_case(""));
+    }
+
+    #[test]
+    fn test_snake_or_camel_case() {
+        assert!(is_snake_or_camel_case("main"));
+        assert!(is_snake_or_camel_case("parse_args"));
+        assert!(is_snake_or_camel_case("parseArgs"));
+        assert!(!is_snake_or_camel_case("ParseArgs"));
+        assert!(!is_snake_or_camel_case("parse_Args"));
+        assert!(!is_snake_or_camel_case("_private"));
+        assert!(!is_snake_or_camel_case(""));
+    }
+}
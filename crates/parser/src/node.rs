@@ -0,0 +1,168 @@
+//! Node ids and lookups from source positions to nodes, for editor tooling.
+
+use std::collections::HashMap;
+
+use lex::span::Span;
+
+use crate::{
+    ast::{Ast, Expression, Statement},
+    visitor::{Visitor, walk_ast, walk_expression, walk_statement},
+};
+
+/// Identifies a statement or expression within one parse. Ids are handed out
+/// by `ParserContext` from 1 upwards; `NodeId::default()` marks a node that
+/// was built by hand rather than parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, PartialOrd, Ord)]
+pub struct NodeId(pub u32);
+
+/// A node that has a `NodeId`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Node<'a> {
+    Statement(&'a Statement),
+    Expression(&'a Expression),
+}
+
+impl Node<'_> {
+    pub fn id(&self) -> NodeId {
+        match self {
+            Node::Statement(statement) => statement.id,
+            Node::Expression(expression) => expression.id,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Statement(statement) => statement.span,
+            Node::Expression(expression) => expression.span,
+        }
+    }
+}
+
+/// Every parsed node of an AST by id.
+#[derive(Debug, Default)]
+pub struct NodeIndex<'a> {
+    pub spans: HashMap<NodeId, Span>,
+    pub nodes: HashMap<NodeId, Node<'a>>,
+}
+
+impl<'a> NodeIndex<'a> {
+    pub fn new(ast: &'a Ast) -> Self {
+        let mut index = NodeIndex::default();
+        walk_ast(&mut index, ast);
+        index
+    }
+
+    /// The innermost node whose span contains `offset`.
+    pub fn node_at(&self, offset: usize) -> Option<NodeId> {
+        self.spans
+            .iter()
+            .filter(|(_, span)| span.contains(offset))
+            // A node is created after its children, so on equal spans, such
+            // as an expression statement and its expression, the lower id is
+            // the inner node
+            .min_by_key(|(id, span)| (span.end.offset - span.start.offset, **id))
+            .map(|(id, _)| *id)
+    }
+
+    fn add(&mut self, node: Node<'a>) {
+        if node.id() != NodeId::default() {
+            self.spans.insert(node.id(), node.span());
+            self.nodes.insert(node.id(), node);
+        }
+    }
+}
+
+impl<'a> Visitor<'a> for NodeIndex<'a> {
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        self.add(Node::Statement(statement));
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        self.add(Node::Expression(expression));
+        walk_expression(self, expression);
+    }
+}
+
+impl Ast {
+    /// Indexes every parsed node of this AST by id.
+    pub fn index(&self) -> NodeIndex<'_> {
+        NodeIndex::new(self)
+    }
+
+    /// The innermost statement or expression at `offset`, counted in bytes
+    /// from the start of the source. Needs an AST parsed with spans.
+    pub fn node_at(&self, offset: usize) -> Option<NodeId> {
+        self.index().node_at(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast::{ExpressionKind, StatementKind, Variable},
+        test_util::parse_with_spans as parse,
+    };
+    use lex::token::Operator;
+
+    const SOURCE: &str = "module Main\nfn main() {\n    x := a + bc\n}\n";
+
+    fn variable_name(node: Option<&Node>) -> Option<String> {
+        match node {
+            Some(Node::Expression(Expression {
+                kind: ExpressionKind::Variable(Variable { name, .. }),
+                ..
+            })) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_distinct_node_ids() {
+        let ast = parse(SOURCE);
+        let statement = &ast.root.as_ref().unwrap().functions[0].block.statements[0];
+        let StatementKind::Expression(declaration) = &statement.kind else {
+            panic!("Expected expression statement, got {:?}", statement.kind);
+        };
+        assert_ne!(statement.id, NodeId::default());
+        assert_ne!(statement.id, declaration.id);
+
+        let index = ast.index();
+        // The statement, the declaration, the sum and its two operands
+        assert_eq!(index.nodes.len(), 5);
+        assert!(!index.nodes.contains_key(&NodeId::default()));
+    }
+
+    #[test]
+    fn test_node_at_finds_innermost_node() {
+        let ast = parse(SOURCE);
+        let index = ast.index();
+
+        // `bc`
+        let id = ast.node_at(37).unwrap();
+        assert_eq!(variable_name(index.nodes.get(&id)), Some("bc".to_string()));
+
+        // `+` belongs only to the sum
+        let id = ast.node_at(35).unwrap();
+        assert!(matches!(
+            index.nodes.get(&id),
+            Some(Node::Expression(Expression {
+                kind: ExpressionKind::Binary {
+                    operator: Operator::Plus,
+                    ..
+                },
+                ..
+            }))
+        ));
+
+        // `x` starts both the statement and the declaration; the declaration
+        // is inside the statement
+        let id = ast.node_at(28).unwrap();
+        assert_eq!(variable_name(index.nodes.get(&id)), Some("x".to_string()));
+        assert_eq!(index.spans[&id].start.offset, 28);
+
+        // Outside any statement
+        assert_eq!(ast.node_at(3), None);
+    }
+}
@@ -0,0 +1,224 @@
+//! Canonicalization of ASTs, so programs that only differ in layout compare
+//! equal.
+
+use lex::token::Operator;
+
+use crate::{
+    ast::{Ast, Expression, ExpressionKind, Statement, StatementKind, Variable},
+    impls::Impl,
+    modules::Module,
+    traits::Trait,
+    visitor::{
+        Visitor, VisitorMut, walk_ast_mut, walk_expression, walk_expression_mut, walk_impl_mut,
+        walk_module_mut, walk_statement_mut, walk_trait_mut, walk_variable, walk_variable_mut,
+    },
+};
+
+/// Rewrites `ast` into a canonical shape:
+///
+/// - The modules, functions, impls, traits and aliases of a module, the
+///   functions of an impl and the methods of a trait are sorted by name.
+///   Variables and statements keep their order, which is significant.
+/// - Parentheses that don't change how the program parses are removed, such
+///   as those around an initializer, a call argument or a variable.
+/// - `a > b` and `a >= b` become `b < a` and `b <= a`, unless swapping the
+///   operands would reorder side effects.
+///
+/// Equivalent programs parsed without spans normalize to equal ASTs.
+pub fn normalize(ast: &mut Ast) {
+    walk_ast_mut(&mut Normalize, ast);
+}
+
+struct Normalize;
+
+impl VisitorMut for Normalize {
+    fn visit_module(&mut self, module: &mut Module) {
+        module.modules.sort_by(|a, b| a.name.cmp(&b.name));
+        module.functions.sort_by(|a, b| a.name.cmp(&b.name));
+        module.impls.sort_by(|a, b| a.type_name.cmp(&b.type_name));
+        module.traits.sort_by(|a, b| a.name.cmp(&b.name));
+        module.structs.sort_by(|a, b| a.name.cmp(&b.name));
+        module.enums.sort_by(|a, b| a.name.cmp(&b.name));
+        module.aliases.sort_by(|a, b| a.name.cmp(&b.name));
+        walk_module_mut(self, module);
+    }
+
+    fn visit_impl(&mut self, implementation: &mut Impl) {
+        implementation.functions.sort_by(|a, b| a.name.cmp(&b.name));
+        walk_impl_mut(self, implementation);
+    }
+
+    fn visit_trait(&mut self, trait_def: &mut Trait) {
+        trait_def
+            .methods
+            .sort_by(|a, b| a.signature.name.cmp(&b.signature.name));
+        walk_trait_mut(self, trait_def);
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+        match &mut statement.kind {
+            StatementKind::Expression(expression)
+            | StatementKind::Destructure { expression, .. }
+            | StatementKind::Return(Some(expression))
+            | StatementKind::Yield(expression) => ungroup(expression),
+            StatementKind::Assert { condition, message } => {
+                ungroup(condition);
+                if let Some(message) = message {
+                    ungroup(message);
+                }
+            }
+            StatementKind::Block(_) | StatementKind::Return(None) => {}
+        }
+    }
+
+    fn visit_variable(&mut self, variable: &mut Variable) {
+        walk_variable_mut(self, variable);
+        if let Some(expression) = &mut variable.expression {
+            ungroup(expression);
+        }
+    }
+
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+        match &mut expression.kind {
+            ExpressionKind::Call { arguments, .. } | ExpressionKind::Tuple(arguments) => {
+                arguments.iter_mut().for_each(ungroup);
+            }
+            ExpressionKind::Index { index, .. } => ungroup(index),
+            ExpressionKind::StructLiteral { fields, .. } | ExpressionKind::Record(fields) => {
+                for field in fields {
+                    ungroup(&mut field.value);
+                }
+            }
+            ExpressionKind::Match { arms, .. } => {
+                for arm in arms {
+                    ungroup(&mut arm.body);
+                }
+            }
+            ExpressionKind::Binary {
+                left,
+                right,
+                operator,
+            } if !has_side_effects(left) && !has_side_effects(right) => {
+                let flipped = match operator {
+                    Operator::Greater => Operator::Less,
+                    Operator::GreaterEqual => Operator::LessEqual,
+                    _ => return,
+                };
+                *operator = flipped;
+                std::mem::swap(left, right);
+            }
+            _ => {}
+        }
+
+        if let ExpressionKind::Group { inner } = &expression.kind
+            && is_atomic(inner)
+        {
+            ungroup(expression);
+        }
+    }
+}
+
+/// Removes the parentheses around `expression`, which is somewhere any
+/// expression can go without them.
+fn ungroup(expression: &mut Expression) {
+    while let ExpressionKind::Group { inner } = &mut expression.kind {
+        let inner = std::mem::replace(inner.as_mut(), ExpressionKind::LiteralNull.into());
+        *expression = inner;
+    }
+}
+
+/// Whether `expression` binds tighter than any operator, so parentheses
+/// around it are never needed. A struct literal isn't: it needs them in the
+/// head of an `if` or `match`.
+fn is_atomic(expression: &Expression) -> bool {
+    matches!(
+        expression.kind,
+        ExpressionKind::Literal(_)
+            | ExpressionKind::LiteralNull
+            | ExpressionKind::Group { .. }
+            | ExpressionKind::Tuple(_)
+            | ExpressionKind::Variable(_)
+            | ExpressionKind::Path(_)
+            | ExpressionKind::SelfRef
+            | ExpressionKind::FieldAccess { .. }
+            | ExpressionKind::OptionalAccess { .. }
+            | ExpressionKind::Call { .. }
+            | ExpressionKind::Index { .. }
+            | ExpressionKind::Slice { .. }
+            | ExpressionKind::Try(_)
+    )
+}
+
+/// Whether evaluating `expression` may call a function, propagate an error
+/// or assign a variable.
+fn has_side_effects(expression: &Expression) -> bool {
+    let mut finder = SideEffects(false);
+    finder.visit_expression(expression);
+    finder.0
+}
+
+struct SideEffects(bool);
+
+impl<'a> Visitor<'a> for SideEffects {
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        match expression.kind {
+            ExpressionKind::Call { .. } | ExpressionKind::Try(_) => self.0 = true,
+            _ => walk_expression(self, expression),
+        }
+    }
+
+    fn visit_variable(&mut self, variable: &'a Variable) {
+        if !variable.is_decl && variable.expression.is_some() {
+            self.0 = true;
+        }
+        walk_variable(self, variable);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::PrettyPrinter, test_util::parse};
+
+    fn normalized(source: &str) -> Ast {
+        let mut ast = parse(source);
+        normalize(&mut ast);
+        ast
+    }
+
+    #[test]
+    fn test_normalize_reordered_declarations() {
+        let ordered = "module Main\n\nfn a() {\n    1\n}\n\nfn b() {\n    2\n}\n";
+        let reordered = "module Main\n\nfn b() {\n    2\n}\n\nfn a() {\n    1\n}\n";
+
+        assert_eq!(normalized(ordered), normalized(reordered));
+        assert_eq!(PrettyPrinter::new().print(&normalized(reordered)), ordered);
+    }
+
+    #[test]
+    fn test_normalize_redundant_parentheses() {
+        let plain = "module Main\n\nfn main() {\n    x := 1 + 2\n    y := f(x, x.a) * (x + 1)\n}\n";
+        let parenthesized =
+            "module Main\n\nfn main() {\n    x := ((1 + 2))\n    y := f((x), (x).a) * (x + 1)\n}\n";
+
+        assert_eq!(normalized(plain), normalized(parenthesized));
+        // `(x + 1)` changes how the product parses, so it stays
+        assert_eq!(
+            PrettyPrinter::new().print(&normalized(parenthesized)),
+            plain
+        );
+    }
+
+    #[test]
+    fn test_normalize_comparisons() {
+        let less = "module Main\n\nfn main() {\n    x := 1\n    y := x < 2 && x <= 3\n}\n";
+        let greater = "module Main\n\nfn main() {\n    x := 1\n    y := 2 > x && 3 >= x\n}\n";
+        assert_eq!(normalized(less), normalized(greater));
+
+        // Swapping the operands would call `f` after reading `x`
+        let call = "module Main\n\nfn main() {\n    x := 1\n    y := f() > x\n}\n";
+        assert_eq!(PrettyPrinter::new().print(&normalized(call)), call);
+    }
+}
@@ -1,24 +1,114 @@
 use std::error::Error;
 
-use lex::token::Token;
+use crate::attributes::Attribute;
+use lex::{
+    source::SourceError,
+    span::Position,
+    token::{Keyword, Literal, Token},
+};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParserError {
     ModuleNotFound,
     ModuleWithoutName,
-    ModuleEmpty { start: Token, end: Token },
-    NestedModuleMustBeTopLevel { start: Token, end: Option<Token> },
-    NestedModuleWithoutBody { start: Token, end: Token },
-    UnexpectedToken(Token),
+    ModuleEmpty {
+        start: Token,
+        end: Token,
+    },
+    NestedModuleMustBeTopLevel {
+        start: Token,
+        end: Option<Token>,
+    },
+    NestedModuleWithoutBody {
+        start: Token,
+        end: Token,
+    },
+    /// `expected` lists what could have appeared instead of `found`, as
+    /// written in source (`(`, `fn`) or named (`identifier`, `string`).
+    UnexpectedToken {
+        found: Token,
+        expected: Vec<String>,
+    },
     UnexpectedEndOfInput,
-    MissingClosingBrace { start: Token, end: Option<Token> },
+    ReservedKeyword(Keyword),
+    UndeclaredVariable(String),
+    NamingConvention {
+        name: String,
+        convention: &'static str,
+    },
+    /// `error` at a 1-based `line` and `column`. Only added when parsing
+    /// with spans.
+    AtLine {
+        line: usize,
+        column: usize,
+        error: Box<ParserError>,
+    },
+    MissingClosingBrace {
+        start: Token,
+        end: Option<Token>,
+    },
+    /// Expressions, blocks or modules nested deeper than `limit`
+    RecursionLimitExceeded {
+        limit: usize,
+    },
+    /// A statement followed by `found` on the same line
+    MissingTerminator {
+        found: Token,
+    },
+    /// An attribute on an item that doesn't take it, such as an import
+    /// gated by anything but a single `@cfg(...)`
+    UnexpectedAttribute(Attribute),
+    /// An `else` that doesn't follow the block of an `if`
+    DanglingElse,
+    /// A file given to `Parser::parse_files` under the same name as an
+    /// earlier one
+    DuplicateFile(String),
+    /// `error` inside the module or function `frame`. Added as the parsers
+    /// unwind, so the innermost frame wraps the error directly.
+    InContext {
+        frame: ContextFrame,
+        error: Box<ParserError>,
+    },
+}
+
+/// A module or function the parser was inside of when it failed, or the
+/// file it was parsing for `Parser::parse_files`
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContextFrame {
+    File(String),
+    Module(String),
+    Function(String),
+}
+
+impl std::fmt::Display for ContextFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextFrame::File(name) => write!(f, "file `{}`", name),
+            ContextFrame::Module(name) => write!(f, "module `{}`", name),
+            ContextFrame::Function(name) => write!(f, "function `{}`", name),
+        }
+    }
 }
 
 impl std::fmt::Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ParserError::UnexpectedToken(token) => {
-                write!(f, "Unexpected token: {:?}", token)
+            ParserError::UnexpectedToken { found, expected } => {
+                let found = describe(found);
+                match expected.as_slice() {
+                    [] => write!(f, "unexpected `{}`.", found),
+                    [expected] => write!(f, "expected `{}`, found `{}`.", expected, found),
+                    expected => {
+                        let expected: Vec<String> =
+                            expected.iter().map(|e| format!("`{}`", e)).collect();
+                        write!(
+                            f,
+                            "expected one of {}, found `{}`.",
+                            expected.join(", "),
+                            found
+                        )
+                    }
+                }
             }
             ParserError::ModuleNotFound => {
                 write!(f, "File must begin with a module declaration!")
@@ -55,8 +145,175 @@ impl std::fmt::Display for ParserError {
             ParserError::UnexpectedEndOfInput => {
                 write!(f, "Unexpected end of input!")
             }
+            ParserError::UndeclaredVariable(name) => {
+                write!(f, "Cannot assign to undeclared variable `{}`!", name)
+            }
+            ParserError::AtLine { error, .. } => write!(f, "{}", error),
+            ParserError::InContext { .. } => {
+                let frames: Vec<String> = self.context().iter().map(|f| f.to_string()).collect();
+                write!(f, "{} (while parsing {})", self.kind(), frames.join(" in "))
+            }
+            ParserError::RecursionLimitExceeded { limit } => {
+                write!(f, "Nesting is deeper than the limit of {}!", limit)
+            }
+            ParserError::MissingTerminator { found } => {
+                write!(
+                    f,
+                    "Missing statement terminator before `{}`!",
+                    describe(found)
+                )
+            }
+            ParserError::UnexpectedAttribute(attribute) => {
+                write!(f, "Unexpected attribute `{}`!", attribute)
+            }
+            ParserError::DanglingElse => {
+                write!(f, "`else` without a matching `if`!")
+            }
+            ParserError::DuplicateFile(name) => {
+                write!(f, "File `{}` was given more than once!", name)
+            }
+            ParserError::NamingConvention { name, convention } => {
+                write!(f, "`{}` should be {}!", name, convention)
+            }
+            ParserError::ReservedKeyword(keyword) => {
+                write!(
+                    f,
+                    "`{}` is a reserved keyword and cannot be used as a name!",
+                    keyword
+                )
+            }
+        }
+    }
+}
+
+impl ParserError {
+    /// The 1-based line the parser stopped on, if it was given spans.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ParserError::AtLine { line, .. } => Some(*line),
+            ParserError::InContext { error, .. } => error.line(),
+            _ => None,
+        }
+    }
+
+    /// The 1-based column the parser stopped on, if it was given spans.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            ParserError::AtLine { column, .. } => Some(*column),
+            ParserError::InContext { error, .. } => error.column(),
+            _ => None,
+        }
+    }
+
+    /// Wraps the error in `AtLine`, unless `position` is on line 0 because
+    /// the parser had no spans.
+    pub fn at(self, position: Position) -> Self {
+        match position.line {
+            0 => self,
+            line => ParserError::AtLine {
+                line,
+                column: position.column,
+                error: Box::new(self),
+            },
+        }
+    }
+
+    /// Wraps the error in `InContext`, as a parser unwinds out of `frame`.
+    pub fn with_context(self, frame: ContextFrame) -> Self {
+        ParserError::InContext {
+            frame,
+            error: Box::new(self),
+        }
+    }
+
+    /// The files, modules and functions the parser was inside of, innermost
+    /// first.
+    pub fn context(&self) -> Vec<&ContextFrame> {
+        let mut frames = Vec::new();
+        let mut error = self;
+        loop {
+            match error {
+                ParserError::AtLine { error: inner, .. } => error = inner,
+                ParserError::InContext {
+                    frame,
+                    error: inner,
+                } => {
+                    frames.push(frame);
+                    error = inner;
+                }
+                _ => break,
+            }
         }
+        // The innermost frame wraps the error directly, so it's found last
+        frames.reverse();
+        frames
+    }
+
+    /// The error without its location or context.
+    pub fn kind(&self) -> &ParserError {
+        match self {
+            ParserError::AtLine { error, .. } | ParserError::InContext { error, .. } => {
+                error.kind()
+            }
+            error => error,
+        }
+    }
+
+    /// Attaches the offending line of `source`, the input that was parsed.
+    pub fn with_source(self, source: &str) -> SourceError<Self> {
+        let line = self.line();
+        SourceError::new(self, line, source)
+    }
+
+    /// `found` where nothing in particular was expected.
+    pub fn unexpected(found: Token) -> Self {
+        ParserError::UnexpectedToken {
+            found,
+            expected: Vec::new(),
+        }
+    }
+
+    /// `found` where one of the `expected` token patterns should have been.
+    /// Patterns are matched like `Token::matches`, so
+    /// `Token::Identifier(String::new())` stands for any identifier.
+    pub fn expected(found: Token, expected: &[Token]) -> Self {
+        ParserError::UnexpectedToken {
+            found,
+            expected: expected.iter().map(describe).collect(),
+        }
+    }
+}
+
+/// How a token, or a token pattern, is named in error messages.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Identifier(name) if name.is_empty() => "identifier".to_string(),
+        Token::Literal(Literal::String(value)) if value.is_empty() => "string".to_string(),
+        Token::Literal(Literal::Number(value)) if value.is_empty() => "number".to_string(),
+        Token::Newline => "newline".to_string(),
+        token => token.to_string(),
     }
 }
 
 impl Error for ParserError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lex::token::Keyword;
+
+    #[test]
+    fn test_display_unexpected_token() {
+        let error = ParserError::expected(
+            Token::Keyword(Keyword::Fn),
+            &[Token::OpenParen, Token::OpenBrace],
+        );
+        assert_eq!(error.to_string(), "expected one of `(`, `{`, found `fn`.");
+
+        let error = ParserError::expected(Token::Newline, &[Token::Identifier(String::new())]);
+        assert_eq!(error.to_string(), "expected `identifier`, found `newline`.");
+
+        let error = ParserError::unexpected(Token::CloseParen);
+        assert_eq!(error.to_string(), "unexpected `)`.");
+    }
+}
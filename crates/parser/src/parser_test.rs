@@ -1,12 +1,81 @@
 #[cfg(test)]
 mod tests {
+    use std::io::BufReader;
+
     use crate::{
-        Parser,
-        ast::{Ast, Expression, Statement},
-        modules::Module,
-        parser_error::ParserError,
+        Parser, ParserContext, Terminator,
+        ast::{
+            Ast, Expression, ExpressionKind, FieldInit, IfCondition, Range, Statement,
+            StatementKind, Variable,
+        },
+        attributes::{Attribute, AttributeArgument},
+        block::Block,
+        expression::ExpressionParser,
+        functions::{Arg, GenericParam, Type, TypeInfo},
+        modules::{ImportPath, Module},
+        parser_error::{ContextFrame, ParserError},
+        pattern::Pattern,
+        statement::StatementExtension,
+        structs::StructKind,
+        token_stream::TokenStream,
+        visitor::AstNodeRef,
+    };
+    use lex::{
+        lexer::{Lexer, LexerConfig},
+        span::Span,
+        token::{Keyword, Literal, Operator, Token},
     };
-    use lex::token::{Keyword, Literal, Operator, Token};
+    use stringreader::StringReader;
+
+    fn parse_source(source: &str) -> Result<Ast, ParserError> {
+        let reader = StringReader::new(source);
+        let tokens = Lexer::lex(BufReader::new(reader)).expect("failed to lex");
+        Parser::parse(&tokens)
+    }
+
+    fn parse_source_with_spans(source: &str) -> Ast {
+        let reader = StringReader::new(source);
+        let (tokens, spans) = Lexer::lex_with_spans(BufReader::new(reader), LexerConfig::default())
+            .expect("failed to lex");
+        Parser::parse_with_spans(&tokens, &spans).expect("failed to parse")
+    }
+
+    fn parse_with_naming_rules(source: &str, naming_rules: bool) -> Result<Ast, ParserError> {
+        let reader = StringReader::new(source);
+        let tokens = Lexer::lex(BufReader::new(reader)).expect("failed to lex");
+        Parser::with_naming_rules(naming_rules).parse_tokens(&tokens, &[])
+    }
+
+    /// `result` with the modules and functions an error happened in
+    /// stripped, for comparing against a bare `ParserError`.
+    fn without_context<T>(result: Result<T, ParserError>) -> Result<T, ParserError> {
+        result.map_err(|error| error.kind().clone())
+    }
+
+    /// Parses `source` and returns the initializer of the first statement of
+    /// the first function.
+    fn first_initializer(source: &str) -> Expression {
+        let ast = parse_source(source).expect("failed to parse");
+        let module = ast.root.unwrap();
+        match &module.functions[0].block.statements[0].kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(var),
+                ..
+            }) => *var.expression.clone().expect("Expected initializer"),
+            statement => panic!("Expected variable statement, got {:?}", statement),
+        }
+    }
+
+    fn var(name: &str) -> Expression {
+        ExpressionKind::Variable(Variable {
+            name: name.to_string(),
+            is_decl: false,
+            is_const: false,
+            expression: None,
+            type_info: None,
+        })
+        .into()
+    }
 
     #[test]
     fn test_parse_empty_module() {
@@ -24,11 +93,19 @@ mod tests {
             Ast {
                 root: Some(Module {
                     decl: Token::Keyword(Keyword::Module),
+                    decl_span: Span::default(),
                     name: "Main".to_string(),
                     imports: vec![],
+                    aliases: vec![],
                     modules: vec![],
                     functions: vec![],
+                    impls: vec![],
+                    traits: vec![],
+                    structs: vec![],
+                    enums: vec![],
                     variables: vec![],
+                    statements: vec![],
+                    span: Span::default(),
                 })
             }
         );
@@ -74,7 +151,10 @@ mod tests {
         let module = ast.root.unwrap();
 
         assert_eq!(module.imports.len(), 1);
-        assert_eq!(module.imports[0].path, "std/io");
+        assert_eq!(
+            module.imports[0].path,
+            ImportPath::File("std/io".to_string())
+        );
     }
 
     #[test]
@@ -87,7 +167,7 @@ mod tests {
             Token::Identifier("main".to_string()),
             Token::OpenParen,
             Token::CloseParen,
-            Token::Identifier("void".to_string()),
+            Token::Keyword(Keyword::Void),
             Token::OpenBrace,
             Token::CloseBrace,
         ];
@@ -104,7 +184,7 @@ mod tests {
         let function = &module.functions[0];
         assert_eq!(function.name, "main");
         assert!(function.parameters.is_empty());
-        assert_eq!(function.return_type.name, Some("void".to_string()));
+        assert_eq!(function.return_type.ty, Some(Type::Void));
         assert!(function.block.statements.is_empty());
     }
 
@@ -142,8 +222,8 @@ mod tests {
             Token::Identifier("x".to_string())
         );
         assert_eq!(
-            function.parameters[0].type_info.name,
-            Some("i32".to_string())
+            function.parameters[0].type_info.ty,
+            Some(Type::named("i32"))
         );
 
         assert_eq!(
@@ -151,11 +231,12 @@ mod tests {
             Token::Identifier("y".to_string())
         );
         assert_eq!(
-            function.parameters[1].type_info.name,
-            Some("i32".to_string())
+            function.parameters[1].type_info.ty,
+            Some(Type::named("i32"))
         );
 
-        assert_eq!(function.return_type.name, Some("i32".to_string()));
+        assert_eq!(function.return_type.ty, Some(Type::named("i32")));
+        assert!(!function.return_type.is_mut);
     }
 
     #[test]
@@ -165,8 +246,7 @@ mod tests {
             Token::Identifier("Main".to_string()),
             Token::Newline,
             Token::Identifier("x".to_string()),
-            Token::Operator(Operator::Assign),
-            Token::Operator(Operator::Reassign),
+            Token::Operator(Operator::Walrus),
             Token::Literal(Literal::Number("42".to_string())),
             Token::Newline,
         ];
@@ -187,8 +267,8 @@ mod tests {
             panic!("Expected variable expression");
         };
 
-        match &(**expr) {
-            Expression::Literal(Literal::Number(n)) => assert_eq!(n, "42"),
+        match &expr.kind {
+            ExpressionKind::Literal(Literal::Number(n)) => assert_eq!(n, "42"),
             _ => panic!("Expected number literal"),
         }
     }
@@ -206,7 +286,7 @@ mod tests {
             Token::Identifier("helper".to_string()),
             Token::OpenParen,
             Token::CloseParen,
-            Token::Identifier("void".to_string()),
+            Token::Keyword(Keyword::Void),
             Token::OpenBrace,
             Token::CloseBrace,
             Token::CloseBrace,
@@ -236,11 +316,10 @@ mod tests {
             Token::Identifier("main".to_string()),
             Token::OpenParen,
             Token::CloseParen,
-            Token::Identifier("void".to_string()),
+            Token::Keyword(Keyword::Void),
             Token::OpenBrace,
             Token::Identifier("x".to_string()),
-            Token::Operator(Operator::Assign),
-            Token::Operator(Operator::Reassign),
+            Token::Operator(Operator::Walrus),
             Token::Literal(Literal::Number("10".to_string())),
             Token::Newline,
             Token::CloseBrace,
@@ -256,13 +335,16 @@ mod tests {
 
         assert_eq!(function.block.statements.len(), 1);
 
-        match &function.block.statements[0] {
-            Statement::Expression(Expression::Variable(var)) => {
+        match &function.block.statements[0].kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(var),
+                ..
+            }) => {
                 assert_eq!(var.name, "x");
                 assert!(var.is_decl);
                 let expr = var.expression.as_ref().unwrap();
-                match &**expr {
-                    Expression::Literal(Literal::Number(n)) => assert_eq!(n, "10"),
+                match &expr.kind {
+                    ExpressionKind::Literal(Literal::Number(n)) => assert_eq!(n, "10"),
                     _ => panic!("Expected number literal"),
                 }
             }
@@ -280,9 +362,15 @@ mod tests {
             Token::Identifier("main".to_string()),
             Token::OpenParen,
             Token::CloseParen,
-            Token::Identifier("void".to_string()),
+            Token::Keyword(Keyword::Void),
             Token::OpenBrace,
             Token::Identifier("x".to_string()),
+            Token::Operator(Operator::Colon),
+            Token::Keyword(Keyword::Mut),
+            Token::Operator(Operator::Reassign),
+            Token::Literal(Literal::Number("10".to_string())),
+            Token::Newline,
+            Token::Identifier("x".to_string()),
             Token::Operator(Operator::Reassign),
             Token::Literal(Literal::Number("20".to_string())),
             Token::CloseBrace,
@@ -295,8 +383,11 @@ mod tests {
         let module = ast.root.unwrap();
         let function = &module.functions[0];
 
-        match &function.block.statements[0] {
-            Statement::Expression(Expression::Variable(var)) => {
+        match &function.block.statements[1].kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(var),
+                ..
+            }) => {
                 assert!(!var.is_decl); // This is a reassignment
                 assert_eq!(var.name, "x");
             }
@@ -305,98 +396,108 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_error_missing_module() {
-        let tokens = vec![
-            Token::Keyword(Keyword::Fn),
-            Token::Identifier("main".to_string()),
-        ];
-
-        let result = Parser::parse(&tokens);
-        println!("{:?}", result);
-        assert!(result.is_err());
+    fn test_parse_typed_and_untyped_declarations() {
+        let ast = parse_source(
+            "module Main\nfn main() {\n    a := 5\n    b :i32 = 5\n    c :mut = 5\n    d :mut []i32 = f()\n}\n",
+        )
+        .expect("failed to parse");
+        let statements = &ast.root.unwrap().functions[0].block.statements;
 
-        match result {
-            Err(ParserError::ModuleNotFound) => {}
-            _ => panic!("Expected ModuleNotFound error"),
+        let type_info = |ty, is_mut| TypeInfo { ty, is_mut };
+        let expected = [
+            type_info(None, false),
+            type_info(Some(Type::named("i32")), false),
+            type_info(None, true),
+            type_info(Some(Type::array(Type::named("i32"))), true),
+        ];
+        for (statement, expected) in statements.iter().zip(expected) {
+            let StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(variable),
+                ..
+            }) = &statement.kind
+            else {
+                panic!("Expected declaration, got {:?}", statement.kind);
+            };
+            assert!(variable.is_decl);
+            assert_eq!(variable.type_info, Some(expected));
         }
     }
 
     #[test]
-    fn test_parse_error_module_without_name() {
-        let tokens = vec![Token::Keyword(Keyword::Module), Token::OpenBrace];
-
-        let result = Parser::parse(&tokens);
-        assert!(result.is_err());
-
-        match result {
-            Err(ParserError::ModuleWithoutName) => {}
-            _ => panic!("Expected ModuleWithoutName error"),
-        }
+    fn test_parse_colon_needs_a_type_or_mut() {
+        let result = parse_source("module Main\nfn main() {\n    x : = 5\n}\n");
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::expected(
+                Token::Operator(Operator::Reassign),
+                &[Token::Identifier(String::new())]
+            ))
+        );
     }
 
     #[test]
-    fn test_parse_error_unexpected_end_of_input() {
+    fn test_parse_self_field_access() {
         let tokens = vec![
             Token::Keyword(Keyword::Module),
             Token::Identifier("Main".to_string()),
             Token::Newline,
             Token::Keyword(Keyword::Fn),
-            Token::Identifier("main".to_string()),
+            Token::Identifier("x".to_string()),
             Token::OpenParen,
-            // Missing the rest of the function
+            Token::CloseParen,
+            Token::Identifier("i32".to_string()),
+            Token::OpenBrace,
+            Token::Identifier("value".to_string()),
+            Token::Operator(Operator::Walrus),
+            Token::Keyword(Keyword::SelfKw),
+            Token::Dot,
+            Token::Identifier("x".to_string()),
+            Token::Newline,
+            Token::CloseBrace,
         ];
 
         let result = Parser::parse(&tokens);
-        assert!(result.is_err());
+        assert!(result.is_ok());
 
-        match result {
-            Err(ParserError::UnexpectedEndOfInput) => {}
-            _ => panic!("Expected UnexpectedEndOfInput error"),
+        let ast = result.unwrap();
+        let module = ast.root.unwrap();
+        let function = &module.functions[0];
+
+        match &function.block.statements[0].kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(var),
+                ..
+            }) => {
+                let expr = var.expression.as_ref().unwrap();
+                assert_eq!(
+                    **expr,
+                    ExpressionKind::FieldAccess {
+                        object: Box::new(ExpressionKind::SelfRef.into()),
+                        field: "x".to_string(),
+                    }
+                    .into()
+                );
+            }
+            _ => panic!("Expected variable expression"),
         }
     }
 
     #[test]
-    fn test_parse_complex_program() {
+    fn test_parse_self_standalone() {
         let tokens = vec![
-            // Module declaration
             Token::Keyword(Keyword::Module),
-            Token::Identifier("Calculator".to_string()),
-            Token::Newline,
-            // Import
-            Token::Keyword(Keyword::Import),
-            Token::Literal(Literal::String("std/math".to_string())),
-            Token::Newline,
-            // Global variable
-            Token::Identifier("PI".to_string()),
-            Token::Operator(Operator::Assign),
-            Token::Operator(Operator::Reassign),
-            Token::Literal(Literal::Number("3.14159".to_string())),
+            Token::Identifier("Main".to_string()),
             Token::Newline,
-            // Nested module
-            Token::Keyword(Keyword::Module),
-            Token::Identifier("Utils".to_string()),
-            Token::OpenBrace,
-            Token::Keyword(Keyword::Fn),
-            Token::Identifier("square".to_string()),
-            Token::OpenParen,
-            Token::Identifier("x".to_string()),
-            Token::Identifier("f64".to_string()),
-            Token::CloseParen,
-            Token::Identifier("f64".to_string()),
-            Token::OpenBrace,
-            Token::CloseBrace,
-            Token::CloseBrace,
-            // Main function
             Token::Keyword(Keyword::Fn),
-            Token::Identifier("main".to_string()),
+            Token::Identifier("get".to_string()),
             Token::OpenParen,
             Token::CloseParen,
-            Token::Identifier("void".to_string()),
+            Token::Identifier("Self".to_string()),
             Token::OpenBrace,
-            Token::Identifier("result".to_string()),
-            Token::Operator(Operator::Assign),
-            Token::Operator(Operator::Reassign),
-            Token::Literal(Literal::Number("42".to_string())),
+            Token::Identifier("this".to_string()),
+            Token::Operator(Operator::Walrus),
+            Token::Keyword(Keyword::SelfKw),
+            Token::Newline,
             Token::CloseBrace,
         ];
 
@@ -405,28 +506,2787 @@ mod tests {
 
         let ast = result.unwrap();
         let module = ast.root.unwrap();
+        let function = &module.functions[0];
 
-        // Check main module
-        assert_eq!(module.name, "Calculator");
-        assert_eq!(module.imports.len(), 1);
-        assert_eq!(module.imports[0].path, "std/math");
-        assert_eq!(module.variables.len(), 1);
-        assert_eq!(module.modules.len(), 1);
-        assert_eq!(module.functions.len(), 1);
+        match &function.block.statements[0].kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(var),
+                ..
+            }) => {
+                assert_eq!(var.name, "this");
+                assert_eq!(
+                    **var.expression.as_ref().unwrap(),
+                    ExpressionKind::SelfRef.into()
+                );
+            }
+            _ => panic!("Expected variable expression"),
+        }
+    }
 
-        // Check global variable
-        let global_var = &module.variables[0];
-        assert_eq!(global_var.name, "PI");
+    #[test]
+    fn test_parse_try_operator() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := parse()?\n}\n");
 
-        // Check nested module
-        let utils_module = &module.modules[0];
-        assert_eq!(utils_module.name, "Calculator.Utils");
-        assert_eq!(utils_module.functions.len(), 1);
-        assert_eq!(utils_module.functions[0].name, "square");
+        assert_eq!(
+            expr,
+            ExpressionKind::Try(Box::new(
+                ExpressionKind::Call {
+                    callee: Box::new(var("parse")),
+                    arguments: vec![],
+                }
+                .into()
+            ))
+            .into()
+        );
+    }
 
-        // Check main function
-        let main_fn = &module.functions[0];
-        assert_eq!(main_fn.name, "main");
-        assert_eq!(main_fn.block.statements.len(), 1);
+    fn unary(operator: Operator, operand: Expression) -> Expression {
+        ExpressionKind::Unary {
+            operator,
+            operand: Box::new(operand),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_parse_unary_plus_and_minus() {
+        let parse = |source: &str| {
+            first_initializer(&format!(
+                "module Main\nfn main() {{\n    x := {}\n}}\n",
+                source
+            ))
+        };
+
+        assert_eq!(parse("+5"), unary(Operator::Plus, number("5")));
+        assert_eq!(parse("-5"), unary(Operator::Minus, number("5")));
+        assert_eq!(
+            parse("-(a + b)"),
+            unary(
+                Operator::Minus,
+                ExpressionKind::Group {
+                    inner: Box::new(
+                        ExpressionKind::Binary {
+                            left: Box::new(var("a")),
+                            operator: Operator::Plus,
+                            right: Box::new(var("b")),
+                        }
+                        .into()
+                    ),
+                }
+                .into()
+            )
+        );
+        assert_eq!(
+            parse("- -x"),
+            unary(Operator::Minus, unary(Operator::Minus, var("x")))
+        );
+    }
+
+    #[test]
+    fn test_parse_unary_after_assignment() {
+        let ast = parse_source("module Main\nfn main() {\n    x := 1\n    x=-5\n}\n").unwrap();
+        let root = ast.root.unwrap();
+        let StatementKind::Expression(expr) = &root.functions[0].block.statements[1].kind else {
+            panic!("Expected an expression statement");
+        };
+        let ExpressionKind::Variable(variable) = &expr.kind else {
+            panic!("Expected an assignment, got {:?}", expr.kind);
+        };
+        assert_eq!(
+            variable.expression.as_deref(),
+            Some(&unary(Operator::Minus, number("5")))
+        );
+    }
+
+    const NEWLINE_TERMINATED: &str = "module Main\nx := 1\nfn main() {\n    y := x\n    if y > 1 {\n        print(y)\n    }\n    z := y\n}\n";
+    const SEMICOLON_TERMINATED: &str = "module Main\nx := 1;\nfn main() {\n    y := x;\n    if y > 1 {\n        print(y);\n    }\n    z := y;\n}\n";
+    const ONE_LINE: &str = "module Main\nfn main() { y := 1; z := y; print(z) }\n";
+
+    fn parse_terminated(source: &str, terminator: Terminator) -> Result<Ast, ParserError> {
+        let tokens = Lexer::lex(BufReader::new(StringReader::new(source))).expect("failed to lex");
+        Parser::default()
+            .terminator(terminator)
+            .parse_tokens(&tokens, &[])
+    }
+
+    #[test]
+    fn test_newline_terminator() {
+        let expected = parse_source(NEWLINE_TERMINATED).unwrap();
+        assert_eq!(
+            parse_terminated(NEWLINE_TERMINATED, Terminator::Newline).unwrap(),
+            expected
+        );
+
+        for source in [SEMICOLON_TERMINATED, ONE_LINE] {
+            let error = parse_terminated(source, Terminator::Newline).unwrap_err();
+            assert_eq!(error.kind(), &ParserError::unexpected(Token::Semicolon));
+        }
+    }
+
+    #[test]
+    fn test_semicolon_terminator() {
+        let expected = parse_source(NEWLINE_TERMINATED).unwrap();
+        assert_eq!(
+            parse_terminated(SEMICOLON_TERMINATED, Terminator::Semicolon).unwrap(),
+            expected
+        );
+        assert!(parse_terminated(ONE_LINE, Terminator::Semicolon).is_ok());
+
+        let error = parse_terminated(NEWLINE_TERMINATED, Terminator::Semicolon).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ParserError::expected(Token::Keyword(Keyword::Fn), &[Token::Semicolon])
+        );
+    }
+
+    #[test]
+    fn test_either_terminator() {
+        let expected = parse_source(NEWLINE_TERMINATED).unwrap();
+        for source in [NEWLINE_TERMINATED, SEMICOLON_TERMINATED] {
+            assert_eq!(
+                parse_terminated(source, Terminator::Either).unwrap(),
+                expected
+            );
+        }
+        assert!(parse_terminated(ONE_LINE, Terminator::Either).is_ok());
+    }
+
+    #[test]
+    fn test_statements_on_one_line_need_a_terminator() {
+        let source = "module Main\nfn main() {\n    x := 1 y := 2\n}\n";
+        for terminator in [Terminator::Newline, Terminator::Either] {
+            let error = parse_terminated(source, terminator).unwrap_err();
+            assert_eq!(
+                error.kind(),
+                &ParserError::MissingTerminator {
+                    found: Token::Identifier("y".to_string()),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn test_module_items_at_end_of_input() {
+        for source in [
+            "module Main\nx := 1",
+            "module Main\nx := a + b",
+            "module Main\nimport std.io",
+            "module Main\nfn main() {\n}",
+            "module Main\nstruct Unit()",
+        ] {
+            let with_newline = parse_source(&format!("{}\n", source)).unwrap();
+            assert_eq!(parse_source(source), Ok(with_newline), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_module_items_need_a_terminator() {
+        for (source, found) in [
+            ("module Main\nfn main() {\n} x := 1\n", "x"),
+            ("module Main\nimport std.io print := 1\n", "print"),
+            ("module Main\nmodule Net = std.net x := 1\n", "x"),
+        ] {
+            assert_eq!(
+                without_context(parse_source(source)),
+                Err(ParserError::MissingTerminator {
+                    found: Token::Identifier(found.to_string()),
+                }),
+                "{:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_terminator_after_function_is_outside_it() {
+        let error = parse_source("module Main\nfn f() {\n} x\n").unwrap_err();
+        assert_eq!(
+            error.context(),
+            vec![&ContextFrame::Module("Main".to_string())]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Missing statement terminator before `x`! (while parsing module `Main`)"
+        );
+    }
+
+    #[test]
+    fn test_missing_semicolon_is_recovered() {
+        let source = "module Main\nfn main() {\n    y := 1\n    z := y;\n}\n";
+        let tokens = Lexer::lex(BufReader::new(StringReader::new(source))).expect("failed to lex");
+        let (ast, errors) = Parser::default()
+            .terminator(Terminator::Semicolon)
+            .parse_recovering(&tokens, &[]);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(ast.root.unwrap().functions[0].block.statements.len(), 2);
+    }
+
+    fn match_patterns(arms: &str) -> Vec<Pattern> {
+        let expr = first_initializer(&format!(
+            "module Main\nfn main() {{\n    x := match y {{\n{}\n    }}\n}}\n",
+            arms
+        ));
+        let ExpressionKind::Match { scrutinee, arms } = expr.kind else {
+            panic!("Expected match, got {:?}", expr.kind);
+        };
+        assert_eq!(*scrutinee, var("y"));
+        arms.into_iter().map(|arm| arm.pattern).collect()
+    }
+
+    fn int(n: &str) -> Literal {
+        Literal::Number(n.to_string())
+    }
+
+    #[test]
+    fn test_parse_match_literal_pattern() {
+        assert_eq!(
+            match_patterns("        1 => a\n        \"b\" => b"),
+            vec![
+                Pattern::Literal(int("1")),
+                Pattern::Literal(Literal::String("b".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_match_range_pattern() {
+        assert_eq!(
+            match_patterns("        1..10 => a"),
+            vec![Pattern::Range {
+                start: int("1"),
+                end: int("10"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_match_or_pattern() {
+        assert_eq!(
+            match_patterns("        1 | 2 | 3 => a"),
+            vec![Pattern::Or(vec![
+                Pattern::Literal(int("1")),
+                Pattern::Literal(int("2")),
+                Pattern::Literal(int("3")),
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_parse_match_wildcard_and_binding_patterns() {
+        assert_eq!(
+            match_patterns("        n => n\n        _ => 0"),
+            vec![Pattern::Binding("n".to_string()), Pattern::Wildcard]
+        );
+    }
+
+    #[test]
+    fn test_parse_match_mixed_patterns() {
+        let expr = first_initializer(
+            "module Main\nfn main() {\n    x := match y { 0 => a, 1 | 2 => b, 3..10 => c, n => n, _ => d }\n}\n",
+        );
+        let ExpressionKind::Match { arms, .. } = expr.kind else {
+            panic!("Expected match, got {:?}", expr.kind);
+        };
+
+        let patterns: Vec<_> = arms.iter().map(|arm| arm.pattern.to_string()).collect();
+        assert_eq!(patterns, ["0", "1 | 2", "3..10", "n", "_"]);
+        let bodies: Vec<_> = arms.into_iter().map(|arm| arm.body).collect();
+        assert_eq!(bodies, [var("a"), var("b"), var("c"), var("n"), var("d")]);
+    }
+
+    #[test]
+    fn test_parse_match_rejects_non_pattern() {
+        let result = parse_source(
+            "module Main\nfn main() {\n    x := match y {\n        (1) => a\n    }\n}\n",
+        );
+        let error = result.unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ParserError::UnexpectedToken {
+                found: Token::OpenParen,
+                expected: vec!["pattern".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_complement_binds_tighter_than_plus() {
+        let expr = first_initializer("module Main\nfn main() {\n    y := ~x + 1\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Binary {
+                left: Box::new(
+                    ExpressionKind::Unary {
+                        operator: Operator::Tilde,
+                        operand: Box::new(var("x")),
+                    }
+                    .into()
+                ),
+                operator: Operator::Plus,
+                right: Box::new(number("1")),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_exponent_is_right_associative() {
+        let expr = first_initializer("module Main\nfn main() {\n    y := a * 2 ^ 3 ^ b\n}\n");
+        let power = |left, right| -> Expression {
+            ExpressionKind::Binary {
+                left: Box::new(left),
+                operator: Operator::Caret,
+                right: Box::new(right),
+            }
+            .into()
+        };
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Binary {
+                left: Box::new(var("a")),
+                operator: Operator::Star,
+                right: Box::new(power(number("2"), power(number("3"), var("b")))),
+            }
+            .into()
+        );
+
+        let chain = format!(
+            "module Main\nx := 2{}\n",
+            " ^ 2".repeat(crate::DEFAULT_RECURSION_LIMIT)
+        );
+        assert!(matches!(
+            without_context(parse_source(&chain)),
+            Err(ParserError::RecursionLimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_ternary() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := a ? b : c\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Ternary {
+                condition: Box::new(var("a")),
+                then_branch: Box::new(var("b")),
+                else_branch: Box::new(var("c")),
+            }
+            .into()
+        );
+    }
+
+    fn value_block(value: Expression) -> Block {
+        Block {
+            statements: vec![StatementKind::Expression(value).into()],
+        }
+    }
+
+    #[test]
+    fn test_parse_if_expression_initializer() {
+        let expr =
+            first_initializer("module Main\nfn main() {\n    x := if cond { 1 } else { 2 }\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::If {
+                condition: IfCondition::Expr(Box::new(var("cond"))),
+                then_branch: value_block(number("1")),
+                else_branch: Some(value_block(number("2"))),
+            }
+            .into()
+        );
+        let ExpressionKind::If { then_branch, .. } = &expr.kind else {
+            unreachable!()
+        };
+        assert_eq!(then_branch.value(), Some(&number("1")));
+    }
+
+    #[test]
+    fn test_parse_if_condition_with_and_without_parens() {
+        let bare = first_initializer(
+            "module Main\nfn main() {\n    r := if x > 5 && y < 10 { 1 } else { 2 }\n}\n",
+        );
+        let parenthesized = first_initializer(
+            "module Main\nfn main() {\n    r := if (x > 5 && y < 10) { 1 } else { 2 }\n}\n",
+        );
+
+        let condition = ExpressionKind::And {
+            left: Box::new(
+                ExpressionKind::Binary {
+                    left: Box::new(var("x")),
+                    operator: Operator::Greater,
+                    right: Box::new(number("5")),
+                }
+                .into(),
+            ),
+            right: Box::new(
+                ExpressionKind::Binary {
+                    left: Box::new(var("y")),
+                    operator: Operator::Less,
+                    right: Box::new(number("10")),
+                }
+                .into(),
+            ),
+        };
+        let ExpressionKind::If {
+            condition: IfCondition::Expr(bare),
+            ..
+        } = bare.kind
+        else {
+            panic!("Expected if expression, got {:?}", bare.kind);
+        };
+        let ExpressionKind::If {
+            condition: IfCondition::Expr(parenthesized),
+            ..
+        } = parenthesized.kind
+        else {
+            panic!("Expected if expression, got {:?}", parenthesized.kind);
+        };
+
+        assert_eq!(*bare, condition.clone().into());
+        // A parenthesized condition is a grouped expression
+        assert_eq!(
+            *parenthesized,
+            ExpressionKind::Group {
+                inner: Box::new(condition.into())
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_logical_and_binds_tighter_than_or() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := a || b && c\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Or {
+                left: Box::new(var("a")),
+                right: Box::new(
+                    ExpressionKind::And {
+                        left: Box::new(var("b")),
+                        right: Box::new(var("c")),
+                    }
+                    .into()
+                ),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_logical_operators_bind_looser_than_comparison() {
+        let comparison = |left: &str, operator, right: &str| -> Expression {
+            ExpressionKind::Binary {
+                left: Box::new(var(left)),
+                operator,
+                right: Box::new(var(right)),
+            }
+            .into()
+        };
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := a < b && c == d\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::And {
+                left: Box::new(comparison("a", Operator::Less, "b")),
+                right: Box::new(comparison("c", Operator::Equal, "d")),
+            }
+            .into()
+        );
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := a >= b || c != d\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Or {
+                left: Box::new(comparison("a", Operator::GreaterEqual, "b")),
+                right: Box::new(comparison("c", Operator::NotEqual, "d")),
+            }
+            .into()
+        );
+    }
+
+    fn field(name: &str, value: Expression) -> FieldInit {
+        FieldInit {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_parse_struct_update_with_override() {
+        let expr =
+            first_initializer("module Main\nfn main() {\n    p := Point { ..base, x := 5 }\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::StructLiteral {
+                name: "Point".to_string(),
+                base: Some(Box::new(var("base"))),
+                fields: vec![field("x", number("5"))],
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_struct_update_without_overrides() {
+        let expr =
+            first_initializer("module Main\nfn main() {\n    p := Point { ..origin() }\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::StructLiteral {
+                name: "Point".to_string(),
+                base: Some(Box::new(
+                    ExpressionKind::Call {
+                        callee: Box::new(var("origin")),
+                        arguments: vec![],
+                    }
+                    .into()
+                )),
+                fields: vec![],
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_struct_literal_without_spread() {
+        let expr = first_initializer(
+            "module Main\nfn main() {\n    p := Point {\n        x := 1\n        y := 2,\n    }\n}\n",
+        );
+
+        assert_eq!(
+            expr,
+            ExpressionKind::StructLiteral {
+                name: "Point".to_string(),
+                base: None,
+                fields: vec![field("x", number("1")), field("y", number("2"))],
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_struct_literal_not_in_if_condition() {
+        let expr =
+            first_initializer("module Main\nfn main() {\n    x := if ready { 1 } else { 2 }\n}\n");
+        assert!(matches!(
+            expr.kind,
+            ExpressionKind::If {
+                condition: IfCondition::Expr(ref condition),
+                ..
+            } if **condition == var("ready")
+        ));
+
+        // Parenthesized, it is a struct literal again
+        let source = "module Main\nfn main() {\n    x := if (p == Point { x := 1 }) { 1 }\n}\n";
+        assert!(parse_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_parse_path() {
+        let expr =
+            first_initializer("module Main\nfn main() {\n    x := std::io::println(\"hi\")\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Call {
+                callee: Box::new(
+                    ExpressionKind::Path(vec![
+                        "std".to_string(),
+                        "io".to_string(),
+                        "println".to_string()
+                    ])
+                    .into()
+                ),
+                arguments: vec![ExpressionKind::Literal(Literal::String("hi".to_string())).into()],
+            }
+            .into()
+        );
+
+        // A `.` is field access, whatever is on its left
+        let expr = first_initializer("module Main\nfn main() {\n    x := obj.field\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::FieldAccess {
+                object: Box::new(var("obj")),
+                field: "field".to_string(),
+            }
+            .into()
+        );
+
+        assert!(parse_source("module Main\nfn main() {\n    x := std::\n}\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_record() {
+        let expr = first_initializer(
+            "module Main\nfn main() {\n    p := {\n        x := 1, y := 2\n    }\n}\n",
+        );
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Record(vec![field("x", number("1")), field("y", number("2"))]).into()
+        );
+    }
+
+    #[test]
+    fn test_parse_record_or_block() {
+        // Where an expression is expected, `name :=` first makes a record
+        let expr = first_initializer("module Main\nfn main() {\n    p := { x := 1 }\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Record(vec![field("x", number("1"))]).into()
+        );
+
+        // A `{` starting a statement is always a block
+        let ast = parse_source("module Main\nfn main() {\n    {\n        x := 1\n    }\n}\n")
+            .expect("failed to parse");
+        let statements = &ast.root.unwrap().functions[0].block.statements;
+        assert!(matches!(statements[0].kind, StatementKind::Block(_)));
+
+        // Without a first field there is nothing to tell a record by
+        assert!(parse_source("module Main\nfn main() {\n    p := {}\n}\n").is_err());
+        assert!(parse_source("module Main\nfn main() {\n    p := { 1 }\n}\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_record_in_ternary() {
+        let record =
+            || -> Expression { ExpressionKind::Record(vec![field("k", number("1"))]).into() };
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := c ? { k := 1 } : b\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Ternary {
+                condition: Box::new(var("c")),
+                then_branch: Box::new(record()),
+                else_branch: Box::new(var("b")),
+            }
+            .into()
+        );
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := c ? a : { k := 1 }\n}\n");
+        assert!(matches!(expr.kind, ExpressionKind::Ternary { .. }));
+    }
+
+    #[test]
+    fn test_parse_return() {
+        let ast = parse_source("module Main\nfn main() {\n    return 1 + 2\n    return\n}\n")
+            .expect("failed to parse");
+        let statements = &ast.root.unwrap().functions[0].block.statements;
+
+        assert_eq!(
+            statements[0].kind,
+            StatementKind::Return(Some(
+                ExpressionKind::Binary {
+                    left: Box::new(number("1")),
+                    operator: Operator::Plus,
+                    right: Box::new(number("2")),
+                }
+                .into()
+            ))
+        );
+        assert_eq!(statements[1].kind, StatementKind::Return(None));
+    }
+
+    #[test]
+    fn test_parse_yield() {
+        let ast = parse_source("module Main\nfn count(x i32) {\n    yield x\n    yield x + 1\n}\n")
+            .expect("failed to parse");
+        let statements = &ast.root.unwrap().functions[0].block.statements;
+
+        assert_eq!(statements[0].kind, StatementKind::Yield(var("x")));
+        assert_eq!(
+            statements[1].kind,
+            StatementKind::Yield(
+                ExpressionKind::Binary {
+                    left: Box::new(var("x")),
+                    operator: Operator::Plus,
+                    right: Box::new(number("1")),
+                }
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_yield_or_return_outside_function() {
+        assert_eq!(
+            without_context(parse_source("module Main\nyield 1\n")),
+            Err(ParserError::unexpected(Token::Keyword(Keyword::Yield)))
+        );
+        assert_eq!(
+            without_context(parse_source(
+                "module Main\nmodule Util {\n    return 1\n}\n"
+            )),
+            Err(ParserError::unexpected(Token::Keyword(Keyword::Return)))
+        );
+    }
+
+    #[test]
+    fn test_parse_dangling_else() {
+        assert_eq!(
+            without_context(parse_source(
+                "module Main\nfn main() {\n    x := 1\n    else {\n        x = 2\n    }\n}\n"
+            )),
+            Err(ParserError::DanglingElse)
+        );
+        assert_eq!(
+            without_context(parse_source("module Main\nelse { }\n")),
+            Err(ParserError::DanglingElse)
+        );
+        // Attached to its `if`, `else` is fine
+        assert!(
+            parse_source("module Main\nfn main() {\n    if true { 1 } else { 2 }\n}\n").is_ok()
+        );
+    }
+
+    #[test]
+    fn test_parse_assert() {
+        let ast = parse_source(
+            "module Main\nfn main() {\n    x := 1\n    assert x > 0\n    assert x > 0, \"must be positive\"\n}\n",
+        )
+        .expect("failed to parse");
+        let statements = &ast.root.unwrap().functions[0].block.statements;
+
+        let condition: Expression = ExpressionKind::Binary {
+            left: Box::new(var("x")),
+            operator: Operator::Greater,
+            right: Box::new(number("0")),
+        }
+        .into();
+        assert_eq!(
+            statements[1].kind,
+            StatementKind::Assert {
+                condition: condition.clone(),
+                message: None,
+            }
+        );
+        assert_eq!(
+            statements[2].kind,
+            StatementKind::Assert {
+                condition,
+                message: Some(
+                    ExpressionKind::Literal(Literal::String("must be positive".to_string())).into()
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_if_statement_with_parenthesized_condition() {
+        let bare =
+            "module Main\nfn main() {\n    x := 0\n    if x > 5 {\n        x = 1\n    }\n}\n";
+        let parenthesized =
+            "module Main\nfn main() {\n    x := 0\n    if (x > 5) {\n        x = 1\n    }\n}\n";
+
+        assert!(parse_source(bare).is_ok());
+        assert!(parse_source(parenthesized).is_ok());
+    }
+
+    #[test]
+    fn test_parse_if_binding_condition() {
+        let expr = first_initializer(
+            "module Main\nfn main() {\n    y := if x := optional() { x } else { 0 }\n}\n",
+        );
+
+        let ExpressionKind::If {
+            condition: IfCondition::Binding(binding),
+            then_branch,
+            ..
+        } = expr.kind
+        else {
+            panic!("Expected if with binding, got {:?}", expr.kind);
+        };
+        assert_eq!(binding.name, "x");
+        assert!(binding.is_decl);
+        assert_eq!(
+            binding.expression.map(|e| e.kind),
+            Some(ExpressionKind::Call {
+                callee: Box::new(var("optional")),
+                arguments: vec![],
+            })
+        );
+        assert_eq!(then_branch.value(), Some(&var("x")));
+    }
+
+    #[test]
+    fn test_parse_if_binding_scope() {
+        let in_then =
+            "module Main\nfn main() {\n    if x := optional() {\n        x = 1\n    }\n}\n";
+        assert!(parse_source(in_then).is_ok());
+
+        let in_else = "module Main\nfn main() {\n    if x := optional() {\n    } else {\n        x = 1\n    }\n}\n";
+        assert_eq!(
+            without_context(parse_source(in_else)),
+            Err(ParserError::UndeclaredVariable("x".to_string()))
+        );
+
+        let after = "module Main\nfn main() {\n    if x := optional() {\n    }\n    x = 1\n}\n";
+        assert_eq!(
+            without_context(parse_source(after)),
+            Err(ParserError::UndeclaredVariable("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_if_expression_in_binary() {
+        let expr = first_initializer(
+            "module Main\nfn main() {\n    x := 10 + if a > b {\n        y := a\n        y\n    } else {\n        b\n    }\n}\n",
+        );
+
+        let ExpressionKind::Binary {
+            left,
+            operator,
+            right,
+        } = expr.kind
+        else {
+            panic!("Expected binary expression, got {:?}", expr.kind);
+        };
+        assert_eq!(*left, number("10"));
+        assert_eq!(operator, Operator::Plus);
+        let ExpressionKind::If {
+            then_branch,
+            else_branch,
+            ..
+        } = right.kind
+        else {
+            panic!("Expected if expression, got {:?}", right.kind);
+        };
+        assert_eq!(then_branch.statements.len(), 2);
+        assert_eq!(then_branch.value(), Some(&var("y")));
+        assert_eq!(else_branch.unwrap().value(), Some(&var("b")));
+    }
+
+    #[test]
+    fn test_parse_try_as_ternary_condition() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := ok()? ? b : c\n}\n");
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Ternary {
+                condition: Box::new(
+                    ExpressionKind::Try(Box::new(
+                        ExpressionKind::Call {
+                            callee: Box::new(var("ok")),
+                            arguments: vec![],
+                        }
+                        .into()
+                    ))
+                    .into()
+                ),
+                then_branch: Box::new(var("b")),
+                else_branch: Box::new(var("c")),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_many_try_operators() {
+        let terms: Vec<String> = (0..64).map(|i| format!("a{}?", i)).collect();
+        let expr = first_initializer(&format!(
+            "module Main\nfn main() {{\n    x := {}\n}}\n",
+            terms.join(" + ")
+        ));
+
+        let mut tries = 0;
+        let mut expr = &expr;
+        while let ExpressionKind::Binary { left, right, .. } = &expr.kind {
+            assert!(matches!(right.kind, ExpressionKind::Try(_)));
+            tries += 1;
+            expr = left;
+        }
+        assert!(matches!(expr.kind, ExpressionKind::Try(_)));
+        assert_eq!(tries + 1, 64);
+    }
+
+    #[test]
+    fn test_parse_try_inside_ternary() {
+        let try_var =
+            |name: &str| -> Expression { ExpressionKind::Try(Box::new(var(name))).into() };
+        let expr = first_initializer("module Main\nfn main() {\n    x := c ? a? : b?\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Ternary {
+                condition: Box::new(var("c")),
+                then_branch: Box::new(try_var("a")),
+                else_branch: Box::new(try_var("b")),
+            }
+            .into()
+        );
+
+        // The `:` belongs to the ternary inside the parentheses
+        let expr = first_initializer("module Main\nfn main() {\n    x := a? + (c ? d : e)\n}\n");
+        assert!(matches!(expr.kind, ExpressionKind::Binary { .. }));
+        let expr = first_initializer("module Main\nfn main() {\n    x := a? + c ? d : e\n}\n");
+        assert!(matches!(expr.kind, ExpressionKind::Ternary { .. }));
+    }
+
+    fn number(n: &str) -> Expression {
+        ExpressionKind::Literal(Literal::Number(n.to_string())).into()
+    }
+
+    fn slice(start: Option<Expression>, end: Option<Expression>) -> Expression {
+        ExpressionKind::Slice {
+            object: Box::new(var("arr")),
+            range: Range {
+                start: start.map(Box::new),
+                end: end.map(Box::new),
+            },
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_parse_slice() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := arr[1..3]\n}\n");
+        assert_eq!(expr, slice(Some(number("1")), Some(number("3"))));
+    }
+
+    #[test]
+    fn test_parse_slice_open_start() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := arr[..2]\n}\n");
+        assert_eq!(expr, slice(None, Some(number("2"))));
+    }
+
+    #[test]
+    fn test_parse_slice_open_end() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := arr[2..]\n}\n");
+        assert_eq!(expr, slice(Some(number("2")), None));
+    }
+
+    #[test]
+    fn test_parse_full_slice() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := arr[..]\n}\n");
+        assert_eq!(expr, slice(None, None));
+    }
+
+    #[test]
+    fn test_parse_index() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := arr[0]\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Index {
+                object: Box::new(var("arr")),
+                index: Box::new(number("0")),
+            }
+            .into()
+        );
+    }
+
+    fn method_call() -> ExpressionKind {
+        ExpressionKind::Call {
+            callee: Box::new(
+                ExpressionKind::FieldAccess {
+                    object: Box::new(var("obj")),
+                    field: "method".to_string(),
+                }
+                .into(),
+            ),
+            arguments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_call_statements_in_module() {
+        let ast = parse_source("module Main\nfoo()\nobj.method()\nLIMIT := 1\n").unwrap();
+        let module = ast.root.unwrap();
+        let kinds: Vec<_> = module
+            .statements
+            .iter()
+            .map(|statement| statement.kind.clone())
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                StatementKind::Expression(
+                    ExpressionKind::Call {
+                        callee: Box::new(var("foo")),
+                        arguments: vec![],
+                    }
+                    .into()
+                ),
+                StatementKind::Expression(method_call().into()),
+            ]
+        );
+        assert_eq!(module.variables[0].name, "LIMIT");
+    }
+
+    #[test]
+    fn test_parse_call_statements_in_block() {
+        let ast =
+            parse_source("module Main\nfn main() {\n    foo()\n    obj.method()\n}\n").unwrap();
+        let statements = &ast.root.unwrap().functions[0].block.statements;
+
+        assert_eq!(statements.len(), 2);
+        assert_eq!(
+            statements[1].kind,
+            StatementKind::Expression(method_call().into())
+        );
+    }
+
+    fn optional(object: Expression, field: &str) -> Expression {
+        ExpressionKind::OptionalAccess {
+            object: Box::new(object),
+            field: field.to_string(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_parse_optional_access() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := a?.b\n}\n");
+        assert_eq!(expr, optional(var("a"), "b"));
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := a?.b()\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Call {
+                callee: Box::new(optional(var("a"), "b")),
+                arguments: vec![],
+            }
+            .into()
+        );
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := a?.b?.c\n}\n");
+        assert_eq!(expr, optional(optional(var("a"), "b"), "c"));
+    }
+
+    #[test]
+    fn test_parse_optional_access_beside_try_and_ternary() {
+        let expr = first_initializer("module Main\nfn main() {\n    x := a()?.b\n}\n");
+        assert!(matches!(expr.kind, ExpressionKind::OptionalAccess { .. }));
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := a()?\n}\n");
+        assert!(matches!(expr.kind, ExpressionKind::Try(_)));
+
+        let expr = first_initializer("module Main\nfn main() {\n    x := a ? b.c : d\n}\n");
+        assert!(matches!(expr.kind, ExpressionKind::Ternary { .. }));
+    }
+
+    #[test]
+    fn test_parse_index_expressions() {
+        let index = |source: &str| {
+            let expr = first_initializer(&format!(
+                "module Main\nfn main() {{\n    x := arr[{}]\n}}\n",
+                source
+            ));
+            match expr.kind {
+                ExpressionKind::Index { object, index } => {
+                    assert_eq!(*object, var("arr"));
+                    index.kind
+                }
+                kind => panic!("Expected index, got {:?}", kind),
+            }
+        };
+
+        assert_eq!(
+            index("-1"),
+            ExpressionKind::Unary {
+                operator: Operator::Minus,
+                operand: Box::new(number("1")),
+            }
+        );
+        assert_eq!(
+            index("n-1"),
+            ExpressionKind::Binary {
+                left: Box::new(var("n")),
+                operator: Operator::Minus,
+                right: Box::new(number("1")),
+            }
+        );
+        assert_eq!(
+            index("f(x)"),
+            ExpressionKind::Call {
+                callee: Box::new(var("f")),
+                arguments: vec![var("x")],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_const_fn() {
+        let ast = parse_source("module Main\nconst fn square(x i32) -> i32 {\n}\nfn main() {\n}\n")
+            .unwrap();
+        let module = ast.root.unwrap();
+
+        assert_eq!(module.functions.len(), 2);
+
+        let square = &module.functions[0];
+        assert_eq!(square.name, "square");
+        assert!(square.is_const);
+        assert_eq!(square.decl, Token::Keyword(Keyword::Fn));
+        assert_eq!(square.return_type.ty, Some(Type::named("i32")));
+
+        let main = &module.functions[1];
+        assert_eq!(main.name, "main");
+        assert!(!main.is_const);
+    }
+
+    #[test]
+    fn test_parse_const_variable() {
+        let ast =
+            parse_source("module Main\nconst LIMIT := 10\nfn main() {\n    const x := 1\n}\n")
+                .unwrap();
+        let module = ast.root.unwrap();
+
+        let limit = &module.variables[0];
+        assert_eq!(limit.name, "LIMIT");
+        assert!(limit.is_decl);
+        assert!(limit.is_const);
+
+        match &module.functions[0].block.statements[0].kind {
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(var),
+                ..
+            }) => {
+                assert_eq!(var.name, "x");
+                assert!(var.is_const);
+            }
+            _ => panic!("Expected variable expression"),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_const_reassignment() {
+        let result = parse_source("module Main\nfn main() {\n    const x = 1\n}\n");
+
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::unexpected(Token::Keyword(Keyword::Const)))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_keyword_as_function_name() {
+        let result = without_context(parse_source("module Main\nfn module() {\n}\n"));
+
+        assert_eq!(result, Err(ParserError::ReservedKeyword(Keyword::Module)));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "`module` is a reserved keyword and cannot be used as a name!"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_keyword_as_parameter_name() {
+        let result = parse_source("module Main\nfn f(if i32) {\n}\n");
+
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::ReservedKeyword(Keyword::If))
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_module_forms() {
+        for source in [
+            "module Foo",
+            "module Foo\n",
+            "module Foo {}",
+            "module Foo { }",
+            "module Foo {\n}\n",
+        ] {
+            let module = parse_source(source)
+                .unwrap_or_else(|error| panic!("{:?} failed: {:?}", source, error))
+                .root
+                .unwrap();
+            assert_eq!(module.name, "Foo", "{:?}", source);
+            assert!(module.functions.is_empty(), "{:?}", source);
+            assert!(module.modules.is_empty(), "{:?}", source);
+            assert!(module.variables.is_empty(), "{:?}", source);
+        }
+    }
+
+    #[test]
+    fn test_parse_braced_module_body() {
+        let module = parse_source("module Foo {\n    fn main() {\n    }\n}\n")
+            .unwrap()
+            .root
+            .unwrap();
+        assert_eq!(module.functions[0].name, "main");
+    }
+
+    #[test]
+    fn test_parse_error_item_after_braced_module() {
+        assert_eq!(
+            parse_source("module Foo {}\nfn main() {\n}\n"),
+            Err(ParserError::unexpected(Token::Keyword(Keyword::Fn)))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_unclosed_braced_module() {
+        for source in [
+            "module Foo {\nfn f() {\n}\n",
+            "module Main\nmodule Net {\n    fn f() {\n    }\n",
+        ] {
+            assert_eq!(
+                without_context(parse_source(source)),
+                Err(ParserError::UnexpectedEndOfInput),
+                "{:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_error_close_brace_in_unbraced_module() {
+        assert_eq!(
+            without_context(parse_source(
+                "module Foo\nfn f() {\n}\n}\nfn g() {\n    x = )\n}\n"
+            )),
+            Err(ParserError::unexpected(Token::CloseBrace))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_eof_inside_function() {
+        let tokens = vec![
+            Token::Keyword(Keyword::Module),
+            Token::Identifier("Main".to_string()),
+            Token::Newline,
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("main".to_string()),
+            Token::OpenParen,
+            Token::Eof,
+        ];
+
+        assert_eq!(
+            without_context(Parser::parse(&tokens)),
+            Err(ParserError::UnexpectedEndOfInput)
+        );
+    }
+
+    #[test]
+    fn test_parse_error_at_lexed_eof() {
+        for source in [
+            "module Main\nfn main(",
+            "module Main\nstruct Point {\n    x i32,",
+            "module Main\nenum Status { Ok,",
+            "module Main\nfn main() {\n    x := f(1, 2",
+        ] {
+            assert_eq!(
+                without_context(parse_source(source)),
+                Err(ParserError::UnexpectedEndOfInput),
+                "source {:?}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_error_missing_module() {
+        let tokens = vec![
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("main".to_string()),
+        ];
+
+        let result = Parser::parse(&tokens);
+        println!("{:?}", result);
+        assert!(result.is_err());
+
+        match result {
+            Err(ParserError::ModuleNotFound) => {}
+            _ => panic!("Expected ModuleNotFound error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_module_without_name() {
+        let tokens = vec![Token::Keyword(Keyword::Module), Token::OpenBrace];
+
+        let result = Parser::parse(&tokens);
+        assert!(result.is_err());
+
+        match result {
+            Err(ParserError::ModuleWithoutName) => {}
+            _ => panic!("Expected ModuleWithoutName error"),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_unexpected_end_of_input() {
+        let tokens = vec![
+            Token::Keyword(Keyword::Module),
+            Token::Identifier("Main".to_string()),
+            Token::Newline,
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("main".to_string()),
+            Token::OpenParen,
+            // Missing the rest of the function
+        ];
+
+        let result = Parser::parse(&tokens);
+        assert!(result.is_err());
+
+        match without_context(result) {
+            Err(ParserError::UnexpectedEndOfInput) => {}
+            _ => panic!("Expected UnexpectedEndOfInput error"),
+        }
+    }
+
+    fn complex_program_tokens() -> Vec<Token> {
+        vec![
+            // Module declaration
+            Token::Keyword(Keyword::Module),
+            Token::Identifier("Calculator".to_string()),
+            Token::Newline,
+            // Import
+            Token::Keyword(Keyword::Import),
+            Token::Literal(Literal::String("std/math".to_string())),
+            Token::Newline,
+            // Global variable
+            Token::Identifier("PI".to_string()),
+            Token::Operator(Operator::Walrus),
+            Token::Literal(Literal::Number("3.14159".to_string())),
+            Token::Newline,
+            // Nested module
+            Token::Keyword(Keyword::Module),
+            Token::Identifier("Utils".to_string()),
+            Token::OpenBrace,
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("square".to_string()),
+            Token::OpenParen,
+            Token::Identifier("x".to_string()),
+            Token::Identifier("f64".to_string()),
+            Token::CloseParen,
+            Token::Identifier("f64".to_string()),
+            Token::OpenBrace,
+            Token::CloseBrace,
+            Token::CloseBrace,
+            // Main function
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("main".to_string()),
+            Token::OpenParen,
+            Token::CloseParen,
+            Token::Keyword(Keyword::Void),
+            Token::OpenBrace,
+            Token::Identifier("result".to_string()),
+            Token::Operator(Operator::Walrus),
+            Token::Literal(Literal::Number("42".to_string())),
+            Token::CloseBrace,
+        ]
+    }
+
+    #[test]
+    fn test_parse_complex_program() {
+        let result = Parser::parse(&complex_program_tokens());
+        assert!(result.is_ok());
+
+        let ast = result.unwrap();
+        let module = ast.root.unwrap();
+
+        // Check main module
+        assert_eq!(module.name, "Calculator");
+        assert_eq!(module.imports.len(), 1);
+        assert_eq!(
+            module.imports[0].path,
+            ImportPath::File("std/math".to_string())
+        );
+        assert_eq!(module.variables.len(), 1);
+        assert_eq!(module.modules.len(), 1);
+        assert_eq!(module.functions.len(), 1);
+
+        // Check global variable
+        let global_var = &module.variables[0];
+        assert_eq!(global_var.name, "PI");
+
+        // Check nested module
+        let utils_module = &module.modules[0];
+        assert_eq!(utils_module.name, "Calculator.Utils");
+        assert_eq!(utils_module.functions.len(), 1);
+        assert_eq!(utils_module.functions[0].name, "square");
+
+        // Check main function
+        let main_fn = &module.functions[0];
+        assert_eq!(main_fn.name, "main");
+        assert_eq!(main_fn.block.statements.len(), 1);
+    }
+
+    #[test]
+    fn test_walk_preorder() {
+        let ast = Parser::parse(&complex_program_tokens()).unwrap();
+        let nodes: Vec<String> = ast
+            .walk_preorder()
+            .map(|node| match node {
+                AstNodeRef::Module(module) => format!("module {}", module.name),
+                AstNodeRef::Function(function) => format!("fn {}", function.name),
+                AstNodeRef::Block(_) => "block".to_string(),
+                AstNodeRef::Statement(_) => "statement".to_string(),
+                AstNodeRef::Expression(expression) => match &expression.kind {
+                    ExpressionKind::Literal(literal) => format!("literal {}", literal),
+                    ExpressionKind::Variable(_) => "variable expression".to_string(),
+                    kind => panic!("Unexpected expression {:?}", kind),
+                },
+                AstNodeRef::Variable(variable) => format!("variable {}", variable.name),
+                node => panic!("Unexpected node {:?}", node),
+            })
+            .collect();
+
+        assert_eq!(
+            nodes,
+            [
+                "module Calculator",
+                "variable PI",
+                "literal 3.14159",
+                "module Calculator.Utils",
+                "fn square",
+                "block",
+                "fn main",
+                "block",
+                "statement",
+                "variable expression",
+                "variable result",
+                "literal 42",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_walk_preorder_in_source_order() {
+        let ast = parse_source_with_spans(
+            "module Main\nfn a() {\n}\nX := 1\nmodule Inner {\n}\nprint(X)\nfn b() {\n}\nY := 2\n",
+        );
+        let items: Vec<String> = ast
+            .walk_preorder()
+            .filter_map(|node| match node {
+                AstNodeRef::Module(module) => Some(format!("module {}", module.name)),
+                AstNodeRef::Function(function) => Some(format!("fn {}", function.name)),
+                AstNodeRef::Statement(_) => Some("statement".to_string()),
+                AstNodeRef::Variable(variable) if variable.is_decl => {
+                    Some(format!("variable {}", variable.name))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            items,
+            [
+                "module Main",
+                "fn a",
+                "variable X",
+                "module Main.Inner",
+                "statement",
+                "fn b",
+                "variable Y",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binary_expression_span() {
+        let source = "module Main\nfn main() {\n    x := a + b * 2\n}\n";
+        let ast = parse_source_with_spans(source);
+        let function = &ast.root.unwrap().functions[0];
+
+        let StatementKind::Expression(Expression {
+            kind: ExpressionKind::Variable(var),
+            ..
+        }) = &function.block.statements[0].kind
+        else {
+            panic!("Expected variable statement");
+        };
+        let expr = var.expression.as_ref().unwrap();
+
+        assert!(matches!(expr.kind, ExpressionKind::Binary { .. }));
+        assert_eq!(
+            &source[expr.span.start.offset..expr.span.end.offset],
+            "a + b * 2"
+        );
+        assert_eq!(expr.span.start.line, 3);
+        assert_eq!(expr.span.start.column, 10);
+    }
+
+    #[test]
+    fn test_function_span() {
+        let source = "module Main\n\nconst fn square(x i32) -> i32 {\n    y := x * x\n}\n";
+        let ast = parse_source_with_spans(source);
+        let function = &ast.root.unwrap().functions[0];
+
+        assert_eq!(
+            &source[function.span.start.offset..function.span.end.offset],
+            "const fn square(x i32) -> i32 {\n    y := x * x\n}"
+        );
+        assert_eq!(function.span.start.line, 3);
+        assert_eq!(function.span.end.line, 5);
+    }
+
+    #[test]
+    fn test_module_span() {
+        let source = "module Main\nmodule Inner {\n    fn f() {\n    }\n}\n";
+        let ast = parse_source_with_spans(source);
+        let module = ast.root.unwrap();
+
+        assert_eq!(module.span.start.offset, 0);
+        assert_eq!(
+            &source[module.modules[0].span.start.offset..module.modules[0].span.end.offset],
+            "module Inner {\n    fn f() {\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn test_decl_keywords() {
+        let source = "module Main\nimport \"std/io\"\nmodule Inner {\n    fn f() {\n    }\n}\nconst fn g() {\n}\n";
+        let ast = parse_source_with_spans(source);
+        let module = ast.root.unwrap();
+        let text = |span: Span| &source[span.start.offset..span.end.offset];
+
+        assert_eq!(module.decl, Token::Keyword(Keyword::Module));
+        assert_eq!(text(module.decl_span), "module");
+        assert_eq!(module.decl_span.start.line, 1);
+
+        let import = &module.imports[0];
+        assert_eq!(import.decl, Token::Keyword(Keyword::Import));
+        assert_eq!(text(import.decl_span), "import");
+        assert_eq!(import.decl_span.start.line, 2);
+
+        let inner = &module.modules[0];
+        assert_eq!(inner.decl, Token::Keyword(Keyword::Module));
+        assert_eq!(text(inner.decl_span), "module");
+        assert_eq!(inner.decl_span.start.line, 3);
+
+        let nested_fn = &inner.functions[0];
+        assert_eq!(nested_fn.decl, Token::Keyword(Keyword::Fn));
+        assert_eq!(text(nested_fn.decl_span), "fn");
+        assert_eq!(nested_fn.decl_span.start.line, 4);
+
+        let const_fn = &module.functions[0];
+        assert_eq!(const_fn.decl, Token::Keyword(Keyword::Fn));
+        assert_eq!(text(const_fn.decl_span), "fn");
+        assert_eq!(const_fn.decl_span.start.column, 7);
+    }
+
+    #[test]
+    fn test_parse_error_import_without_string_path() {
+        let result = parse_source("module Main\nimport 42\n");
+
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::UnexpectedToken {
+                found: Token::Literal(Literal::Number("42".to_string())),
+                expected: vec!["string".to_string(), "identifier".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_dotted_import() {
+        let ast = parse_source("module Main\nimport a.b.c\nimport \"std/io\"\n").unwrap();
+        let module = ast.root.unwrap();
+
+        assert_eq!(
+            module.imports[0].path,
+            ImportPath::Module(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert_eq!(
+            module.imports[1].path,
+            ImportPath::File("std/io".to_string())
+        );
+    }
+
+    fn import_items(source: &str) -> Vec<Vec<String>> {
+        let ast = parse_source(source).unwrap();
+        let imports = ast.root.unwrap().imports;
+        imports.into_iter().map(|import| import.items).collect()
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_multiline_import_items() {
+        let items = import_items(
+            "module Main\nimport \"std/math\" {\n  sqrt,\n  pow,\n  abs,\n}\nfn main() {\n}\n",
+        );
+        assert_eq!(items, vec![names(&["sqrt", "pow", "abs"])]);
+
+        let items = import_items("module Main\nimport std.math {\n  sqrt,\n  pow\n}\n");
+        assert_eq!(items, vec![names(&["sqrt", "pow"])]);
+    }
+
+    #[test]
+    fn test_parse_single_line_import_items() {
+        let items = import_items(
+            "module Main\nimport \"std/math\" { sqrt, pow }\nimport \"std/io\" {}\nimport std.fs\n",
+        );
+        assert_eq!(items, vec![names(&["sqrt", "pow"]), vec![], vec![]]);
+    }
+
+    #[test]
+    fn test_parse_error_import_items() {
+        assert_eq!(
+            without_context(parse_source(
+                "module Main\nimport \"std/math\" { sqrt pow }\n"
+            )),
+            Err(ParserError::expected(
+                Token::Identifier("pow".to_string()),
+                &[Token::CloseBrace]
+            ))
+        );
+        assert_eq!(
+            without_context(parse_source(
+                "module Main\nimport \"std/math\" { sqrt,, pow }\n"
+            )),
+            Err(ParserError::expected(
+                Token::Comma,
+                &[Token::Identifier(String::new())]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_gated_import() {
+        let ast = parse_source(
+            "module Main\n@cfg(os = \"linux\") import \"std/linux\"\nimport \"std/io\"\n",
+        )
+        .unwrap();
+        let imports = ast.root.unwrap().imports;
+
+        assert_eq!(imports[0].path, ImportPath::File("std/linux".to_string()));
+        assert_eq!(
+            imports[0].cfg,
+            Some(Attribute {
+                name: "cfg".to_string(),
+                arguments: vec![AttributeArgument {
+                    key: "os".to_string(),
+                    value: Some(Literal::String("linux".to_string())),
+                }],
+            })
+        );
+        assert_eq!(
+            imports[0].cfg.as_ref().unwrap().to_string(),
+            "@cfg(os = \"linux\")"
+        );
+        assert_eq!(imports[1].path, ImportPath::File("std/io".to_string()));
+        assert_eq!(imports[1].cfg, None);
+    }
+
+    #[test]
+    fn test_parse_error_gated_import() {
+        assert_eq!(
+            without_context(parse_source("module Main\n@inline import \"std/io\"\n")),
+            Err(ParserError::UnexpectedAttribute(attribute("inline")))
+        );
+        assert_eq!(
+            without_context(parse_source(
+                "module Main\n@cfg(test) @cfg(debug) import \"std/io\"\n"
+            )),
+            Err(ParserError::UnexpectedAttribute(Attribute {
+                name: "cfg".to_string(),
+                arguments: vec![AttributeArgument {
+                    key: "debug".to_string(),
+                    value: None,
+                }],
+            }))
+        );
+        assert_eq!(
+            without_context(parse_source("module Main\n@cfg(test)\nfn main() {\n}\n")),
+            Err(ParserError::expected(
+                Token::Newline,
+                &[Token::Keyword(Keyword::Import)]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_error_dotted_import_trailing_dot() {
+        let result = parse_source("module Main\nimport a.\nfn main() {\n}\n");
+
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::UnexpectedToken {
+                found: Token::Keyword(Keyword::Fn),
+                expected: vec!["identifier".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_module_alias() {
+        let ast =
+            parse_source("module Main\nmodule Net = std.net\nmodule Io = io\nfn main() {\n}\n")
+                .unwrap();
+        let module = ast.root.unwrap();
+
+        let aliases: Vec<_> = module
+            .aliases
+            .iter()
+            .map(|alias| (alias.name.as_str(), alias.target.clone()))
+            .collect();
+        assert_eq!(
+            aliases,
+            vec![("Net", names(&["std", "net"])), ("Io", names(&["io"]))]
+        );
+        assert!(module.modules.is_empty());
+        assert_eq!(module.functions[0].name, "main");
+    }
+
+    #[test]
+    fn test_parse_module_alias_beside_module() {
+        let ast = parse_source(
+            "module Main\nmodule Net = std.net\nmodule Util {\n    module Fmt = std.fmt\n    fn f() {\n    }\n}\n",
+        )
+        .unwrap();
+        let module = ast.root.unwrap();
+
+        assert_eq!(module.aliases.len(), 1);
+        assert_eq!(module.modules.len(), 1);
+
+        let inner = &module.modules[0];
+        assert_eq!(inner.name, "Main.Util");
+        assert_eq!(inner.aliases[0].name, "Fmt");
+        assert_eq!(inner.aliases[0].target, names(&["std", "fmt"]));
+        assert_eq!(inner.functions[0].name, "f");
+    }
+
+    #[test]
+    fn test_parse_error_module_alias_without_path() {
+        assert_eq!(
+            without_context(parse_source("module Main\nmodule Net =\nfn main() {\n}\n")),
+            Err(ParserError::UnexpectedToken {
+                found: Token::Keyword(Keyword::Fn),
+                expected: vec!["identifier".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_destructure() {
+        let ast = parse_source("module Main\nfn main() {\n    a, b := (1, 2)\n}\n").unwrap();
+        let function = &ast.root.unwrap().functions[0];
+
+        assert_eq!(
+            function.block.statements[0].kind,
+            StatementKind::Destructure {
+                names: vec!["a".to_string(), "b".to_string()],
+                expression: ExpressionKind::Tuple(vec![number("1"), number("2")]).into(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_discard() {
+        let expr = first_initializer("module Main\nfn main() {\n    _ := sideEffect()\n}\n");
+        assert_eq!(
+            expr.kind,
+            ExpressionKind::Call {
+                callee: Box::new(var("sideEffect")),
+                arguments: vec![],
+            }
+        );
+
+        let repeated = "module Main\nfn main() {\n    _ := 1\n    _ := 2\n    _, b := pair()\n    _, _ := pair()\n}\n";
+        assert!(parse_source(repeated).is_ok());
+    }
+
+    #[test]
+    fn test_parse_discard_cannot_be_reassigned() {
+        let result = parse_source("module Main\nfn main() {\n    _ := 1\n    _ = 2\n}\n");
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::UndeclaredVariable("_".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_single_declaration_is_not_destructure() {
+        let expr = first_initializer("module Main\nfn main() {\n    a := 1\n}\n");
+        assert_eq!(expr, number("1"));
+    }
+
+    #[test]
+    fn test_parse_group_is_not_tuple() {
+        let expr = first_initializer("module Main\nfn main() {\n    a := (1)\n}\n");
+        assert_eq!(
+            expr,
+            ExpressionKind::Group {
+                inner: Box::new(number("1"))
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_error_destructure_without_declaration() {
+        let result = parse_source("module Main\nfn main() {\n    a, b = f()\n}\n");
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::UnexpectedToken {
+                found: Token::Operator(Operator::Reassign),
+                expected: vec![":=".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_blocks() {
+        let ast = parse_source(
+            "module Main\nfn main() {\n    {\n        {\n            x := 1\n        }\n    }\n}\n",
+        )
+        .unwrap();
+        let function = &ast.root.unwrap().functions[0];
+
+        let StatementKind::Block(outer) = &function.block.statements[0].kind else {
+            panic!("Expected outer block");
+        };
+        let StatementKind::Block(inner) = &outer.statements[0].kind else {
+            panic!("Expected inner block");
+        };
+        assert!(matches!(
+            &inner.statements[0].kind,
+            StatementKind::Expression(Expression {
+                kind: ExpressionKind::Variable(Variable { name, .. }),
+                ..
+            }) if name == "x"
+        ));
+    }
+
+    #[test]
+    fn test_block_scope_does_not_leak() {
+        let result =
+            parse_source("module Main\nfn main() {\n    {\n        x := 1\n    }\n    x = 2\n}\n");
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::UndeclaredVariable("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_outer_scope_is_visible_in_block() {
+        let result = parse_source(
+            "module Main\nLIMIT :mut = 1\nfn main(n i32) {\n    x :mut = 1\n    {\n        x = 2\n        n = 3\n        LIMIT = 4\n    }\n}\n",
+        );
+        assert!(result.is_ok(), "{:?}", result);
+    }
+
+    #[test]
+    fn test_parse_method_chain_after_multiline_call() {
+        let expr = first_initializer(
+            "module Main\nfn main() {\n    x := foo(\n        a,\n        b\n    )\n        .bar()\n}\n",
+        );
+
+        assert_eq!(
+            expr,
+            ExpressionKind::Call {
+                callee: Box::new(
+                    ExpressionKind::FieldAccess {
+                        object: Box::new(
+                            ExpressionKind::Call {
+                                callee: Box::new(var("foo")),
+                                arguments: vec![var("a"), var("b")],
+                            }
+                            .into()
+                        ),
+                        field: "bar".to_string(),
+                    }
+                    .into()
+                ),
+                arguments: vec![],
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn test_parse_impl_block() {
+        let ast = parse_source(
+            "module Main\nimpl Point {\n    fn distance() -> f64 {\n        d := 0\n    }\n\n    fn origin() Point {\n    }\n}\n",
+        )
+        .unwrap();
+        let module = ast.root.unwrap();
+
+        assert_eq!(module.impls.len(), 1);
+        assert!(module.functions.is_empty());
+
+        let implementation = &module.impls[0];
+        assert_eq!(implementation.decl, Token::Keyword(Keyword::Impl));
+        assert_eq!(implementation.type_name, "Point");
+        let names: Vec<&str> = implementation
+            .functions
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["distance", "origin"]);
+        assert_eq!(
+            implementation.functions[0].return_type.ty,
+            Some(Type::named("f64"))
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_impl_block() {
+        let ast = parse_source("module Main\nimpl Point {\n}\nfn main() {\n}\n").unwrap();
+        let module = ast.root.unwrap();
+
+        assert_eq!(module.impls.len(), 1);
+        assert_eq!(module.impls[0].type_name, "Point");
+        assert!(module.impls[0].functions.is_empty());
+        assert_eq!(module.functions[0].name, "main");
+    }
+
+    #[test]
+    fn test_parse_error_impl_with_variable() {
+        let result = parse_source("module Main\nimpl Point {\n    x := 1\n}\n");
+        assert_eq!(
+            without_context(result),
+            Err(ParserError::UnexpectedToken {
+                found: Token::Identifier("x".to_string()),
+                expected: vec!["fn".to_string(), "const".to_string(), "}".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_error_missing_paren_expected() {
+        let result = parse_source("module Main\nfn main 42 {\n}\n");
+        let error = without_context(result).unwrap_err();
+        assert_eq!(
+            error,
+            ParserError::UnexpectedToken {
+                found: Token::Literal(Literal::Number("42".to_string())),
+                expected: vec!["(".to_string(), "{".to_string(), "identifier".to_string()],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "expected one of `(`, `{`, `identifier`, found `42`."
+        );
+    }
+
+    #[test]
+    fn test_parse_function_without_parens() {
+        let without = parse_source("module Main\nfn main {\n}\n").unwrap();
+        let with = parse_source("module Main\nfn main() {\n}\n").unwrap();
+        assert_eq!(without, with);
+
+        let function = &without.root.unwrap().functions[0];
+        assert_eq!(function.name, "main");
+        assert!(function.parameters.is_empty());
+        assert_eq!(function.return_type, TypeInfo::void());
+    }
+
+    fn generics(header: &str) -> Vec<GenericParam> {
+        let source = format!("module Main\n{}\n}}\n", header);
+        let ast = parse_source(&source).unwrap();
+        ast.root.unwrap().functions[0].generics.clone()
+    }
+
+    fn generic(name: &str, bound: Option<&str>) -> GenericParam {
+        GenericParam {
+            name: name.to_string(),
+            bound: bound.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_parse_generic_bounds() {
+        assert_eq!(
+            generics("fn sort<T: Ord>(items []T) {"),
+            vec![generic("T", Some("Ord"))]
+        );
+        assert_eq!(generics("fn id<T>(x T) T {"), vec![generic("T", None)]);
+        assert_eq!(
+            generics("fn insert<K: Hash, V>(key K, value V) {"),
+            vec![generic("K", Some("Hash")), generic("V", None)]
+        );
+        assert!(generics("fn main() {").is_empty());
+    }
+
+    #[test]
+    fn test_parse_generic_errors() {
+        assert_eq!(
+            without_context(parse_source("module Main\nfn f<T: >() {\n}\n")),
+            Err(ParserError::expected(
+                Token::Operator(Operator::Greater),
+                &[Token::Identifier(String::new())]
+            ))
+        );
+        assert!(parse_source("module Main\nfn f<T(x T) {\n}\n").is_err());
+    }
+
+    fn parameters(header: &str) -> Vec<Arg> {
+        let source = format!("module Main\n{}\n}}\n", header);
+        let ast = parse_source(&source).unwrap();
+        ast.root.unwrap().functions[0].parameters.clone()
+    }
+
+    fn attribute(name: &str) -> Attribute {
+        Attribute {
+            name: name.to_string(),
+            arguments: vec![],
+        }
+    }
+
+    #[test]
+    fn test_parse_parameter_attributes() {
+        let args = parameters("fn f(@required name string, @deprecated @internal id i32) {");
+
+        assert_eq!(args[0].attributes, vec![attribute("required")]);
+        assert_eq!(args[0].name, Token::Identifier("name".to_string()));
+        assert_eq!(
+            args[1].attributes,
+            vec![attribute("deprecated"), attribute("internal")]
+        );
+        assert_eq!(args[1].name, Token::Identifier("id".to_string()));
+    }
+
+    #[test]
+    fn test_parse_plain_parameters_have_no_attributes() {
+        let args = parameters("fn f(name string, id i32) {");
+
+        assert_eq!(args.len(), 2);
+        assert!(args.iter().all(|arg| arg.attributes.is_empty()));
+    }
+
+    #[test]
+    fn test_parse_parameter_attribute_errors() {
+        assert_eq!(
+            without_context(parse_source("module Main\nfn f(@) {\n}\n")),
+            Err(ParserError::expected(
+                Token::CloseParen,
+                &[Token::Identifier(String::new())]
+            ))
+        );
+        assert_eq!(
+            without_context(parse_source("module Main\nfn f(@fn name string) {\n}\n")),
+            Err(ParserError::ReservedKeyword(Keyword::Fn))
+        );
+    }
+
+    #[test]
+    fn test_parse_void_return_type() {
+        let void_type = |header: &str| {
+            let source = format!("module Main\n{}\n}}\n", header);
+            let ast = parse_source(&source).unwrap();
+            ast.root.unwrap().functions[0].return_type.clone()
+        };
+
+        assert_eq!(void_type("fn f() void {"), TypeInfo::void());
+        assert_eq!(void_type("fn f() -> void {"), TypeInfo::void());
+        assert_eq!(void_type("fn f void {"), TypeInfo::void());
+        assert_eq!(void_type("fn f() {"), TypeInfo::void());
+        assert!(!void_type("fn f() i32 {").is_void());
+
+        // An inferred variable type has no type, unlike void
+        let module = parse_source("module Main\nx := 1\n").unwrap().root.unwrap();
+        let inferred = module.variables[0].type_info.as_ref().unwrap();
+        assert_eq!(inferred.ty, None);
+        assert!(!inferred.is_void());
+
+        // Only `Type::Void` is void, not a type that happens to be named so
+        let named = TypeInfo {
+            ty: Some(Type::named("void")),
+            is_mut: false,
+        };
+        assert!(!named.is_void());
+        assert_ne!(named, TypeInfo::void());
+    }
+
+    #[test]
+    fn test_parse_function_without_parens_with_return_type() {
+        let ast = parse_source("module Main\nfn value i32 {\n}\n").unwrap();
+        let function = &ast.root.unwrap().functions[0];
+
+        assert_eq!(function.name, "value");
+        assert!(function.parameters.is_empty());
+        assert_eq!(function.return_type.ty, Some(Type::named("i32")));
+    }
+
+    #[test]
+    fn test_parse_compound_return_types() {
+        let named = Type::named;
+        let strings = || Type::array(named("string"));
+        let cases = [
+            ("fn names() []string {", strings()),
+            ("fn parse() i32? {", Type::optional(named("i32"))),
+            (
+                "fn pair() (i32, string) {",
+                Type::Tuple(vec![named("i32"), named("string")]),
+            ),
+            (
+                "fn index() -> Map<string, []i32> {",
+                Type::Generic {
+                    name: "Map".to_string(),
+                    arguments: vec![named("string"), Type::array(named("i32"))],
+                },
+            ),
+            ("fn names []string {", strings()),
+            ("fn names() -> []string {", strings()),
+            (
+                "fn grid() [][]i32 {",
+                Type::array(Type::array(named("i32"))),
+            ),
+            (
+                "fn maybe() []i32?? {",
+                Type::array(Type::optional(Type::optional(named("i32")))),
+            ),
+        ];
+        for (header, expected) in cases {
+            let source = format!("module Main\n{}\n}}\n", header);
+            let ast = parse_source(&source).unwrap();
+            let function = &ast.root.unwrap().functions[0];
+            assert_eq!(function.return_type.ty, Some(expected), "{}", header);
+        }
+    }
+
+    #[test]
+    fn test_parse_compound_parameter_types() {
+        let ast = parse_source(
+            "module Main\nfn f(xs []i32, pair (i32, string), y i32?, grid [][]i32) {\n}\n",
+        )
+        .unwrap();
+        let function = &ast.root.unwrap().functions[0];
+        let types: Vec<_> = function
+            .parameters
+            .iter()
+            .map(|arg| arg.type_info.ty.clone().unwrap())
+            .collect();
+
+        let i32 = || Type::named("i32");
+        assert_eq!(
+            types,
+            vec![
+                Type::array(i32()),
+                Type::Tuple(vec![i32(), Type::named("string")]),
+                Type::optional(i32()),
+                Type::array(Type::array(i32())),
+            ]
+        );
+
+        let ast = parse_source("module Main\nfn sum(xs []i32) -> i32 {\n}\n").unwrap();
+        let function = &ast.root.unwrap().functions[0];
+        assert_eq!(
+            function.parameters[0].type_info.ty,
+            Some(Type::array(i32()))
+        );
+        assert_eq!(function.return_type.ty, Some(i32()));
+    }
+
+    #[test]
+    fn test_parse_error_missing_brace_expected() {
+        let result = parse_source("module Main\nfn main() i32 x\n");
+        let error = without_context(result).unwrap_err();
+        assert_eq!(
+            error,
+            ParserError::UnexpectedToken {
+                found: Token::Identifier("x".to_string()),
+                expected: vec!["{".to_string()],
+            }
+        );
+        assert_eq!(error.to_string(), "expected `{`, found `x`.");
+    }
+
+    #[test]
+    fn test_parse_trait_signature() {
+        let ast = parse_source(
+            "module Main
+trait Comparable {
+    fn compare(other Self) -> i32
+}
+",
+        )
+        .unwrap();
+        let module = ast.root.unwrap();
+
+        assert_eq!(module.traits.len(), 1);
+        let trait_def = &module.traits[0];
+        assert_eq!(trait_def.decl, Token::Keyword(Keyword::Trait));
+        assert_eq!(trait_def.name, "Comparable");
+        assert_eq!(trait_def.methods.len(), 1);
+
+        let method = &trait_def.methods[0];
+        assert_eq!(method.signature.name, "compare");
+        assert_eq!(method.signature.parameters.len(), 1);
+        assert_eq!(
+            method.signature.parameters[0].name,
+            Token::Identifier("other".to_string())
+        );
+        assert_eq!(
+            method.signature.parameters[0].type_info.ty,
+            Some(Type::named("Self"))
+        );
+        assert_eq!(method.signature.return_type.ty, Some(Type::named("i32")));
+        assert!(method.default.is_none());
+    }
+
+    #[test]
+    fn test_parse_trait_default_method() {
+        let ast = parse_source(
+            "module Main
+trait Greeter {
+    fn name() str
+
+    fn greet() {
+        greeting := name()
+    }
+}
+",
+        )
+        .unwrap();
+        let trait_def = &ast.root.unwrap().traits[0];
+
+        assert_eq!(trait_def.methods.len(), 2);
+        assert!(trait_def.methods[0].default.is_none());
+        let default = trait_def.methods[1].default.as_ref().unwrap();
+        assert_eq!(default.statements.len(), 1);
+        assert!(trait_def.methods[1].signature.return_type.is_void());
+    }
+
+    #[test]
+    fn test_parse_tuple_struct() {
+        let module = parse_source("module Main\nstruct Pair(i32, []string)\nstruct Unit()\n")
+            .unwrap()
+            .root
+            .unwrap();
+        let type_info = |ty| TypeInfo {
+            ty: Some(ty),
+            is_mut: false,
+        };
+
+        assert_eq!(module.structs.len(), 2);
+        assert_eq!(module.structs[0].decl, Token::Keyword(Keyword::Struct));
+        assert_eq!(module.structs[0].name, "Pair");
+        assert_eq!(
+            module.structs[0].kind,
+            StructKind::Tuple(vec![
+                type_info(Type::named("i32")),
+                type_info(Type::array(Type::named("string")))
+            ])
+        );
+        assert_eq!(module.structs[1].name, "Unit");
+        assert_eq!(module.structs[1].kind, StructKind::Tuple(vec![]));
+    }
+
+    #[test]
+    fn test_parse_named_struct() {
+        let module =
+            parse_source("module Main\nstruct Point {\n    x i32, y i32\n    label string\n}\n")
+                .unwrap()
+                .root
+                .unwrap();
+
+        let StructKind::Named(fields) = &module.structs[0].kind else {
+            panic!("expected named fields, got {:?}", module.structs[0].kind);
+        };
+        let names: Vec<&Token> = fields.iter().map(|field| &field.name).collect();
+        assert_eq!(
+            names,
+            [
+                &Token::Identifier("x".to_string()),
+                &Token::Identifier("y".to_string()),
+                &Token::Identifier("label".to_string()),
+            ]
+        );
+        assert_eq!(fields[2].type_info.ty, Some(Type::named("string")));
+
+        assert_eq!(
+            without_context(parse_source("module Main\nstruct Point { x i32 y i32 }\n")),
+            Err(ParserError::expected(
+                Token::Identifier("y".to_string()),
+                &[Token::Comma, Token::CloseBrace]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_explicit_discriminants() {
+        let module = parse_source("module Main\nenum Status { Ok = 0, Err = 1 }\n")
+            .unwrap()
+            .root
+            .unwrap();
+
+        let enum_def = &module.enums[0];
+        assert_eq!(enum_def.decl, Token::Keyword(Keyword::Enum));
+        assert_eq!(enum_def.name, "Status");
+        let variants: Vec<(&str, Option<&Expression>)> = enum_def
+            .variants
+            .iter()
+            .map(|variant| (variant.name.as_str(), variant.value.as_ref()))
+            .collect();
+        assert_eq!(
+            variants,
+            [("Ok", Some(&number("0"))), ("Err", Some(&number("1")))]
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_implicit_discriminants() {
+        let module =
+            parse_source("module Main\nenum Color {\n    Red\n    Green = 1 + 4\n    Blue,\n}\n")
+                .unwrap()
+                .root
+                .unwrap();
+
+        let variants = &module.enums[0].variants;
+        assert_eq!(variants.len(), 3);
+        assert_eq!(variants[0].name, "Red");
+        assert!(variants[0].value.is_none());
+        assert!(variants[1].value.is_some());
+        assert_eq!(variants[2].name, "Blue");
+        assert!(variants[2].value.is_none());
+        assert!(variants.iter().all(|variant| variant.doc.is_none()));
+
+        assert_eq!(
+            without_context(parse_source("module Main\nenum Color { Red Green }\n")),
+            Err(ParserError::expected(
+                Token::Identifier("Green".to_string()),
+                &[Token::Comma, Token::CloseBrace]
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_enum_variant_doc() {
+        let source = "module Main\nenum Status {\n    Ok = 0 // success\n    Err = 1\n}\n";
+        let reader = StringReader::new(source);
+        let (tokens, spans, comments) =
+            Lexer::lex_with_comments(BufReader::new(reader), LexerConfig::default())
+                .expect("failed to lex");
+        let mut ast = Parser::parse_with_spans(&tokens, &spans).expect("failed to parse");
+        ast.attach_docs(&comments);
+
+        let variants = &ast.root.unwrap().enums[0].variants;
+        assert_eq!(variants[0].doc.as_deref(), Some("success"));
+        assert_eq!(variants[1].doc, None);
+    }
+
+    #[test]
+    fn test_naming_rules_accept_compliant_names() {
+        let source = "module Main\nmodule HttpServer {\n    fn handle_request() {\n    }\n}\nfn parseArgs() {\n}\n";
+        assert!(parse_with_naming_rules(source, true).is_ok());
+        assert!(parse_with_naming_rules(source, false).is_ok());
+    }
+
+    #[test]
+    fn test_naming_rules_reject_module_name() {
+        let source = "module Main\nmodule http_server {\n}\n";
+        assert_eq!(
+            without_context(parse_with_naming_rules(source, true)),
+            Err(ParserError::NamingConvention {
+                name: "http_server".to_string(),
+                convention: "PascalCase",
+            })
+        );
+        assert!(parse_with_naming_rules(source, false).is_ok());
+    }
+
+    #[test]
+    fn test_naming_rules_reject_function_name() {
+        let source = "module Main\nfn Parse_Args() {\n}\n";
+        assert_eq!(
+            without_context(parse_with_naming_rules(source, true)),
+            Err(ParserError::NamingConvention {
+                name: "Parse_Args".to_string(),
+                convention: "snake_case or camelCase",
+            })
+        );
+        assert!(parse_with_naming_rules(source, false).is_ok());
+        assert!(parse_source(source).is_ok());
+    }
+
+    #[test]
+    fn test_parse_files_with_cross_file_import() {
+        let lex = |source: &str| {
+            Lexer::lex(BufReader::new(StringReader::new(source))).expect("failed to lex")
+        };
+        let files = vec![
+            (
+                "main.aeon".to_string(),
+                lex("module Main\nimport Util\nfn main() {\n}\n"),
+            ),
+            (
+                "util.aeon".to_string(),
+                lex("module Util\nfn id(x i32) i32 {\n}\n"),
+            ),
+        ];
+
+        let program = Parser::parse_files(&files).unwrap();
+        assert_eq!(program.files.len(), 2);
+
+        let main = &program.files["main.aeon"];
+        assert_eq!(main.name, "Main");
+        let ImportPath::Module(segments) = &main.imports[0].path else {
+            panic!("Expected module import, got {:?}", main.imports[0].path);
+        };
+        let util = program.find_module(&segments.join(".")).unwrap();
+        assert_eq!(util.name, "Util");
+        assert_eq!(util.functions[0].name, "id");
+        assert_eq!(program.find_module("Missing"), None);
+    }
+
+    #[test]
+    fn test_find_function() {
+        let source = std::fs::read_to_string("../../examples/modules.aeon")
+            .expect("failed to load test source");
+        let ast = parse_source(&source).unwrap();
+
+        let main = ast.find_function("Calculator.main").unwrap();
+        assert_eq!(main.name, "main");
+
+        let square = ast.find_function("Calculator.Utils.square").unwrap();
+        assert_eq!(square.name, "square");
+        assert_eq!(square.parameters.len(), 1);
+
+        assert!(ast.find_function("Calculator.Utils.main").is_none());
+        assert!(ast.find_function("Calculator.Missing.square").is_none());
+        assert!(ast.find_function("main").is_none());
+    }
+
+    #[test]
+    fn test_parse_files_reports_first_error() {
+        let tokens = Lexer::lex(BufReader::new(StringReader::new("fn main() {\n}\n"))).unwrap();
+        let files = vec![("bad.aeon".to_string(), tokens)];
+        let error = Parser::parse_files(&files).unwrap_err();
+        assert_eq!(error.kind(), &ParserError::ModuleNotFound);
+        assert_eq!(
+            error.context(),
+            vec![&ContextFrame::File("bad.aeon".to_string())]
+        );
+        assert_eq!(
+            error.to_string(),
+            "File must begin with a module declaration! (while parsing file `bad.aeon`)"
+        );
+    }
+
+    #[test]
+    fn test_parse_files_rejects_duplicate_names() {
+        let tokens = Lexer::lex(BufReader::new(StringReader::new("module Main\n"))).unwrap();
+        let files = vec![
+            ("main.aeon".to_string(), tokens.clone()),
+            ("main.aeon".to_string(), tokens),
+        ];
+        assert_eq!(
+            Parser::parse_files(&files),
+            Err(ParserError::DuplicateFile("main.aeon".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_files_with_spans() {
+        let lex = |filename: &str, source: &str| {
+            let (tokens, spans) = Lexer::lex_with_spans(
+                BufReader::new(StringReader::new(source)),
+                LexerConfig::default(),
+            )
+            .expect("failed to lex");
+            (filename.to_string(), tokens, spans)
+        };
+        let files = vec![
+            lex("main.aeon", "module Main\nfn main() {\n}\n"),
+            lex("util.aeon", "module Util\n\nfn id(x i32) i32 {\n}\n"),
+        ];
+
+        let program = Parser::parse_files_with_spans(&files).unwrap();
+        assert_eq!(program.files["util.aeon"].functions[0].span.start.line, 3);
+
+        let files = vec![lex("bad.aeon", "module Main\nx := )\n")];
+        let error = Parser::parse_files_with_spans(&files).unwrap_err();
+        assert_eq!(error.line(), Some(2));
+        assert_eq!(
+            error.context(),
+            vec![
+                &ContextFrame::Module("Main".to_string()),
+                &ContextFrame::File("bad.aeon".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_error_context() {
+        let error = parse_source(
+            "module Main\nmodule Net {\n    fn connect() {\n        x := )\n    }\n}\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.context(),
+            vec![
+                &ContextFrame::Function("connect".to_string()),
+                &ContextFrame::Module("Net".to_string()),
+                &ContextFrame::Module("Main".to_string()),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "expected `expression`, found `)`. (while parsing function `connect` in module `Net` in module `Main`)"
+        );
+
+        // Nothing had been entered yet
+        let error = parse_source("fn main() {\n}\n").unwrap_err();
+        assert!(error.context().is_empty());
+        assert_eq!(error, ParserError::ModuleNotFound);
+    }
+
+    #[test]
+    fn test_parse_error_with_source_line() {
+        let source = "module Main\nfn main() {\n    x := )\n}\n";
+        let reader = StringReader::new(source);
+        let (tokens, spans) = Lexer::lex_with_spans(BufReader::new(reader), LexerConfig::default())
+            .expect("failed to lex");
+        let error = Parser::parse_with_spans(&tokens, &spans).unwrap_err();
+
+        assert_eq!(error.line(), Some(3));
+        assert_eq!(error.column(), Some(10));
+        assert_eq!(
+            error.kind(),
+            &ParserError::UnexpectedToken {
+                found: Token::CloseParen,
+                expected: vec!["expression".to_string()],
+            }
+        );
+
+        let error = error.with_source(source);
+        assert_eq!(error.line_text, Some("    x := )".to_string()));
+        assert_eq!(
+            error.to_string(),
+            "error at line 3: expected `expression`, found `)`. (while parsing function `main` in module `Main`)\n        x := )"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_without_spans_has_no_line() {
+        let error = parse_source("module Main\nfn main() {\n    x := )\n}\n").unwrap_err();
+        assert_eq!(error.line(), None);
+        assert!(error.with_source("").to_string().starts_with("error: "));
+    }
+
+    fn nested_parens(depth: usize) -> String {
+        format!(
+            "module Main\nx := {}1{}\n",
+            "(".repeat(depth),
+            ")".repeat(depth)
+        )
+    }
+
+    #[test]
+    fn test_parse_deep_nesting_is_an_error() {
+        assert_eq!(
+            without_context(parse_source(&nested_parens(10_000))),
+            Err(ParserError::RecursionLimitExceeded {
+                limit: crate::DEFAULT_RECURSION_LIMIT
+            })
+        );
+
+        let unary = format!("module Main\nx := {}1\n", "-".repeat(10_000));
+        assert!(matches!(
+            without_context(parse_source(&unary)),
+            Err(ParserError::RecursionLimitExceeded { .. })
+        ));
+
+        let blocks = format!(
+            "module Main\nfn main() {{\n{}{}}}\n",
+            "{\n".repeat(10_000),
+            "}\n".repeat(10_000)
+        );
+        assert!(matches!(
+            without_context(parse_source(&blocks)),
+            Err(ParserError::RecursionLimitExceeded { .. })
+        ));
+
+        let modules = format!(
+            "module Main\n{}{}",
+            "module Inner {\n".repeat(10_000),
+            "}\n".repeat(10_000)
+        );
+        assert!(matches!(
+            without_context(parse_source(&modules)),
+            Err(ParserError::RecursionLimitExceeded { .. })
+        ));
+
+        for (open, close) in [("[]", ""), ("(", ")"), ("Map<", ">")] {
+            let types = format!(
+                "module Main\nfn f(x {}i32{}) {{\n}}\n",
+                open.repeat(100_000),
+                close.repeat(100_000)
+            );
+            assert!(matches!(
+                without_context(parse_source(&types)),
+                Err(ParserError::RecursionLimitExceeded { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_parse_nesting_within_limit() {
+        // One level is taken by the declaration itself
+        let depth = crate::DEFAULT_RECURSION_LIMIT - 1;
+        assert!(parse_source(&nested_parens(depth)).is_ok());
+
+        // An `if` is one level, with its condition and body one deeper
+        let ifs = |depth: usize| {
+            format!(
+                "module Main\nfn main() {{\n{}{}}}\n",
+                "if x {\n".repeat(depth),
+                "}\n".repeat(depth)
+            )
+        };
+        assert!(parse_source(&ifs(depth)).is_ok());
+        assert!(matches!(
+            without_context(parse_source(&ifs(depth + 1))),
+            Err(ParserError::RecursionLimitExceeded { .. })
+        ));
+
+        let types = format!(
+            "module Main\nfn f(x {}i32) {{\n}}\n",
+            "[]".repeat(crate::DEFAULT_RECURSION_LIMIT - 1)
+        );
+        assert!(parse_source(&types).is_ok());
+    }
+
+    /// Parses `print expr` as a call to `print`
+    #[derive(Debug)]
+    struct PrintStatement;
+
+    impl StatementExtension for PrintStatement {
+        fn try_parse(
+            &self,
+            ctx: &mut ParserContext,
+            stream: &mut TokenStream,
+        ) -> Option<Result<Statement, ParserError>> {
+            let start = stream.position();
+            stream.try_consume(Token::Identifier("print".to_string()))?;
+            let callee = Expression::with_id(
+                ctx.next_node_id(),
+                var("print").kind,
+                stream.span_from(start),
+            );
+            Some(ExpressionParser::parse(ctx, stream).map(|argument| {
+                let call = Expression::with_id(
+                    ctx.next_node_id(),
+                    ExpressionKind::Call {
+                        callee: Box::new(callee),
+                        arguments: vec![argument],
+                    },
+                    stream.span_from(start),
+                );
+                Statement::with_id(
+                    ctx.next_node_id(),
+                    StatementKind::Expression(call),
+                    stream.span_from(start),
+                )
+            }))
+        }
+    }
+
+    #[test]
+    fn test_parse_statement_extension() {
+        let source = "module Main\nfn main() {\n    x := 1\n    print x + 1\n    print(x)\n}\n";
+        let tokens = Lexer::lex(BufReader::new(StringReader::new(source))).expect("failed to lex");
+        assert!(Parser::parse(&tokens).is_err());
+
+        let ast = Parser::default()
+            .statement_extension(PrintStatement)
+            .parse_tokens(&tokens, &[])
+            .expect("failed to parse");
+        let statements = &ast.root.unwrap().functions[0].block.statements;
+        let print = |argument| {
+            StatementKind::Expression(
+                ExpressionKind::Call {
+                    callee: Box::new(var("print")),
+                    arguments: vec![argument],
+                }
+                .into(),
+            )
+        };
+        assert_eq!(
+            statements[1].kind,
+            print(
+                ExpressionKind::Binary {
+                    left: Box::new(var("x")),
+                    operator: Operator::Plus,
+                    right: Box::new(number("1")),
+                }
+                .into()
+            )
+        );
+        // Claimed by the extension too, with `(x)` as the argument
+        assert_eq!(
+            statements[2].kind,
+            print(
+                ExpressionKind::Group {
+                    inner: Box::new(var("x"))
+                }
+                .into()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_custom_recursion_limit() {
+        let reader = StringReader::new("module Main\nx := ((1))\n");
+        let tokens = Lexer::lex(BufReader::new(reader)).expect("failed to lex");
+
+        let parser = Parser::default().recursion_limit(2);
+        assert_eq!(
+            without_context(parser.parse_tokens(&tokens, &[])),
+            Err(ParserError::RecursionLimitExceeded { limit: 2 })
+        );
+        let parser = Parser::default().recursion_limit(3);
+        assert!(parser.parse_tokens(&tokens, &[]).is_ok());
+    }
+
+    fn parse_recovering(source: &str) -> (Ast, Vec<ParserError>) {
+        let reader = StringReader::new(source);
+        let (tokens, spans) = Lexer::lex_with_spans(BufReader::new(reader), LexerConfig::default())
+            .expect("failed to lex");
+        Parser::default().parse_recovering(&tokens, &spans)
+    }
+
+    #[test]
+    fn test_parse_recovering_collects_statement_errors() {
+        let source = "module Main\nfn main() {\n    x := )\n    y := 1\n    {\n        z = 2\n    }\n    w := (1 + \n}\n";
+        let (ast, errors) = parse_recovering(source);
+
+        let lines: Vec<_> = errors.iter().map(ParserError::line).collect();
+        assert_eq!(lines, vec![Some(3), Some(6), Some(9)]);
+        assert_eq!(
+            errors[1].kind(),
+            &ParserError::UndeclaredVariable("z".to_string())
+        );
+
+        // The statements that did parse are kept
+        let function = &ast.root.unwrap().functions[0];
+        assert_eq!(function.block.statements.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_recovering_closes_scopes_of_failed_statements() {
+        // The `if` opens a scope for its binding before its condition fails
+        let source = "module Main\nfn main() {\n    {\n        y := 1\n        if a b {\n        }\n    }\n    y = 2\n}\n";
+        let (_, errors) = parse_recovering(source);
+
+        let lines: Vec<_> = errors.iter().map(ParserError::line).collect();
+        assert_eq!(lines, vec![Some(5), Some(8)]);
+        assert_eq!(
+            errors[1].kind(),
+            &ParserError::UndeclaredVariable("y".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_recovering_outside_block_ends_parse() {
+        let (ast, errors) = parse_recovering("module Main\nfn (x) {\n}\n");
+
+        assert_eq!(ast.root, None);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line(), Some(2));
+    }
+
+    #[test]
+    fn test_parse_recovering_valid_source() {
+        let source = "module Main\nfn main() {\n    x := 1\n}\n";
+        let (ast, errors) = parse_recovering(source);
+
+        assert!(errors.is_empty());
+        assert_eq!(ast, parse_source_with_spans(source));
     }
 }
@@ -0,0 +1,96 @@
+use crate::{DISCARD, ParserError, token_stream::TokenStream};
+use lex::token::{Literal, Operator, Token};
+use std::fmt;
+
+/// What a `match` arm compares the scrutinee against.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// `1`, `"a"` or `true`
+    Literal(Literal),
+    /// `1..10`, matching from `start` up to but not including `end`
+    Range { start: Literal, end: Literal },
+    /// `1 | 2`, matching any of the alternatives
+    Or(Vec<Pattern>),
+    /// `_`, matching anything
+    Wildcard,
+    /// `n`, matching anything and binding it to `n` in the arm
+    Binding(String),
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Literal(literal) => write!(f, "{}", literal),
+            Pattern::Range { start, end } => write!(f, "{}..{}", start, end),
+            Pattern::Or(alternatives) => {
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " | ")?;
+                    }
+                    write!(f, "{}", alternative)?;
+                }
+                Ok(())
+            }
+            Pattern::Wildcard => write!(f, "{}", DISCARD),
+            Pattern::Binding(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+pub struct PatternParser;
+
+impl PatternParser {
+    /// Parses a pattern, stopping before the `=>` that ends it.
+    pub fn parse(stream: &mut TokenStream) -> Result<Pattern, ParserError> {
+        let first = Self::alternative(stream)?;
+        if stream.peek() != Some(&Token::Operator(Operator::Pipe)) {
+            return Ok(first);
+        }
+
+        let mut alternatives = vec![first];
+        while stream
+            .try_consume(Token::Operator(Operator::Pipe))
+            .is_some()
+        {
+            alternatives.push(Self::alternative(stream)?);
+        }
+        Ok(Pattern::Or(alternatives))
+    }
+
+    /// A pattern other than an or-pattern.
+    fn alternative(stream: &mut TokenStream) -> Result<Pattern, ParserError> {
+        match stream.current()?.clone() {
+            Token::Literal(start) => {
+                stream.advance(1)?;
+                if stream
+                    .try_consume(Token::Operator(Operator::Range))
+                    .is_none()
+                {
+                    return Ok(Pattern::Literal(start));
+                }
+                match stream.current()?.clone() {
+                    Token::Literal(end) => {
+                        stream.advance(1)?;
+                        Ok(Pattern::Range { start, end })
+                    }
+                    token => Err(ParserError::UnexpectedToken {
+                        found: token,
+                        expected: vec!["literal".to_string()],
+                    }),
+                }
+            }
+            Token::Identifier(name) => {
+                stream.advance(1)?;
+                if name == DISCARD {
+                    Ok(Pattern::Wildcard)
+                } else {
+                    Ok(Pattern::Binding(name))
+                }
+            }
+            token => Err(ParserError::UnexpectedToken {
+                found: token,
+                expected: vec!["pattern".to_string()],
+            }),
+        }
+    }
+}
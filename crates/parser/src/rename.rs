@@ -0,0 +1,212 @@
+//! Scope-aware renaming, for editor refactorings.
+
+use lex::token::Token;
+
+use crate::{
+    ast::{Ast, Expression, Statement, Variable},
+    block::Block,
+    functions::Function,
+    modules::Module,
+    node::NodeId,
+    scope::{ScopedVisitorMut, walk_scoped_expression_mut, walk_scoped_statement_mut},
+    visitor::{
+        VisitorMut, walk_ast_mut, walk_block_mut, walk_function_mut, walk_module_mut,
+        walk_variable_mut,
+    },
+};
+
+/// Renames the variable or function `from` to `to` within the statement or
+/// expression `scope`, or within the whole AST for `NodeId::default()`.
+///
+/// The symbol renamed is the binding of `from` visible where `scope` starts,
+/// or if there is none, the first one declared inside it. Its declaration
+/// and uses are renamed; a binding that shadows it in an inner scope, and
+/// the uses of that binding, are not. A use of `from` that nothing declares,
+/// such as a call to a function from another module, counts as a use of the
+/// renamed symbol.
+pub fn rename_symbol(ast: &mut Ast, from: &str, to: &str, scope: NodeId) {
+    let mut rename = Rename {
+        from,
+        to,
+        scope,
+        active: scope == NodeId::default(),
+        scopes: Vec::new(),
+    };
+    walk_ast_mut(&mut rename, ast);
+}
+
+struct Rename<'a> {
+    from: &'a str,
+    to: &'a str,
+    scope: NodeId,
+    /// Whether the walk is inside `scope`
+    active: bool,
+    /// For each enclosing scope, innermost last: `None` if it doesn't
+    /// declare `from`, otherwise whether its declaration is the renamed one
+    scopes: Vec<Option<bool>>,
+}
+
+impl Rename<'_> {
+    /// Runs `visit` on the node `id`, renaming within it if it is `scope`.
+    fn node(&mut self, id: NodeId, visit: impl FnOnce(&mut Self)) {
+        let entering = !self.active && id == self.scope;
+        if entering {
+            self.active = true;
+        }
+        visit(self);
+        if entering {
+            self.active = false;
+        }
+    }
+
+    fn scoped(&mut self, visit: impl FnOnce(&mut Self)) {
+        self.enter_scope();
+        visit(self);
+        self.exit_scope();
+    }
+
+    /// Records a declaration of `from` in the current scope, returning
+    /// whether it is the renamed one. Outside `scope` every declaration is,
+    /// so that the binding visible at the start of `scope` gets renamed.
+    fn declare(&mut self) -> bool {
+        let outer = self.scopes.len().saturating_sub(1);
+        let is_target = match self.scopes.last().copied().flatten() {
+            // Declaring the name again in the same scope, as in `x := x + 1`,
+            // keeps referring to the same symbol
+            Some(is_target) => is_target,
+            None => !self.active || self.scopes[..outer].iter().all(Option::is_none),
+        };
+        if let Some(current) = self.scopes.last_mut() {
+            *current = Some(is_target);
+        }
+        is_target
+    }
+
+    /// Whether a use of `from` here refers to the renamed symbol
+    fn is_target(&self) -> bool {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| *scope)
+            .unwrap_or(true)
+    }
+
+    /// Renames the declaration `name` if it declares `from`
+    fn rename_declaration(&mut self, name: &mut String) {
+        if name == self.from && self.declare() && self.active {
+            *name = self.to.to_string();
+        }
+    }
+}
+
+impl ScopedVisitorMut for Rename<'_> {
+    fn enter_scope(&mut self) {
+        self.scopes.push(None);
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn declare(&mut self, name: &mut String) {
+        self.rename_declaration(name);
+    }
+}
+
+impl VisitorMut for Rename<'_> {
+    fn visit_module(&mut self, module: &mut Module) {
+        self.scoped(|this| {
+            // Functions are in scope throughout their module, even before
+            // they are declared
+            for function in &mut module.functions {
+                this.rename_declaration(&mut function.name);
+            }
+            walk_module_mut(this, module);
+        });
+    }
+
+    fn visit_function(&mut self, function: &mut Function) {
+        self.scoped(|this| {
+            for parameter in &mut function.parameters {
+                if let Token::Identifier(name) = &mut parameter.name {
+                    this.rename_declaration(name);
+                }
+            }
+            walk_function_mut(this, function);
+        });
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        self.scoped(|this| walk_block_mut(this, block));
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        self.node(statement.id, |this| {
+            walk_scoped_statement_mut(this, statement)
+        });
+    }
+
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        self.node(expression.id, |this| {
+            walk_scoped_expression_mut(this, expression)
+        });
+    }
+
+    fn visit_variable(&mut self, variable: &mut Variable) {
+        // The initializer can't see the variable it declares
+        walk_variable_mut(self, variable);
+        if variable.is_decl {
+            self.rename_declaration(&mut variable.name);
+        } else if variable.name == self.from && self.active && self.is_target() {
+            variable.name = self.to.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::PrettyPrinter, test_util::parse};
+
+    const SHADOWED: &str = "module Main\n\nfn main() {\n    x := 1\n    {\n        x := 2\n        y := x\n    }\n    z := x\n}\n";
+
+    #[test]
+    fn test_rename_shadowed_local() {
+        let mut ast = parse(SHADOWED);
+        rename_symbol(&mut ast, "x", "count", NodeId::default());
+
+        assert_eq!(
+            PrettyPrinter::new().print(&ast),
+            "module Main\n\nfn main() {\n    count := 1\n    {\n        x := 2\n        y := x\n    }\n    z := count\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_within_statement() {
+        let mut ast = parse(SHADOWED);
+        let statements = &ast.root.as_ref().unwrap().functions[0].block.statements;
+        let (block, last) = (statements[1].id, statements[2].id);
+
+        // The inner `x` shadows the one visible at the start of the block
+        rename_symbol(&mut ast, "x", "count", block);
+        assert_eq!(PrettyPrinter::new().print(&ast), SHADOWED);
+
+        rename_symbol(&mut ast, "x", "count", last);
+        assert!(PrettyPrinter::new().print(&ast).contains(
+            "    x := 1\n    {\n        x := 2\n        y := x\n    }\n    z := count\n"
+        ));
+    }
+
+    #[test]
+    fn test_rename_function_and_call_sites() {
+        let mut ast = parse(
+            "module Main\n\nfn main() {\n    a := helper(1)\n    b := helper(helper(a))\n}\n\nfn helper(n i32) i32 {\n    n\n}\n",
+        );
+        rename_symbol(&mut ast, "helper", "double", NodeId::default());
+
+        assert_eq!(
+            PrettyPrinter::new().print(&ast),
+            "module Main\n\nfn main() {\n    a := double(1)\n    b := double(double(a))\n}\n\nfn double(n i32) i32 {\n    n\n}\n"
+        );
+    }
+}
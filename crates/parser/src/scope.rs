@@ -0,0 +1,137 @@
+//! Traversal that follows lexical scopes.
+//!
+//! Implement `ScopedVisitor` (or `ScopedVisitorMut`) alongside `Visitor`,
+//! and call `walk_scoped_statement` and `walk_scoped_expression` from
+//! `visit_statement` and `visit_expression`. They walk the node like the
+//! plain `walk_*` functions, but open a scope for the binding of an `if` and
+//! for each `match` arm, and declare the names they bind. Blocks, functions
+//! and variable declarations are left to the visitor.
+
+use crate::{
+    ast::{Expression, ExpressionKind, IfCondition, Statement, StatementKind},
+    pattern::Pattern,
+    visitor::{
+        Visitor, VisitorMut, walk_expression, walk_expression_mut, walk_statement,
+        walk_statement_mut, walk_variable, walk_variable_mut,
+    },
+};
+
+pub trait ScopedVisitor<'a>: Visitor<'a> {
+    fn enter_scope(&mut self);
+
+    fn exit_scope(&mut self);
+
+    /// `name` is bound in the innermost scope.
+    fn declare(&mut self, name: &'a str);
+}
+
+/// Like `ScopedVisitor`, with mutable access to the names declared.
+pub trait ScopedVisitorMut: VisitorMut {
+    fn enter_scope(&mut self);
+
+    fn exit_scope(&mut self);
+
+    fn declare(&mut self, name: &mut String);
+}
+
+/// Walks `statement`, declaring the names of `a, b := expression` after
+/// its expression.
+pub fn walk_scoped_statement<'a, V: ScopedVisitor<'a> + ?Sized>(
+    visitor: &mut V,
+    statement: &'a Statement,
+) {
+    match &statement.kind {
+        StatementKind::Destructure { names, expression } => {
+            visitor.visit_expression(expression);
+            for name in names {
+                visitor.declare(name);
+            }
+        }
+        _ => walk_statement(visitor, statement),
+    }
+}
+
+/// Walks `expression`, scoping the binding of an `if` to its `then` branch
+/// and a binding pattern to its own `match` arm.
+pub fn walk_scoped_expression<'a, V: ScopedVisitor<'a> + ?Sized>(
+    visitor: &mut V,
+    expression: &'a Expression,
+) {
+    match &expression.kind {
+        ExpressionKind::If {
+            condition: IfCondition::Binding(binding),
+            then_branch,
+            else_branch,
+        } => {
+            walk_variable(visitor, binding);
+            visitor.enter_scope();
+            visitor.declare(&binding.name);
+            visitor.visit_block(then_branch);
+            visitor.exit_scope();
+            if let Some(else_branch) = else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+            for arm in arms {
+                visitor.enter_scope();
+                if let Pattern::Binding(name) = &arm.pattern {
+                    visitor.declare(name);
+                }
+                visitor.visit_expression(&arm.body);
+                visitor.exit_scope();
+            }
+        }
+        _ => walk_expression(visitor, expression),
+    }
+}
+
+pub fn walk_scoped_statement_mut<V: ScopedVisitorMut + ?Sized>(
+    visitor: &mut V,
+    statement: &mut Statement,
+) {
+    match &mut statement.kind {
+        StatementKind::Destructure { names, expression } => {
+            visitor.visit_expression(expression);
+            for name in names {
+                visitor.declare(name);
+            }
+        }
+        _ => walk_statement_mut(visitor, statement),
+    }
+}
+
+pub fn walk_scoped_expression_mut<V: ScopedVisitorMut + ?Sized>(
+    visitor: &mut V,
+    expression: &mut Expression,
+) {
+    match &mut expression.kind {
+        ExpressionKind::If {
+            condition: IfCondition::Binding(binding),
+            then_branch,
+            else_branch,
+        } => {
+            walk_variable_mut(visitor, binding);
+            visitor.enter_scope();
+            visitor.declare(&mut binding.name);
+            visitor.visit_block(then_branch);
+            visitor.exit_scope();
+            if let Some(else_branch) = else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+            for arm in arms {
+                visitor.enter_scope();
+                if let Pattern::Binding(name) = &mut arm.pattern {
+                    visitor.declare(name);
+                }
+                visitor.visit_expression(&mut arm.body);
+                visitor.exit_scope();
+            }
+        }
+        _ => walk_expression_mut(visitor, expression),
+    }
+}
@@ -1,13 +1,30 @@
 use crate::{
-    ParserContext,
-    ast::{Expression, Statement, Variable},
+    ParserContext, Terminator,
+    ast::{Expression, ExpressionKind, Statement, StatementKind, Variable},
     block::BlockParser,
     expression::ExpressionParser,
-    functions::TypeInfo,
+    functions::{TypeInfo, parse_type},
     parser_error::ParserError,
     token_stream::TokenStream,
 };
 use lex::token::{Keyword, Operator, Token};
+use std::fmt;
+
+/// Parses syntax the parser doesn't know, such as a `print x` statement,
+/// for prototyping it without changing the parser. Register one with
+/// `Parser::statement_extension`.
+pub trait StatementExtension: fmt::Debug + Send + Sync {
+    /// Parses the statement at the start of `stream`, or returns `None`
+    /// without consuming anything if it isn't one this extension handles.
+    /// Called only where no built-in statement form matches, so a statement
+    /// that is just an expression is offered here before being parsed as
+    /// one. The terminator after the statement is left to the parser.
+    fn try_parse(
+        &self,
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Option<Result<Statement, ParserError>>;
+}
 
 pub struct StatementParser;
 
@@ -16,69 +33,294 @@ impl StatementParser {
         ctx: &mut ParserContext,
         stream: &mut TokenStream,
     ) -> Result<Statement, ParserError> {
+        let start = stream.position();
+        // Blocks and expressions, through which statements nest, are parsed
+        // here rather than in the much larger frame of the other forms
+        if stream.peek() == Some(&Token::OpenBrace) {
+            return Self::block(ctx, stream, start);
+        }
+        if let Some(statement) = Self::parse_declaration_or_keyword(ctx, stream, start)? {
+            return Ok(statement);
+        }
+        if let Some(statement) = Self::parse_extension(ctx, stream) {
+            return statement;
+        }
+
+        // A bare expression, such as the value at the end of an `if` branch
+        let expression = ExpressionParser::parse(ctx, stream)?;
+        Ok(Statement::with_id(
+            ctx.next_node_id(),
+            StatementKind::Expression(expression),
+            stream.span_from(start),
+        ))
+    }
+
+    /// Every statement but a block or an expression, or `None` without
+    /// consuming anything for those
+    fn parse_declaration_or_keyword(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+    ) -> Result<Option<Statement>, ParserError> {
+        let keywords = [Keyword::Return, Keyword::Yield, Keyword::Assert].map(Token::Keyword);
+        if let Some(Token::Keyword(keyword)) = stream.try_consume_one_of(&keywords) {
+            return Self::keyword_statement(ctx, stream, start, keyword).map(Some);
+        }
+
         let mut window: [Option<Token>; 5] = [const { None }; 5];
         (0..5).for_each(|i| {
             window[i] = stream.peek_ahead(i).cloned();
         });
 
-        match window {
-            [Some(Token::OpenBrace), ..] => {
-                let block = BlockParser::parse(ctx, stream)?;
-                Ok(Statement::Block(block))
+        let variable = match window {
+            [Some(Token::Keyword(Keyword::Const)), ..] => {
+                // Only declarations can be const, `const x = 1` is an error
+                // rather than a reassignment
+                if !matches!(
+                    window[2],
+                    Some(Token::Operator(Operator::Walrus | Operator::Colon))
+                ) {
+                    return Err(ParserError::unexpected(Token::Keyword(Keyword::Const)));
+                }
+                stream.advance(1)?;
+                let statement = Self::parse(ctx, stream)?;
+                return match statement.kind {
+                    StatementKind::Expression(Expression {
+                        kind: ExpressionKind::Variable(mut variable),
+                        ..
+                    }) if variable.is_decl
+                        && !variable.type_info.as_ref().is_some_and(|t| t.is_mut) =>
+                    {
+                        variable.is_const = true;
+                        Ok(Some(Self::variable_statement(ctx, stream, start, variable)))
+                    }
+                    _ => Err(ParserError::unexpected(Token::Keyword(Keyword::Const))),
+                };
+            }
+            [Some(Token::Keyword(Keyword::Else)), ..] => return Err(ParserError::DanglingElse),
+            [Some(Token::Identifier(_)), Some(Token::Comma), ..] => {
+                return Self::destructure(ctx, stream, start).map(Some);
             }
             [
                 Some(Token::Identifier(name)),
                 Some(Token::Operator(Operator::Reassign)),
                 ..,
             ] => {
+                if !ctx.is_declared(&name) {
+                    return Err(ParserError::UndeclaredVariable(name.clone()));
+                }
                 stream.advance(2)?;
-                Ok(Statement::Expression(Expression::Variable(Variable {
+                Variable {
                     name: name.clone(),
                     is_decl: false,
+                    is_const: false,
                     expression: Some(Box::new(ExpressionParser::parse(ctx, stream)?)),
                     type_info: None,
-                })))
+                }
             }
             [
                 Some(Token::Identifier(name)),
-                Some(Token::Operator(Operator::Assign)),
-                Some(Token::Operator(Operator::Reassign)),
+                Some(Token::Operator(Operator::Walrus)),
                 ..,
             ] => {
-                stream.advance(3)?;
-                Ok(Statement::Expression(Expression::Variable(Variable {
+                stream.advance(2)?;
+                Variable {
                     name: name.clone(),
                     is_decl: true,
+                    is_const: false,
                     expression: Some(Box::new(ExpressionParser::parse(ctx, stream)?)),
                     type_info: Some(TypeInfo {
-                        name: None,
+                        ty: None,
                         is_mut: false,
                     }),
-                })))
+                }
             }
+            // `x :mut = 5`, `x :i32 = 5` or `x :mut i32 = 5`
             [
                 Some(Token::Identifier(name)),
-                Some(Token::Operator(Operator::Assign)),
-                Some(Token::Keyword(Keyword::Mut)),
-                Some(Token::Operator(Operator::Reassign)),
+                Some(Token::Operator(Operator::Colon)),
                 ..,
             ] => {
-                stream.advance(4)?;
-                Ok(Statement::Expression(Expression::Variable(Variable {
+                stream.advance(2)?;
+                let is_mut = stream.try_consume(Token::Keyword(Keyword::Mut)).is_some();
+                let ty = match stream.peek() {
+                    Some(Token::Operator(Operator::Reassign)) if is_mut => None,
+                    _ => Some(parse_type(ctx, stream)?),
+                };
+                stream.consume(Token::Operator(Operator::Reassign))?;
+                Variable {
                     name: name.clone(),
                     is_decl: true,
+                    is_const: false,
                     expression: Some(Box::new(ExpressionParser::parse(ctx, stream)?)),
-                    type_info: Some(TypeInfo {
-                        name: None,
-                        is_mut: true,
-                    }),
-                })))
+                    type_info: Some(TypeInfo { ty, is_mut }),
+                }
             }
-            // TODO: Support specifiying the type, either like:
-            // foo :u32 = 10
-            // foo :mut u32 = 11
-            [Some(token), ..] => Err(ParserError::UnexpectedToken(token)),
-            [None, ..] => Err(ParserError::UnexpectedEndOfInput),
+            [Some(_), ..] => return Ok(None),
+            [None, ..] => return Err(ParserError::UnexpectedEndOfInput),
+        };
+
+        if variable.is_decl {
+            ctx.declare(&variable.name);
         }
+        Ok(Some(Self::variable_statement(ctx, stream, start, variable)))
+    }
+
+    /// `return`, `yield` or `assert`, after `keyword`
+    fn keyword_statement(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+        keyword: Keyword,
+    ) -> Result<Statement, ParserError> {
+        let kind = match keyword {
+            Keyword::Return => {
+                let value = match stream.peek() {
+                    None | Some(Token::Newline | Token::Semicolon | Token::CloseBrace) => None,
+                    Some(_) => Some(ExpressionParser::parse(ctx, stream)?),
+                };
+                StatementKind::Return(value)
+            }
+            Keyword::Yield => StatementKind::Yield(ExpressionParser::parse(ctx, stream)?),
+            Keyword::Assert => {
+                let condition = ExpressionParser::parse(ctx, stream)?;
+                let message = match stream.try_consume(Token::Comma) {
+                    Some(_) => Some(ExpressionParser::parse(ctx, stream)?),
+                    None => None,
+                };
+                StatementKind::Assert { condition, message }
+            }
+            keyword => return Err(ParserError::unexpected(Token::Keyword(keyword))),
+        };
+        Ok(Statement::with_id(
+            ctx.next_node_id(),
+            kind,
+            stream.span_from(start),
+        ))
+    }
+
+    /// A bare `{ }` block. The block of an `if` or a function already counts
+    /// as the expression or function around it; a bare one nests itself.
+    fn block(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+    ) -> Result<Statement, ParserError> {
+        let block = ctx.nested(|ctx| BlockParser::parse(ctx, stream))?;
+        Ok(Statement::with_id(
+            ctx.next_node_id(),
+            StatementKind::Block(block),
+            stream.span_from(start),
+        ))
+    }
+
+    /// The statement of the first extension that parses one
+    fn parse_extension(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Option<Result<Statement, ParserError>> {
+        // Cloned so extensions can borrow `ctx` mutably
+        let extensions = ctx.statement_extensions().to_vec();
+        extensions
+            .iter()
+            .find_map(|extension| extension.try_parse(ctx, stream))
+    }
+
+    /// Consumes what ends `statement` under the context's `Terminator`.
+    /// A line break has already been consumed along with the statement.
+    pub fn parse_terminator(
+        ctx: &ParserContext,
+        stream: &mut TokenStream,
+        statement: &Statement,
+    ) -> Result<(), ParserError> {
+        match ctx.terminator() {
+            Terminator::Newline => match stream.peek() {
+                Some(Token::Semicolon) => Err(ParserError::unexpected(Token::Semicolon)),
+                _ if Self::ended_line(stream) => Ok(()),
+                _ => stream.expect_newline_or_end(),
+            },
+            Terminator::Either => {
+                if stream.try_consume(Token::Semicolon).is_some() || Self::ended_line(stream) {
+                    Ok(())
+                } else {
+                    stream.expect_newline_or_end()
+                }
+            }
+            Terminator::Semicolon => {
+                if stream.try_consume(Token::Semicolon).is_some()
+                    || Self::ends_in_block(statement)
+                    || matches!(stream.peek(), Some(Token::CloseBrace) | None)
+                {
+                    Ok(())
+                } else {
+                    Err(ParserError::expected(
+                        stream.current()?.clone(),
+                        &[Token::Semicolon],
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Whether the line break after the statement was already consumed
+    fn ended_line(stream: &TokenStream) -> bool {
+        stream.previous() == Ok(&Token::Newline)
+    }
+
+    /// `{ }`, `if c { } else { }` or `match x { }` on its own
+    fn ends_in_block(statement: &Statement) -> bool {
+        match &statement.kind {
+            StatementKind::Block(_) => true,
+            StatementKind::Expression(expression) => matches!(
+                expression.kind,
+                ExpressionKind::If { .. } | ExpressionKind::Match { .. }
+            ),
+            StatementKind::Destructure { .. }
+            | StatementKind::Return(_)
+            | StatementKind::Yield(_)
+            | StatementKind::Assert { .. } => false,
+        }
+    }
+
+    /// `a, b := expression`
+    fn destructure(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+        start: usize,
+    ) -> Result<Statement, ParserError> {
+        let mut names = Vec::new();
+        loop {
+            names.push(stream.consume_identifier()?);
+            if stream.try_consume(Token::Comma).is_none() {
+                break;
+            }
+        }
+
+        stream.consume(Token::Operator(Operator::Walrus))?;
+        let expression = ExpressionParser::parse(ctx, stream)?;
+        for name in &names {
+            ctx.declare(name);
+        }
+        Ok(Statement::with_id(
+            ctx.next_node_id(),
+            StatementKind::Destructure { names, expression },
+            stream.span_from(start),
+        ))
+    }
+
+    fn variable_statement(
+        ctx: &mut ParserContext,
+        stream: &TokenStream,
+        start: usize,
+        variable: Variable,
+    ) -> Statement {
+        let span = stream.span_from(start);
+        let expression =
+            Expression::with_id(ctx.next_node_id(), ExpressionKind::Variable(variable), span);
+        Statement::with_id(
+            ctx.next_node_id(),
+            StatementKind::Expression(expression),
+            span,
+        )
     }
 }
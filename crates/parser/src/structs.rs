@@ -0,0 +1,131 @@
+use crate::{
+    ParserContext,
+    attributes::AttributeParser,
+    functions::{Arg, TypeInfo, parse_type, reject_keyword},
+    parser_error::ParserError,
+    token_stream::TokenStream,
+};
+use lex::{
+    span::Span,
+    token::{Keyword, Token},
+};
+
+/// `struct Point { x i32, y i32 }` or `struct Pair(i32, i32)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Struct {
+    pub decl: Token,
+    pub decl_span: Span,
+    pub name: String,
+    pub kind: StructKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StructKind {
+    /// Fields with names, separated by commas or newlines
+    Named(Vec<Arg>),
+    /// Positional fields, as in `struct Pair(i32, i32)`
+    Tuple(Vec<TypeInfo>),
+}
+
+pub struct StructParser;
+
+impl StructParser {
+    pub fn parse(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Struct, ParserError> {
+        let start = stream.position();
+        let decl = stream.consume_exact(Token::Keyword(Keyword::Struct))?;
+        let decl_span = stream.span_at(start);
+
+        reject_keyword(stream)?;
+        let name = stream.consume_identifier()?;
+
+        let kind = match stream.peek() {
+            Some(Token::OpenParen) => StructKind::Tuple(Self::parse_tuple_fields(ctx, stream)?),
+            Some(Token::OpenBrace) => StructKind::Named(Self::parse_named_fields(ctx, stream)?),
+            Some(token) => {
+                return Err(ParserError::expected(
+                    token.clone(),
+                    &[Token::OpenBrace, Token::OpenParen],
+                ));
+            }
+            None => return Err(ParserError::UnexpectedEndOfInput),
+        };
+
+        Ok(Struct {
+            decl,
+            decl_span,
+            name,
+            kind,
+            span: stream.span_from(start),
+        })
+    }
+
+    /// `(i32, string)`, from the `(` up to and including the `)`
+    fn parse_tuple_fields(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Vec<TypeInfo>, ParserError> {
+        stream.consume(Token::OpenParen)?;
+        let mut fields = Vec::new();
+        if stream.try_consume(Token::CloseParen).is_some() {
+            return Ok(fields);
+        }
+
+        loop {
+            fields.push(TypeInfo {
+                ty: Some(parse_type(ctx, stream)?),
+                is_mut: false,
+            });
+            if stream.try_consume(Token::Comma).is_none() {
+                stream.consume(Token::CloseParen)?;
+                break;
+            }
+        }
+        Ok(fields)
+    }
+
+    /// `{ x i32, y i32 }`, from the `{` up to and including the `}`
+    fn parse_named_fields(
+        ctx: &mut ParserContext,
+        stream: &mut TokenStream,
+    ) -> Result<Vec<Arg>, ParserError> {
+        stream.consume(Token::OpenBrace)?;
+        let mut fields = Vec::new();
+        loop {
+            match stream.peek() {
+                Some(Token::Newline | Token::Comma) => stream.advance(1)?,
+                Some(Token::CloseBrace) => {
+                    stream.advance(1)?;
+                    break;
+                }
+                Some(_) => {
+                    let attributes = AttributeParser::parse(stream)?;
+                    reject_keyword(stream)?;
+                    let name = stream.consume(Token::Identifier(String::new()))?;
+                    let type_info = TypeInfo {
+                        ty: Some(parse_type(ctx, stream)?),
+                        is_mut: false,
+                    };
+                    fields.push(Arg {
+                        attributes,
+                        name,
+                        type_info,
+                    });
+
+                    match stream.peek() {
+                        Some(Token::Newline | Token::Comma | Token::CloseBrace) => {}
+                        Some(token) => {
+                            return Err(ParserError::expected(
+                                token.clone(),
+                                &[Token::Comma, Token::CloseBrace],
+                            ));
+                        }
+                        None => return Err(ParserError::UnexpectedEndOfInput),
+                    }
+                }
+                None => return Err(ParserError::UnexpectedEndOfInput),
+            }
+        }
+        Ok(fields)
+    }
+}
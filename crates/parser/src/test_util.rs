@@ -0,0 +1,39 @@
+//! Parsing helpers shared by the tests of the other modules.
+
+use std::io::BufReader;
+
+use lex::{
+    lexer::{Lexer, LexerConfig},
+    span::Span,
+    token::Token,
+};
+use stringreader::StringReader;
+
+use crate::{Parser, ast::Ast};
+
+/// Parses `source` without spans, panicking if it doesn't lex or parse.
+pub fn parse(source: &str) -> Ast {
+    let tokens = Lexer::lex(BufReader::new(StringReader::new(source))).expect("failed to lex");
+    Parser::parse(&tokens).expect("failed to parse")
+}
+
+/// Parses `source` with spans, panicking if it doesn't lex or parse.
+pub fn parse_with_spans(source: &str) -> Ast {
+    let (tokens, spans) = lex_with_spans(source);
+    Parser::parse_with_spans(&tokens, &spans).expect("failed to parse")
+}
+
+/// The tokens of `source` and their spans, panicking if it doesn't lex.
+pub fn lex_with_spans(source: &str) -> (Vec<Token>, Vec<Span>) {
+    Lexer::lex_with_spans(
+        BufReader::new(StringReader::new(source)),
+        LexerConfig::default(),
+    )
+    .expect("failed to lex")
+}
+
+/// The source of a `Main` module whose `main(n i32)` has `body`, which
+/// should end with a newline.
+pub fn main_with_body(body: &str) -> String {
+    format!("module Main\nfn main(n i32) {{\n{}}}\n", body)
+}
@@ -1,21 +1,55 @@
 use crate::parser_error::ParserError;
-use lex::token::Token;
+use lex::{
+    span::Span,
+    token::{Keyword, Literal, Token},
+};
 
 #[derive(Debug, Clone)]
 pub struct TokenStream<'a> {
     tokens: &'a [Token],
+    spans: &'a [Span],
     position: usize,
 }
 
 impl<'a> TokenStream<'a> {
     /// Create a new TokenStream from a slice of tokens
     pub fn new(tokens: &'a [Token]) -> Self {
+        Self::with_spans(tokens, &[])
+    }
+
+    /// Create a new TokenStream whose tokens carry source spans.
+    /// `spans[i]` is the span of `tokens[i]`.
+    pub fn with_spans(tokens: &'a [Token], spans: &'a [Span]) -> Self {
         TokenStream {
             tokens,
+            spans,
             position: 0,
         }
     }
 
+    /// Get the span of the token at `index`, or the default span if the
+    /// stream was created without spans
+    pub fn span_at(&self, index: usize) -> Span {
+        self.spans.get(index).copied().unwrap_or_default()
+    }
+
+    /// Get the span of the current token, or of the last token once the
+    /// stream is exhausted
+    pub fn current_span(&self) -> Span {
+        let last = self.tokens.len().saturating_sub(1);
+        self.span_at(self.position.min(last))
+    }
+
+    /// Get the span covering the tokens from `start` up to the last consumed
+    /// token, ignoring trailing newlines
+    pub fn span_from(&self, start: usize) -> Span {
+        let mut end = self.position.saturating_sub(1);
+        while end > start && self.tokens.get(end) == Some(&Token::Newline) {
+            end -= 1;
+        }
+        self.span_at(start).to(&self.span_at(end.max(start)))
+    }
+
     /// Consume a token if it matches the expected token type
     /// Returns the consumed token on success
     pub fn consume(&mut self, expected: Token) -> Result<Token, ParserError> {
@@ -35,20 +69,14 @@ impl<'a> TokenStream<'a> {
 
         let current = self.current()?;
 
-        let matches = match (&expected, current) {
-            (Token::Identifier(_), Token::Identifier(_)) => true,
-            (Token::Literal(_), Token::Literal(_)) => true,
-            _ => current == &expected,
-        };
-
-        if matches {
+        if current.matches(&expected) {
             let token = current.clone();
             self.position += 1;
             Ok(token)
         } else {
             match custom_message {
-                Some(_msg) => Err(ParserError::UnexpectedToken(current.clone())),
-                None => Err(ParserError::UnexpectedToken(current.clone())),
+                Some(_msg) => Err(ParserError::expected(current.clone(), &[expected])),
+                None => Err(ParserError::expected(current.clone(), &[expected])),
             }
         }
     }
@@ -66,7 +94,23 @@ impl<'a> TokenStream<'a> {
             self.position += 1;
             Ok(token)
         } else {
-            Err(ParserError::UnexpectedToken(current.clone()))
+            Err(ParserError::expected(current.clone(), &[expected]))
+        }
+    }
+
+    /// Consume an identifier and return its name
+    pub fn consume_identifier(&mut self) -> Result<String, ParserError> {
+        match self.consume(Token::Identifier(String::new()))? {
+            Token::Identifier(name) => Ok(name),
+            token => Err(ParserError::unexpected(token)),
+        }
+    }
+
+    /// Consume a string literal and return its contents
+    pub fn consume_string_literal(&mut self) -> Result<String, ParserError> {
+        match self.consume(Token::Literal(Literal::String(String::new())))? {
+            Token::Literal(Literal::String(value)) => Ok(value),
+            token => Err(ParserError::unexpected(token)),
         }
     }
 
@@ -81,10 +125,14 @@ impl<'a> TokenStream<'a> {
         }
     }
 
+    /// Get the token at `index`, or None past the end or on a `Token::Eof`
+    fn token_at(&self, index: usize) -> Option<&Token> {
+        self.tokens.get(index).filter(|token| **token != Token::Eof)
+    }
+
     /// Get the current token without consuming it
     pub fn current(&self) -> Result<&Token, ParserError> {
-        self.tokens
-            .get(self.position)
+        self.token_at(self.position)
             .ok_or(ParserError::UnexpectedEndOfInput)
     }
 
@@ -100,28 +148,28 @@ impl<'a> TokenStream<'a> {
 
     pub fn window(&self, n: usize) -> Vec<Option<&Token>> {
         (self.position..self.position + n)
-            .map(|range| self.tokens.get(range))
+            .map(|index| self.token_at(index))
             .collect()
     }
 
     /// Peek at the current token without consuming it (returns None if at end)
     pub fn peek(&self) -> Option<&Token> {
-        self.tokens.get(self.position)
+        self.token_at(self.position)
     }
 
     /// Peek at the next token without consuming it (returns None if at end)
     pub fn peek_next(&self) -> Option<&Token> {
-        self.tokens.get(self.position + 1)
+        self.token_at(self.position + 1)
     }
 
     /// Peek ahead n tokens without consuming (returns None if would go past end)
     pub fn peek_ahead(&self, n: usize) -> Option<&Token> {
-        self.tokens.get(self.position + n)
+        self.token_at(self.position + n)
     }
 
-    /// Check if we're at the end of the token stream
+    /// Check if we're at the end of the token stream, or on a `Token::Eof`
     pub fn is_at_end(&self) -> bool {
-        self.position >= self.tokens.len()
+        self.token_at(self.position).is_none()
     }
 
     /// Get the remaining tokens as a slice
@@ -152,16 +200,26 @@ impl<'a> TokenStream<'a> {
 
         let current = self.peek()?;
 
-        // Check if tokens match based on their type
-        let matches = match (&expected, current) {
-            // For identifiers and literals, any value matches (structural matching)
-            (Token::Identifier(_), Token::Identifier(_)) => true,
-            (Token::Literal(_), Token::Literal(_)) => true,
-            // For keywords, operators, and other tokens, exact match required
-            _ => current == &expected,
-        };
+        if current.matches(&expected) {
+            let token = current.clone();
+            self.position += 1;
+            Some(token)
+        } else {
+            None
+        }
+    }
+
+    /// Consume the current token if it exactly equals one of `expected`
+    /// (including its value), returning None and leaving the stream
+    /// untouched otherwise. Useful for dispatching on keywords.
+    pub fn try_consume_one_of(&mut self, expected: &[Token]) -> Option<Token> {
+        if self.is_at_end() {
+            return None;
+        }
+
+        let current = self.peek()?;
 
-        if matches {
+        if expected.contains(current) {
             let token = current.clone();
             self.position += 1;
             Some(token)
@@ -170,6 +228,39 @@ impl<'a> TokenStream<'a> {
         }
     }
 
+    /// Consume the `Newline` ending a statement. The end of input or a `}`
+    /// closing the enclosing block also ends it; the `}` is not consumed.
+    pub fn expect_newline_or_end(&mut self) -> Result<(), ParserError> {
+        match self.peek() {
+            Some(Token::Newline) => {
+                self.position += 1;
+                Ok(())
+            }
+            None | Some(Token::CloseBrace) => Ok(()),
+            Some(token) => Err(ParserError::MissingTerminator {
+                found: token.clone(),
+            }),
+        }
+    }
+
+    /// Advance until the current token satisfies `sync`, without consuming
+    /// it, and return that token. Returns None at the end of the stream.
+    /// Used to resynchronize after a parse error, for instance with
+    /// `is_sync_token`.
+    pub fn skip_to<F>(&mut self, mut sync: F) -> Option<&Token>
+    where
+        F: FnMut(&Token) -> bool,
+    {
+        while !self.is_at_end() {
+            let token = &self.tokens[self.position];
+            if sync(token) {
+                return Some(token);
+            }
+            self.position += 1;
+        }
+        None
+    }
+
     /// Consume tokens while a predicate is true
     pub fn consume_while<F>(&mut self, mut predicate: F) -> Vec<Token>
     where
@@ -190,10 +281,23 @@ impl<'a> TokenStream<'a> {
     }
 }
 
+/// Where parsing can resume after an error: the end of a line, the end of a
+/// block, or a keyword that starts a top-level item.
+pub fn is_sync_token(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Newline
+            | Token::CloseBrace
+            | Token::Keyword(
+                Keyword::Module | Keyword::Import | Keyword::Fn | Keyword::Impl | Keyword::Trait
+            )
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use lex::token::{Keyword, Token};
+    use lex::token::{Keyword, Literal, Token};
 
     #[test]
     fn test_new_stream() {
@@ -223,10 +327,62 @@ mod tests {
         let mut stream = TokenStream::new(&tokens);
 
         let result = stream.consume(Token::Keyword(Keyword::Fn));
-        assert!(matches!(result, Err(ParserError::UnexpectedToken(_))));
+        assert!(matches!(result, Err(ParserError::UnexpectedToken { .. })));
         assert_eq!(stream.position(), 0); // Position unchanged on error
     }
 
+    #[test]
+    fn test_consume_string_pattern_rejects_number() {
+        let tokens = vec![Token::Literal(Literal::Number("42".to_string()))];
+        let mut stream = TokenStream::new(&tokens);
+
+        let result = stream.consume(Token::Literal(Literal::String(String::new())));
+        assert_eq!(
+            result,
+            Err(ParserError::UnexpectedToken {
+                found: Token::Literal(Literal::Number("42".to_string())),
+                expected: vec!["string".to_string()],
+            })
+        );
+        assert_eq!(stream.position(), 0);
+    }
+
+    #[test]
+    fn test_consume_identifier() {
+        let tokens = vec![Token::Identifier("main".to_string()), Token::OpenParen];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.consume_identifier(), Ok("main".to_string()));
+        assert_eq!(stream.position(), 1);
+        assert_eq!(
+            stream.consume_identifier(),
+            Err(ParserError::UnexpectedToken {
+                found: Token::OpenParen,
+                expected: vec!["identifier".to_string()],
+            })
+        );
+        assert_eq!(stream.position(), 1);
+    }
+
+    #[test]
+    fn test_consume_string_literal() {
+        let tokens = vec![
+            Token::Literal(Literal::String("std/io".to_string())),
+            Token::Identifier("io".to_string()),
+        ];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.consume_string_literal(), Ok("std/io".to_string()));
+        assert_eq!(
+            stream.consume_string_literal(),
+            Err(ParserError::UnexpectedToken {
+                found: Token::Identifier("io".to_string()),
+                expected: vec!["string".to_string()],
+            })
+        );
+        assert_eq!(stream.position(), 1);
+    }
+
     #[test]
     fn test_consume_at_end() {
         let tokens = vec![];
@@ -1,4 +1,7 @@
-use crate::{parser_error::ParserError, token_stream::TokenStream};
+use crate::{
+    parser_error::ParserError,
+    token_stream::{TokenStream, is_sync_token},
+};
 use lex::token::{Keyword, Literal, Operator, Token};
 
 #[cfg(test)]
@@ -59,7 +62,7 @@ mod tests {
         let mut stream = TokenStream::new(&tokens);
 
         let result = stream.consume(Token::Keyword(Keyword::Fn));
-        assert!(matches!(result, Err(ParserError::UnexpectedToken(_))));
+        assert!(matches!(result, Err(ParserError::UnexpectedToken { .. })));
         assert_eq!(stream.position(), 0); // Position unchanged on error
     }
 
@@ -72,22 +75,76 @@ mod tests {
         assert!(matches!(result, Err(ParserError::UnexpectedEndOfInput)));
     }
 
+    #[test]
+    fn test_consume_at_eof() {
+        let tokens = vec![Token::Identifier("x".to_string()), Token::Eof];
+        let mut stream = TokenStream::new(&tokens);
+        stream.advance(1).unwrap();
+
+        assert!(stream.is_at_end());
+        assert_eq!(
+            stream.consume(Token::Keyword(Keyword::Module)),
+            Err(ParserError::UnexpectedEndOfInput)
+        );
+        assert_eq!(
+            stream.consume(Token::Eof),
+            Err(ParserError::UnexpectedEndOfInput)
+        );
+        assert_eq!(
+            stream.consume_exact(Token::CloseBrace),
+            Err(ParserError::UnexpectedEndOfInput)
+        );
+        assert_eq!(
+            stream.consume_identifier(),
+            Err(ParserError::UnexpectedEndOfInput)
+        );
+        assert_eq!(stream.current(), Err(ParserError::UnexpectedEndOfInput));
+        assert_eq!(stream.try_consume(Token::Eof), None);
+        assert_eq!(stream.position(), 1);
+    }
+
+    #[test]
+    fn test_peek_at_eof() {
+        let tokens = vec![Token::Identifier("x".to_string()), Token::Eof];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.peek_next(), None);
+        assert_eq!(stream.peek_ahead(1), None);
+        assert_eq!(stream.window(2), vec![Some(&tokens[0]), None]);
+
+        stream.advance(1).unwrap();
+        assert_eq!(stream.peek(), None);
+    }
+
+    #[test]
+    fn test_skip_to_stops_at_eof() {
+        let tokens = vec![
+            Token::Identifier("x".to_string()),
+            Token::Eof,
+            Token::CloseBrace,
+        ];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.skip_to(|token| *token == Token::CloseBrace), None);
+        assert_eq!(stream.position(), 1);
+    }
+
     #[test]
     fn test_consume_exact() {
         let tokens = vec![
-            Token::Operator(Operator::Assign),
+            Token::Operator(Operator::Walrus),
             Token::Operator(Operator::Plus),
         ];
         let mut stream = TokenStream::new(&tokens);
 
         // This should succeed - exact match
-        let result = stream.consume_exact(Token::Operator(Operator::Assign));
+        let result = stream.consume_exact(Token::Operator(Operator::Walrus));
         assert!(result.is_ok());
         assert_eq!(stream.position(), 1);
 
         // This should fail - wrong operator
         let result = stream.consume_exact(Token::Operator(Operator::Minus));
-        assert!(matches!(result, Err(ParserError::UnexpectedToken(_))));
+        assert!(matches!(result, Err(ParserError::UnexpectedToken { .. })));
         assert_eq!(stream.position(), 1);
     }
 
@@ -209,6 +266,133 @@ mod tests {
         assert!(result.is_none());
     }
 
+    #[test]
+    fn test_try_consume_one_of() {
+        let tokens = vec![
+            Token::Keyword(Keyword::Impl),
+            Token::Identifier("Point".to_string()),
+        ];
+        let mut stream = TokenStream::new(&tokens);
+        let items = [
+            Token::Keyword(Keyword::Fn),
+            Token::Keyword(Keyword::Impl),
+            Token::Keyword(Keyword::Trait),
+        ];
+
+        assert_eq!(
+            stream.try_consume_one_of(&items),
+            Some(Token::Keyword(Keyword::Impl))
+        );
+        assert_eq!(stream.position(), 1);
+    }
+
+    #[test]
+    fn test_try_consume_one_of_no_match() {
+        let tokens = vec![Token::Identifier("point".to_string())];
+        let mut stream = TokenStream::new(&tokens);
+
+        // Matching is exact, so an identifier with another name is no match
+        let items = [
+            Token::Keyword(Keyword::Fn),
+            Token::Identifier("other".to_string()),
+        ];
+        assert_eq!(stream.try_consume_one_of(&items), None);
+        assert_eq!(stream.position(), 0);
+    }
+
+    #[test]
+    fn test_try_consume_one_of_at_end() {
+        let tokens = vec![Token::Eof];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.try_consume_one_of(&[Token::Eof]), None);
+        assert_eq!(stream.position(), 0);
+
+        let mut empty = TokenStream::new(&[]);
+        assert_eq!(empty.try_consume_one_of(&[Token::Newline]), None);
+    }
+
+    #[test]
+    fn test_expect_newline_or_end() {
+        let tokens = vec![Token::Newline, Token::CloseBrace];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.expect_newline_or_end(), Ok(()));
+        assert_eq!(stream.position(), 1);
+
+        // The brace belongs to the enclosing block, so it is left in place
+        assert_eq!(stream.expect_newline_or_end(), Ok(()));
+        assert_eq!(stream.position(), 1);
+    }
+
+    #[test]
+    fn test_expect_newline_or_end_at_end() {
+        let tokens = vec![Token::Eof];
+        let mut stream = TokenStream::new(&tokens);
+        assert_eq!(stream.expect_newline_or_end(), Ok(()));
+
+        let mut empty = TokenStream::new(&[]);
+        assert_eq!(empty.expect_newline_or_end(), Ok(()));
+    }
+
+    #[test]
+    fn test_expect_newline_or_end_trailing_token() {
+        let tokens = vec![Token::Identifier("y".to_string())];
+        let mut stream = TokenStream::new(&tokens);
+
+        let error = stream.expect_newline_or_end().unwrap_err();
+        assert_eq!(
+            error,
+            ParserError::MissingTerminator {
+                found: Token::Identifier("y".to_string()),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Missing statement terminator before `y`!"
+        );
+        assert_eq!(stream.position(), 0);
+    }
+
+    #[test]
+    fn test_skip_to_stops_at_first_match() {
+        let tokens = vec![
+            Token::Identifier("x".to_string()),
+            Token::Operator(Operator::Reassign),
+            Token::CloseParen,
+            Token::Newline,
+            Token::Keyword(Keyword::Fn),
+        ];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.skip_to(is_sync_token), Some(&Token::Newline));
+        assert_eq!(stream.position(), 3);
+
+        // Already on a match, so nothing is skipped
+        assert_eq!(stream.skip_to(is_sync_token), Some(&Token::Newline));
+        assert_eq!(stream.position(), 3);
+
+        stream.advance(1).unwrap();
+        assert_eq!(
+            stream.skip_to(is_sync_token),
+            Some(&Token::Keyword(Keyword::Fn))
+        );
+        assert_eq!(stream.position(), 4);
+    }
+
+    #[test]
+    fn test_skip_to_end_of_input() {
+        let tokens = vec![
+            Token::Identifier("x".to_string()),
+            Token::Operator(Operator::Plus),
+        ];
+        let mut stream = TokenStream::new(&tokens);
+
+        assert_eq!(stream.skip_to(|token| token == &Token::CloseBrace), None);
+        assert!(stream.is_at_end());
+        assert_eq!(stream.skip_to(is_sync_token), None);
+    }
+
     #[test]
     fn test_consume_while() {
         let tokens = vec![
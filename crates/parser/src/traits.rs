@@ -0,0 +1,87 @@
+use crate::{
+    ParserContext,
+    block::{Block, BlockParser},
+    functions::{FunctionParser, FunctionSignature, reject_keyword},
+    parser_error::ParserError,
+    token_stream::TokenStream,
+};
+use lex::{
+    span::Span,
+    token::{Keyword, Token},
+};
+
+/// `trait Comparable { fn compare(other Self) -> i32 }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trait {
+    pub decl: Token,
+    pub decl_span: Span,
+    pub name: String,
+    pub methods: Vec<TraitMethod>,
+    pub span: Span,
+}
+
+/// A method required by a trait, optionally with a default body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitMethod {
+    pub signature: FunctionSignature,
+    pub default: Option<Block>,
+    pub span: Span,
+}
+
+pub struct TraitParser;
+
+impl TraitParser {
+    pub fn parse(ctx: &mut ParserContext, stream: &mut TokenStream) -> Result<Trait, ParserError> {
+        let start = stream.position();
+        let decl = stream.consume_exact(Token::Keyword(Keyword::Trait))?;
+        let decl_span = stream.span_at(start);
+
+        reject_keyword(stream)?;
+        let name = stream.consume_identifier()?;
+
+        stream.consume(Token::OpenBrace)?;
+        let mut methods = Vec::new();
+        loop {
+            match stream.peek() {
+                Some(Token::Newline) => stream.advance(1)?,
+                Some(Token::CloseBrace) => {
+                    stream.advance(1)?;
+                    break;
+                }
+                Some(Token::Keyword(Keyword::Fn | Keyword::Const)) => {
+                    let method_start = stream.position();
+                    let signature = FunctionParser::parse_signature(ctx, stream)?;
+                    let default = match stream.peek() {
+                        Some(Token::OpenBrace) => Some(BlockParser::parse(ctx, stream)?),
+                        _ => None,
+                    };
+                    ctx.exit_function();
+                    methods.push(TraitMethod {
+                        signature,
+                        default,
+                        span: stream.span_from(method_start),
+                    });
+                }
+                Some(token) => {
+                    return Err(ParserError::expected(
+                        token.clone(),
+                        &[
+                            Token::Keyword(Keyword::Fn),
+                            Token::Keyword(Keyword::Const),
+                            Token::CloseBrace,
+                        ],
+                    ));
+                }
+                None => return Err(ParserError::UnexpectedEndOfInput),
+            }
+        }
+
+        Ok(Trait {
+            decl,
+            decl_span,
+            name,
+            methods,
+            span: stream.span_from(start),
+        })
+    }
+}
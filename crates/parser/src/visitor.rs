@@ -0,0 +1,590 @@
+//! Traversal of the AST.
+//!
+//! Implement `Visitor` and override the `visit_*` methods for the nodes of
+//! interest. Each default implementation calls the matching `walk_*`
+//! function, which visits the node's children; call it from an override to
+//! keep descending. `VisitorMut` and the `walk_*_mut` functions do the same
+//! with mutable access, for transforms.
+
+use crate::{
+    ast::{Ast, Expression, ExpressionKind, IfCondition, Statement, StatementKind, Variable},
+    block::Block,
+    functions::Function,
+    impls::Impl,
+    modules::Module,
+    traits::{Trait, TraitMethod},
+};
+use lex::span::Position;
+
+pub trait Visitor<'a> {
+    fn visit_module(&mut self, module: &'a Module) {
+        walk_module(self, module);
+    }
+
+    fn visit_impl(&mut self, implementation: &'a Impl) {
+        walk_impl(self, implementation);
+    }
+
+    fn visit_trait(&mut self, trait_def: &'a Trait) {
+        walk_trait(self, trait_def);
+    }
+
+    fn visit_trait_method(&mut self, method: &'a TraitMethod) {
+        walk_trait_method(self, method);
+    }
+
+    fn visit_function(&mut self, function: &'a Function) {
+        walk_function(self, function);
+    }
+
+    fn visit_block(&mut self, block: &'a Block) {
+        walk_block(self, block);
+    }
+
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        walk_expression(self, expression);
+    }
+
+    /// A declaration, assignment or reference to a variable, including a
+    /// module variable and the binding of an `if`.
+    fn visit_variable(&mut self, variable: &'a Variable) {
+        walk_variable(self, variable);
+    }
+}
+
+pub fn walk_ast<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, ast: &'a Ast) {
+    if let Some(module) = &ast.root {
+        visitor.visit_module(module);
+    }
+}
+
+pub fn walk_module<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, module: &'a Module) {
+    for variable in &module.variables {
+        visitor.visit_variable(variable);
+    }
+    for statement in &module.statements {
+        visitor.visit_statement(statement);
+    }
+    for value in module
+        .enums
+        .iter()
+        .flat_map(|e| &e.variants)
+        .filter_map(|v| v.value.as_ref())
+    {
+        visitor.visit_expression(value);
+    }
+    for inner in &module.modules {
+        visitor.visit_module(inner);
+    }
+    for implementation in &module.impls {
+        visitor.visit_impl(implementation);
+    }
+    for trait_def in &module.traits {
+        visitor.visit_trait(trait_def);
+    }
+    for function in &module.functions {
+        visitor.visit_function(function);
+    }
+}
+
+pub fn walk_impl<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, implementation: &'a Impl) {
+    for function in &implementation.functions {
+        visitor.visit_function(function);
+    }
+}
+
+pub fn walk_trait<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, trait_def: &'a Trait) {
+    for method in &trait_def.methods {
+        visitor.visit_trait_method(method);
+    }
+}
+
+pub fn walk_trait_method<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, method: &'a TraitMethod) {
+    if let Some(block) = &method.default {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_function<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, function: &'a Function) {
+    visitor.visit_block(&function.block);
+}
+
+pub fn walk_block<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, block: &'a Block) {
+    for statement in &block.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, statement: &'a Statement) {
+    match &statement.kind {
+        StatementKind::Expression(expression) | StatementKind::Destructure { expression, .. } => {
+            visitor.visit_expression(expression)
+        }
+        StatementKind::Block(block) => visitor.visit_block(block),
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression(value);
+            }
+        }
+        StatementKind::Yield(value) => visitor.visit_expression(value),
+        StatementKind::Assert { condition, message } => {
+            visitor.visit_expression(condition);
+            if let Some(message) = message {
+                visitor.visit_expression(message);
+            }
+        }
+    }
+}
+
+pub fn walk_variable<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, variable: &'a Variable) {
+    if let Some(initializer) = &variable.expression {
+        visitor.visit_expression(initializer);
+    }
+}
+
+pub fn walk_expression<'a, V: Visitor<'a> + ?Sized>(visitor: &mut V, expression: &'a Expression) {
+    match &expression.kind {
+        ExpressionKind::Literal(_)
+        | ExpressionKind::LiteralNull
+        | ExpressionKind::Path(_)
+        | ExpressionKind::SelfRef => {}
+        ExpressionKind::Group { inner: operand }
+        | ExpressionKind::Unary { operand, .. }
+        | ExpressionKind::FieldAccess {
+            object: operand, ..
+        }
+        | ExpressionKind::OptionalAccess {
+            object: operand, ..
+        }
+        | ExpressionKind::Try(operand) => visitor.visit_expression(operand),
+        ExpressionKind::Tuple(elements) => {
+            for element in elements {
+                visitor.visit_expression(element);
+            }
+        }
+        ExpressionKind::Binary { left, right, .. }
+        | ExpressionKind::And { left, right }
+        | ExpressionKind::Or { left, right }
+        | ExpressionKind::Index {
+            object: left,
+            index: right,
+        } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        ExpressionKind::Variable(variable) => visitor.visit_variable(variable),
+        ExpressionKind::Call { callee, arguments } => {
+            visitor.visit_expression(callee);
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        ExpressionKind::Slice { object, range } => {
+            visitor.visit_expression(object);
+            for bound in [&range.start, &range.end].into_iter().flatten() {
+                visitor.visit_expression(bound);
+            }
+        }
+        ExpressionKind::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(then_branch);
+            visitor.visit_expression(else_branch);
+        }
+        ExpressionKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            match condition {
+                IfCondition::Expr(condition) => visitor.visit_expression(condition),
+                IfCondition::Binding(variable) => visitor.visit_variable(variable),
+            }
+            visitor.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+            for arm in arms {
+                visitor.visit_expression(&arm.body);
+            }
+        }
+        ExpressionKind::StructLiteral { base, fields, .. } => {
+            if let Some(base) = base {
+                visitor.visit_expression(base);
+            }
+            for field in fields {
+                visitor.visit_expression(&field.value);
+            }
+        }
+        ExpressionKind::Record(fields) => {
+            for field in fields {
+                visitor.visit_expression(&field.value);
+            }
+        }
+    }
+}
+
+/// A borrowed node of any kind, as yielded by `Ast::walk_preorder`.
+#[derive(Debug, Clone, Copy)]
+pub enum AstNodeRef<'a> {
+    Module(&'a Module),
+    Impl(&'a Impl),
+    Trait(&'a Trait),
+    TraitMethod(&'a TraitMethod),
+    Function(&'a Function),
+    Block(&'a Block),
+    Statement(&'a Statement),
+    Expression(&'a Expression),
+    Variable(&'a Variable),
+}
+
+impl Ast {
+    /// Every node, each one before its children, with the children of a
+    /// module in source order. A variable expression is followed by its
+    /// `Variable`. Source order comes from spans, so without them a
+    /// module's children are grouped by kind as in `walk_module`.
+    pub fn walk_preorder(&self) -> impl Iterator<Item = AstNodeRef<'_>> {
+        let mut preorder = Preorder(Vec::new());
+        walk_ast(&mut preorder, self);
+        preorder.0.into_iter()
+    }
+}
+
+struct Preorder<'a>(Vec<AstNodeRef<'a>>);
+
+impl<'a> Visitor<'a> for Preorder<'a> {
+    fn visit_module(&mut self, module: &'a Module) {
+        self.0.push(AstNodeRef::Module(module));
+
+        let values = module
+            .enums
+            .iter()
+            .flat_map(|e| &e.variants)
+            .filter_map(|v| v.value.as_ref());
+        // A module variable has no span of its own, so it is placed by its
+        // initializer
+        let mut items: Vec<(Position, AstNodeRef<'a>)> = module
+            .variables
+            .iter()
+            .map(|v| {
+                let start = v.expression.as_ref().map(|e| e.span.start);
+                (start.unwrap_or_default(), AstNodeRef::Variable(v))
+            })
+            .chain(
+                module
+                    .statements
+                    .iter()
+                    .map(|s| (s.span.start, AstNodeRef::Statement(s))),
+            )
+            .chain(values.map(|e| (e.span.start, AstNodeRef::Expression(e))))
+            .chain(
+                module
+                    .modules
+                    .iter()
+                    .map(|m| (m.span.start, AstNodeRef::Module(m))),
+            )
+            .chain(
+                module
+                    .impls
+                    .iter()
+                    .map(|i| (i.span.start, AstNodeRef::Impl(i))),
+            )
+            .chain(
+                module
+                    .traits
+                    .iter()
+                    .map(|t| (t.span.start, AstNodeRef::Trait(t))),
+            )
+            .chain(
+                module
+                    .functions
+                    .iter()
+                    .map(|f| (f.span.start, AstNodeRef::Function(f))),
+            )
+            .collect();
+        // Stable, so items without spans keep `walk_module`'s order
+        items.sort_by_key(|(start, _)| *start);
+
+        for (_, item) in items {
+            match item {
+                AstNodeRef::Variable(variable) => self.visit_variable(variable),
+                AstNodeRef::Statement(statement) => self.visit_statement(statement),
+                AstNodeRef::Expression(expression) => self.visit_expression(expression),
+                AstNodeRef::Module(inner) => self.visit_module(inner),
+                AstNodeRef::Impl(implementation) => self.visit_impl(implementation),
+                AstNodeRef::Trait(trait_def) => self.visit_trait(trait_def),
+                AstNodeRef::Function(function) => self.visit_function(function),
+                AstNodeRef::TraitMethod(_) | AstNodeRef::Block(_) => {
+                    unreachable!("not a child of a module")
+                }
+            }
+        }
+    }
+
+    fn visit_impl(&mut self, implementation: &'a Impl) {
+        self.0.push(AstNodeRef::Impl(implementation));
+        walk_impl(self, implementation);
+    }
+
+    fn visit_trait(&mut self, trait_def: &'a Trait) {
+        self.0.push(AstNodeRef::Trait(trait_def));
+        walk_trait(self, trait_def);
+    }
+
+    fn visit_trait_method(&mut self, method: &'a TraitMethod) {
+        self.0.push(AstNodeRef::TraitMethod(method));
+        walk_trait_method(self, method);
+    }
+
+    fn visit_function(&mut self, function: &'a Function) {
+        self.0.push(AstNodeRef::Function(function));
+        walk_function(self, function);
+    }
+
+    fn visit_block(&mut self, block: &'a Block) {
+        self.0.push(AstNodeRef::Block(block));
+        walk_block(self, block);
+    }
+
+    fn visit_statement(&mut self, statement: &'a Statement) {
+        self.0.push(AstNodeRef::Statement(statement));
+        walk_statement(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &'a Expression) {
+        self.0.push(AstNodeRef::Expression(expression));
+        walk_expression(self, expression);
+    }
+
+    fn visit_variable(&mut self, variable: &'a Variable) {
+        self.0.push(AstNodeRef::Variable(variable));
+        walk_variable(self, variable);
+    }
+}
+
+/// Like `Visitor`, but with mutable access to each node.
+pub trait VisitorMut {
+    fn visit_module(&mut self, module: &mut Module) {
+        walk_module_mut(self, module);
+    }
+
+    fn visit_impl(&mut self, implementation: &mut Impl) {
+        walk_impl_mut(self, implementation);
+    }
+
+    fn visit_trait(&mut self, trait_def: &mut Trait) {
+        walk_trait_mut(self, trait_def);
+    }
+
+    fn visit_trait_method(&mut self, method: &mut TraitMethod) {
+        walk_trait_method_mut(self, method);
+    }
+
+    fn visit_function(&mut self, function: &mut Function) {
+        walk_function_mut(self, function);
+    }
+
+    fn visit_block(&mut self, block: &mut Block) {
+        walk_block_mut(self, block);
+    }
+
+    fn visit_statement(&mut self, statement: &mut Statement) {
+        walk_statement_mut(self, statement);
+    }
+
+    fn visit_expression(&mut self, expression: &mut Expression) {
+        walk_expression_mut(self, expression);
+    }
+
+    fn visit_variable(&mut self, variable: &mut Variable) {
+        walk_variable_mut(self, variable);
+    }
+}
+
+pub fn walk_ast_mut<V: VisitorMut + ?Sized>(visitor: &mut V, ast: &mut Ast) {
+    if let Some(module) = &mut ast.root {
+        visitor.visit_module(module);
+    }
+}
+
+pub fn walk_module_mut<V: VisitorMut + ?Sized>(visitor: &mut V, module: &mut Module) {
+    for variable in &mut module.variables {
+        visitor.visit_variable(variable);
+    }
+    for statement in &mut module.statements {
+        visitor.visit_statement(statement);
+    }
+    for value in module
+        .enums
+        .iter_mut()
+        .flat_map(|e| &mut e.variants)
+        .filter_map(|v| v.value.as_mut())
+    {
+        visitor.visit_expression(value);
+    }
+    for inner in &mut module.modules {
+        visitor.visit_module(inner);
+    }
+    for implementation in &mut module.impls {
+        visitor.visit_impl(implementation);
+    }
+    for trait_def in &mut module.traits {
+        visitor.visit_trait(trait_def);
+    }
+    for function in &mut module.functions {
+        visitor.visit_function(function);
+    }
+}
+
+pub fn walk_impl_mut<V: VisitorMut + ?Sized>(visitor: &mut V, implementation: &mut Impl) {
+    for function in &mut implementation.functions {
+        visitor.visit_function(function);
+    }
+}
+
+pub fn walk_trait_mut<V: VisitorMut + ?Sized>(visitor: &mut V, trait_def: &mut Trait) {
+    for method in &mut trait_def.methods {
+        visitor.visit_trait_method(method);
+    }
+}
+
+pub fn walk_trait_method_mut<V: VisitorMut + ?Sized>(visitor: &mut V, method: &mut TraitMethod) {
+    if let Some(block) = &mut method.default {
+        visitor.visit_block(block);
+    }
+}
+
+pub fn walk_function_mut<V: VisitorMut + ?Sized>(visitor: &mut V, function: &mut Function) {
+    visitor.visit_block(&mut function.block);
+}
+
+pub fn walk_block_mut<V: VisitorMut + ?Sized>(visitor: &mut V, block: &mut Block) {
+    for statement in &mut block.statements {
+        visitor.visit_statement(statement);
+    }
+}
+
+pub fn walk_statement_mut<V: VisitorMut + ?Sized>(visitor: &mut V, statement: &mut Statement) {
+    match &mut statement.kind {
+        StatementKind::Expression(expression) | StatementKind::Destructure { expression, .. } => {
+            visitor.visit_expression(expression)
+        }
+        StatementKind::Block(block) => visitor.visit_block(block),
+        StatementKind::Return(value) => {
+            if let Some(value) = value {
+                visitor.visit_expression(value);
+            }
+        }
+        StatementKind::Yield(value) => visitor.visit_expression(value),
+        StatementKind::Assert { condition, message } => {
+            visitor.visit_expression(condition);
+            if let Some(message) = message {
+                visitor.visit_expression(message);
+            }
+        }
+    }
+}
+
+pub fn walk_variable_mut<V: VisitorMut + ?Sized>(visitor: &mut V, variable: &mut Variable) {
+    if let Some(initializer) = &mut variable.expression {
+        visitor.visit_expression(initializer);
+    }
+}
+
+pub fn walk_expression_mut<V: VisitorMut + ?Sized>(visitor: &mut V, expression: &mut Expression) {
+    match &mut expression.kind {
+        ExpressionKind::Literal(_)
+        | ExpressionKind::LiteralNull
+        | ExpressionKind::Path(_)
+        | ExpressionKind::SelfRef => {}
+        ExpressionKind::Group { inner: operand }
+        | ExpressionKind::Unary { operand, .. }
+        | ExpressionKind::FieldAccess {
+            object: operand, ..
+        }
+        | ExpressionKind::OptionalAccess {
+            object: operand, ..
+        }
+        | ExpressionKind::Try(operand) => visitor.visit_expression(operand),
+        ExpressionKind::Tuple(elements) => {
+            for element in elements {
+                visitor.visit_expression(element);
+            }
+        }
+        ExpressionKind::Binary { left, right, .. }
+        | ExpressionKind::And { left, right }
+        | ExpressionKind::Or { left, right }
+        | ExpressionKind::Index {
+            object: left,
+            index: right,
+        } => {
+            visitor.visit_expression(left);
+            visitor.visit_expression(right);
+        }
+        ExpressionKind::Variable(variable) => visitor.visit_variable(variable),
+        ExpressionKind::Call { callee, arguments } => {
+            visitor.visit_expression(callee);
+            for argument in arguments {
+                visitor.visit_expression(argument);
+            }
+        }
+        ExpressionKind::Slice { object, range } => {
+            visitor.visit_expression(object);
+            for bound in [&mut range.start, &mut range.end].into_iter().flatten() {
+                visitor.visit_expression(bound);
+            }
+        }
+        ExpressionKind::Ternary {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            visitor.visit_expression(condition);
+            visitor.visit_expression(then_branch);
+            visitor.visit_expression(else_branch);
+        }
+        ExpressionKind::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            match condition {
+                IfCondition::Expr(condition) => visitor.visit_expression(condition),
+                IfCondition::Binding(variable) => visitor.visit_variable(variable),
+            }
+            visitor.visit_block(then_branch);
+            if let Some(else_branch) = else_branch {
+                visitor.visit_block(else_branch);
+            }
+        }
+        ExpressionKind::Match { scrutinee, arms } => {
+            visitor.visit_expression(scrutinee);
+            for arm in arms {
+                visitor.visit_expression(&mut arm.body);
+            }
+        }
+        ExpressionKind::StructLiteral { base, fields, .. } => {
+            if let Some(base) = base {
+                visitor.visit_expression(base);
+            }
+            for field in fields {
+                visitor.visit_expression(&mut field.value);
+            }
+        }
+        ExpressionKind::Record(fields) => {
+            for field in fields {
+                visitor.visit_expression(&mut field.value);
+            }
+        }
+    }
+}
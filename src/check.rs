@@ -0,0 +1,22 @@
+/// `aeon check <file>`
+pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(path) = args.first() else {
+        return Err("usage: aeon check <file>".into());
+    };
+
+    let source = std::fs::read_to_string(path)?;
+    let errors = aeon::check_source(&source);
+    for error in &errors {
+        match (error.line(), error.column()) {
+            (Some(line), Some(column)) => eprintln!("{}:{}:{}: {}", path, line, column, error),
+            (Some(line), None) => eprintln!("{}:{}: {}", path, line, error),
+            _ => eprintln!("{}: {}", path, error),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} error(s) in {}", errors.len(), path).into())
+    }
+}
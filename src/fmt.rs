@@ -0,0 +1,33 @@
+use std::io::BufReader;
+
+use aeon::CompileError;
+use lex::lexer::{Lexer, LexerConfig};
+use parser::{Parser, ast::PrettyPrinter};
+
+/// Formats Aeon source into its canonical layout, keeping comments.
+pub fn format_source(source: &str) -> Result<String, CompileError> {
+    let reader = BufReader::new(source.as_bytes());
+    let (tokens, spans, comments) = Lexer::lex_with_comments(reader, LexerConfig::default())?;
+    let ast = Parser::parse_with_spans(&tokens, &spans)?;
+    Ok(PrettyPrinter::with_comments(comments).print(&ast))
+}
+
+/// `aeon fmt [--write] <file>`
+pub fn run(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let write = args.iter().any(|arg| arg == "--write");
+    let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        return Err("usage: aeon fmt [--write] <file>".into());
+    };
+
+    let source = std::fs::read_to_string(path)?;
+    let formatted = format_source(&source)?;
+    if write {
+        if formatted != source {
+            std::fs::write(path, formatted)?;
+        }
+    } else {
+        print!("{}", formatted);
+    }
+
+    Ok(())
+}
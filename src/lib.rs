@@ -0,0 +1,261 @@
+use std::{error::Error, fmt, io::BufReader, path::Path};
+
+use lex::{
+    lexer::{Lexer, LexerConfig, LexerError},
+    span::Span,
+    token::Token,
+};
+use parser::{Parser, ast::Ast, parser_error::ParserError};
+
+/// Any error produced while turning source code into an AST. Both
+/// `LexerError` and `ParserError` convert into it, so `?` works across
+/// lexing and parsing in one function.
+#[derive(Debug)]
+pub enum CompileError {
+    /// The source file couldn't be read
+    Io(std::io::Error),
+    Lexer(LexerError),
+    Parser(ParserError),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompileError::Io(err) => write!(f, "{}", err),
+            CompileError::Lexer(err) => write!(f, "{}", err),
+            CompileError::Parser(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl CompileError {
+    /// The 1-based line the error was found on, when known.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            CompileError::Io(_) => None,
+            CompileError::Lexer(err) => err.line(),
+            CompileError::Parser(err) => err.line(),
+        }
+    }
+
+    /// The 1-based column the error was found at, when known.
+    pub fn column(&self) -> Option<usize> {
+        match self {
+            CompileError::Io(_) => None,
+            CompileError::Lexer(err) => err.column(),
+            CompileError::Parser(err) => err.column(),
+        }
+    }
+}
+
+impl Error for CompileError {}
+
+impl From<std::io::Error> for CompileError {
+    fn from(err: std::io::Error) -> Self {
+        CompileError::Io(err)
+    }
+}
+
+impl From<LexerError> for CompileError {
+    fn from(err: LexerError) -> Self {
+        CompileError::Lexer(err)
+    }
+}
+
+impl From<ParserError> for CompileError {
+    fn from(err: ParserError) -> Self {
+        CompileError::Parser(err)
+    }
+}
+
+/// A parsed source file along with everything it was parsed from. Not to
+/// be confused with `parser::ast::Program`, which gathers several files.
+#[derive(Debug, Clone)]
+pub struct Program {
+    ast: Ast,
+    source: String,
+    filename: Option<String>,
+    tokens: Vec<Token>,
+    /// `spans[i]` is the span of `tokens[i]`
+    spans: Vec<Span>,
+}
+
+impl Program {
+    pub fn ast(&self) -> &Ast {
+        &self.ast
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The file the source was read from, if it was given a name.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    pub fn into_ast(self) -> Ast {
+        self.ast
+    }
+}
+
+/// Lexes and parses `source` in one go.
+pub fn parse_source(source: &str) -> Result<Program, CompileError> {
+    parse(source, None)
+}
+
+/// Like `parse_source`, recording that `source` was read from `filename`.
+pub fn parse_named_source(filename: &str, source: &str) -> Result<Program, CompileError> {
+    parse(source, Some(filename.to_string()))
+}
+
+/// Reads the file at `path` and parses it, naming the program after `path`.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Program, CompileError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path)?;
+    parse(&source, Some(path.display().to_string()))
+}
+
+fn parse(source: &str, filename: Option<String>) -> Result<Program, CompileError> {
+    let reader = BufReader::new(source.as_bytes());
+    let (tokens, spans) = Lexer::lex_with_spans(reader, LexerConfig::default())?;
+    let ast = Parser::parse_with_spans(&tokens, &spans)?;
+    Ok(Program {
+        ast,
+        source: source.to_string(),
+        filename,
+        tokens,
+        spans,
+    })
+}
+
+/// Lexes and parses `source`, reporting every statement that fails to parse
+/// rather than stopping at the first. Lexing still stops at its first error.
+pub fn check_source(source: &str) -> Vec<CompileError> {
+    let reader = BufReader::new(source.as_bytes());
+    match Lexer::lex_with_spans(reader, LexerConfig::default()) {
+        Ok((tokens, spans)) => {
+            let (_, errors) = Parser::default().parse_recovering(&tokens, &spans);
+            errors.into_iter().map(CompileError::from).collect()
+        }
+        Err(err) => vec![err.into()],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hello_world() {
+        let source = include_str!("../examples/hello_world.aeon");
+        let program = parse_source(source).expect("failed to parse hello world");
+
+        let module = program.into_ast().root.unwrap();
+        assert_eq!(module.name, "Main");
+        assert_eq!(module.imports.len(), 1);
+        assert_eq!(module.functions[0].name, "main");
+    }
+
+    #[test]
+    fn test_program_accessors() {
+        let source = include_str!("../examples/hello_world.aeon");
+        let program =
+            parse_named_source("hello_world.aeon", source).expect("failed to parse hello world");
+
+        assert_eq!(program.source(), source);
+        assert_eq!(program.filename(), Some("hello_world.aeon"));
+        assert_eq!(
+            program.tokens()[0],
+            Token::Keyword(lex::token::Keyword::Module)
+        );
+        assert_eq!(program.tokens().len(), program.spans().len());
+        assert_eq!(program.spans()[0].start.line, 1);
+
+        let module = program.ast().root.as_ref().unwrap();
+        assert_eq!(module.name, "Main");
+        assert_eq!(module.decl_span, program.spans()[0]);
+        assert_eq!(parse_source(source).unwrap().filename(), None);
+    }
+
+    #[test]
+    fn test_lexer_error() {
+        let result = parse_source("module Main\nx := \"unterminated");
+        assert!(matches!(
+            result,
+            Err(CompileError::Lexer(LexerError::UnexpectedEndOfInput))
+        ));
+    }
+
+    fn lex_and_parse(source: &str) -> Result<Ast, CompileError> {
+        let tokens = Lexer::lex(BufReader::new(source.as_bytes()))?;
+        Ok(Parser::parse(&tokens)?)
+    }
+
+    #[test]
+    fn test_errors_convert_into_compile_error() {
+        assert!(matches!(
+            lex_and_parse("x := 0x\n"),
+            Err(CompileError::Lexer(LexerError::InvalidNumber { .. }))
+        ));
+        assert!(matches!(
+            lex_and_parse("fn main() {\n}\n"),
+            Err(CompileError::Parser(_))
+        ));
+        assert!(lex_and_parse("module Main\n").is_ok());
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let program = parse_file("examples/hello_world.aeon").expect("failed to parse hello world");
+        assert_eq!(program.filename(), Some("examples/hello_world.aeon"));
+
+        let error = parse_file("examples/missing.aeon").unwrap_err();
+        assert!(matches!(error, CompileError::Io(_)));
+        assert_eq!(error.line(), None);
+    }
+
+    #[test]
+    fn test_check_source_reports_every_error() {
+        let source =
+            "module Main\nfn main() {\n    x := )\n    y := 1\n}\n\nfn other() {\n    z = 2\n}\n";
+        let errors = check_source(source);
+
+        let lines: Vec<_> = errors.iter().map(CompileError::line).collect();
+        assert_eq!(lines, vec![Some(3), Some(8)]);
+        let columns: Vec<_> = errors.iter().map(CompileError::column).collect();
+        assert_eq!(columns, vec![Some(10), Some(5)]);
+        assert!(check_source(include_str!("../examples/hello_world.aeon")).is_empty());
+    }
+
+    #[test]
+    fn test_check_source_rejects_unbalanced_module_braces() {
+        assert_eq!(check_source("module Foo {\nfn f() {\n}\n").len(), 1);
+        assert_eq!(
+            check_source("module Foo\nfn f() {\n}\n}\nfn g() {\n    x = )\n}\n").len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parser_error() {
+        let error = parse_source("fn main() {\n}\n").unwrap_err();
+        assert!(matches!(
+            &error,
+            CompileError::Parser(err) if err.kind() == &ParserError::ModuleNotFound
+        ));
+        assert_eq!(error.line(), Some(1));
+        assert_eq!(
+            error.to_string(),
+            "File must begin with a module declaration!"
+        );
+    }
+}
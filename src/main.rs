@@ -1,15 +1,15 @@
-use std::io::BufReader;
-
-use lex::lexer::Lexer;
-use parser::Parser;
+mod check;
+mod fmt;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let file = std::fs::File::open("hello_world.aeon")?;
-    let mut reader = BufReader::new(file);
-
-    let tokens = Lexer::lex(&mut reader)?;
-
-    let _ast = Parser::parse(&tokens)?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first() {
+        Some((command, rest)) if command == "fmt" => return fmt::run(rest),
+        Some((command, rest)) if command == "check" => return check::run(rest),
+        _ => {}
+    }
+
+    let _program = aeon::parse_file("hello_world.aeon")?;
 
     Ok(())
 }
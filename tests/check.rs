@@ -0,0 +1,62 @@
+use std::process::{Command, Output};
+
+fn check(name: &str, source: &str) -> Output {
+    let path = std::env::temp_dir().join(format!("aeon-check-{}-{}", std::process::id(), name));
+    std::fs::write(&path, source).expect("failed to write source");
+    let output = Command::new(env!("CARGO_BIN_EXE_aeon"))
+        .arg("check")
+        .arg(&path)
+        .output()
+        .expect("failed to run aeon check");
+    std::fs::remove_file(&path).ok();
+    output
+}
+
+#[test]
+fn test_check_reports_every_error() {
+    let output = check(
+        "two-errors.aeon",
+        "module Main\n\nfn main() {\n    x := )\n    y := 1\n}\n\nfn other() {\n    z = 2\n}\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(
+        stderr.contains("two-errors.aeon:4:10: expected `expression`, found `)`."),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("two-errors.aeon:9:5: Cannot assign to undeclared variable `z`!"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_check_valid_source() {
+    let output = check("valid.aeon", "module Main\n\nfn main() {\n    x := 1\n}\n");
+
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn test_check_names_the_function_of_an_error() {
+    let output = check(
+        "in-function.aeon",
+        "module Main\n\nfn f() {\n    x := )\n}\n",
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+
+    assert!(!output.status.success());
+    assert!(
+        stderr.contains(
+            "in-function.aeon:4:10: expected `expression`, found `)`. (while parsing function `f` in module `Main`)"
+        ),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,70 @@
+use std::process::Command;
+
+fn fmt(path: &std::path::Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_aeon"))
+        .arg("fmt")
+        .arg(path)
+        .output()
+        .expect("failed to run aeon fmt");
+    assert!(
+        output.status.success(),
+        "aeon fmt {} failed: {}",
+        path.display(),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("formatter output is not UTF-8")
+}
+
+fn assert_idempotent(example: &str) {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("examples")
+        .join(example);
+    let once = fmt(&path);
+
+    let formatted =
+        std::env::temp_dir().join(format!("aeon-fmt-{}-{}", std::process::id(), example));
+    std::fs::write(&formatted, &once).expect("failed to write formatted source");
+    let twice = fmt(&formatted);
+    std::fs::remove_file(&formatted).ok();
+
+    assert_eq!(once, twice);
+}
+
+#[test]
+fn test_fmt_hello_world_is_idempotent() {
+    assert_idempotent("hello_world.aeon");
+}
+
+#[test]
+fn test_fmt_math_is_idempotent() {
+    assert_idempotent("math.aeon");
+}
+
+#[test]
+fn test_fmt_modules_is_idempotent() {
+    assert_idempotent("modules.aeon");
+}
+
+#[test]
+fn test_fmt_write_in_place() {
+    let path = std::env::temp_dir().join(format!("aeon-fmt-write-{}.aeon", std::process::id()));
+    std::fs::write(
+        &path,
+        "module Main\n\n\nfn main() {\n  a := 5 + 1 // six\n}\n",
+    )
+    .unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_aeon"))
+        .args(["fmt", "--write"])
+        .arg(&path)
+        .status()
+        .expect("failed to run aeon fmt");
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).ok();
+
+    assert!(status.success());
+    assert_eq!(
+        written,
+        "module Main\n\nfn main() {\n    a := 5 + 1 // six\n}\n"
+    );
+}